@@ -0,0 +1,187 @@
+// Benchmarks for parsing, on files built in memory with `WaveFixture`.
+//
+//     cargo bench --bench parse --features testing
+//     cargo bench --bench parse --features testing -- --save-baseline before
+//     cargo bench --bench parse --features testing -- --baseline before
+//
+// A baseline is just the timings from a run, saved under `target/wave-bench`. Comparing against
+// one prints how much each benchmark changed, so a change that's meant to be faster can show it.
+// Anything else on the command line picks the benchmarks whose names contain it.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::hint::black_box;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use wave_file_parser::testing::WaveFixture;
+use wave_file_parser::WaveFileParser;
+
+// How long to keep running each benchmark for
+const MEASUREMENT_TIME: Duration = Duration::from_secs(1);
+// Changes smaller than this are noise
+const NOISE_THRESHOLD: f64 = 0.05;
+
+struct Bencher {
+    filter: Vec<String>,
+    save_baseline: Option<String>,
+    baseline: BTreeMap<String, f64>,
+    results: BTreeMap<String, f64>,
+}
+
+impl Bencher {
+    fn from_args() -> Bencher {
+        let mut bencher = Bencher {
+            filter: vec![],
+            save_baseline: None,
+            baseline: BTreeMap::new(),
+            results: BTreeMap::new(),
+        };
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--save-baseline" => bencher.save_baseline = args.next(),
+                "--baseline" => {
+                    let name = args.next().expect("--baseline needs a name");
+                    bencher.baseline = load_baseline(&name);
+                }
+                // `cargo bench` passes this along to every bench target
+                "--bench" => {}
+                _ => bencher.filter.push(arg),
+            }
+        }
+
+        bencher
+    }
+
+    // Runs `f` over and over, and reports the average time for one run.
+    // `bytes` is how much input one run goes through, for the throughput. 0 leaves it out.
+    fn bench<F: FnMut()>(&mut self, name: &str, bytes: usize, mut f: F) {
+        if !self.filter.is_empty() && !self.filter.iter().any(|filter| name.contains(filter.as_str())) {
+            return;
+        }
+
+        // Warm up, and find out roughly how long a run takes
+        let start = Instant::now();
+        f();
+        let once = start.elapsed().max(Duration::from_nanos(1));
+        let iterations = (MEASUREMENT_TIME.as_nanos() / once.as_nanos()).clamp(1, 1_000_000) as u32;
+
+        let start = Instant::now();
+        for _ in 0..iterations {
+            f();
+        }
+        let nanos = start.elapsed().as_nanos() as f64 / iterations as f64;
+
+        let mut line = format!("{:<40} {:>14}", name, format_nanos(nanos));
+        if bytes > 0 {
+            let throughput = bytes as f64 / (nanos / 1e9) / (1024.0 * 1024.0);
+            line.push_str(&format!(" {:>10.1} MiB/s", throughput));
+        } else {
+            line.push_str(&" ".repeat(17));
+        }
+        if let Some(&before) = self.baseline.get(name) {
+            let change = (nanos - before) / before;
+            let verdict = match change {
+                c if c > NOISE_THRESHOLD => "slower",
+                c if c < -NOISE_THRESHOLD => "faster",
+                _ => "no change",
+            };
+            line.push_str(&format!("  {:>+7.1}% ({})", change * 100.0, verdict));
+        }
+        println!("{}", line);
+
+        self.results.insert(name.to_string(), nanos);
+    }
+
+    fn finish(self) {
+        if let Some(name) = self.save_baseline {
+            let path = baseline_path(&name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+            let contents: String = self.results.iter().map(|(name, nanos)| format!("{}\t{}\n", name, nanos)).collect();
+            fs::write(&path, contents).unwrap();
+            println!("saved baseline {} to {}", name, path.display());
+        }
+    }
+}
+
+fn baseline_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target").join("wave-bench").join(format!("{}.tsv", name))
+}
+
+// One "<benchmark>\t<nanoseconds>" per line
+fn load_baseline(name: &str) -> BTreeMap<String, f64> {
+    let contents = fs::read_to_string(baseline_path(name)).unwrap_or_else(|e| panic!("can't read baseline {}: {}", name, e));
+
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (name, nanos) = line.split_once('\t')?;
+            Some((name.to_string(), nanos.parse().ok()?))
+        })
+        .collect()
+}
+
+fn format_nanos(nanos: f64) -> String {
+    match nanos {
+        n if n < 1e3 => format!("{:.1} ns", n),
+        n if n < 1e6 => format!("{:.1} µs", n / 1e3),
+        n if n < 1e9 => format!("{:.1} ms", n / 1e6),
+        n => format!("{:.2} s", n / 1e9),
+    }
+}
+
+// Ten seconds of audio
+fn ten_seconds(channels: u16, bits_per_sample: u16) -> Vec<u8> {
+    WaveFixture::new(channels, bits_per_sample, 44_100).duration(Duration::from_secs(10)).to_bytes()
+}
+
+// A short file with a lot of chunks around the data, like one that's been through a few editors
+fn metadata_heavy() -> Vec<u8> {
+    let mut fixture = WaveFixture::new(2, 16, 44_100).num_frames(1000);
+    for i in 0..200 {
+        fixture = fixture.info(*b"ICMT", &format!("comment number {}", i));
+    }
+    for i in 0..50 {
+        fixture = fixture.chunk(*b"junk", vec![i as u8; 4096]);
+    }
+
+    fixture.to_bytes()
+}
+
+fn main() {
+    let mut bencher = Bencher::from_args();
+
+    let stereo = ten_seconds(2, 16);
+    // The data chunk is skipped with a seek, so there's no throughput to speak of
+    bencher.bench("header/stereo_16", 0, || {
+        black_box(WaveFileParser::header_from_reader(Cursor::new(&stereo)).unwrap());
+    });
+
+    for &(channels, bits_per_sample) in &[(1, 8), (2, 16), (2, 24), (2, 32), (6, 16), (6, 24)] {
+        let bytes = ten_seconds(channels, bits_per_sample);
+
+        let name = format!("decode/{}ch_{}bit", channels, bits_per_sample);
+        bencher.bench(&name, bytes.len(), || {
+            black_box(WaveFileParser::parse(bytes.clone()).unwrap());
+        });
+
+        let name = format!("slice_to_f32/{}ch_{}bit", channels, bits_per_sample);
+        bencher.bench(&name, bytes.len(), || {
+            black_box(WaveFileParser::parse_slice(&bytes).unwrap().channels_f32().unwrap());
+        });
+    }
+
+    let metadata = metadata_heavy();
+    bencher.bench("metadata/parse", metadata.len(), || {
+        black_box(WaveFileParser::parse(metadata.clone()).unwrap());
+    });
+    bencher.bench("metadata/header", 0, || {
+        black_box(WaveFileParser::header_from_reader(Cursor::new(&metadata)).unwrap());
+    });
+
+    bencher.finish();
+}
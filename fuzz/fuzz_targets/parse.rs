@@ -0,0 +1,23 @@
+#![no_main]
+
+// Run with `cargo fuzz run parse`.
+// Whatever the bytes are, parsing them has to come back with a WaveFile or a WaveError.
+// The limits keep a header that claims billions of samples from running the fuzzer out of memory.
+
+use libfuzzer_sys::fuzz_target;
+use wave_file_parser::{ParserOptions, WaveFileParser};
+
+fuzz_target!(|data: &[u8]| {
+    let options = ParserOptions::new().max_sample_memory(1 << 26).max_chunks(1 << 16);
+
+    if let Ok(wave_file) = WaveFileParser::parse_with_options(data.to_vec(), options.clone()) {
+        let _ = wave_file.validate();
+        let _ = wave_file.markers();
+    }
+
+    let _ = WaveFileParser::parse_lossless_with_options(data.to_vec(), options.clone());
+    let _ = WaveFileParser::parse_repair_with_options(data.to_vec(), options.clone());
+
+    let lenient = options.tolerate_truncated_data(true).allow_unknown_formats(true);
+    let _ = WaveFileParser::parse_with_options(data.to_vec(), lenient);
+});
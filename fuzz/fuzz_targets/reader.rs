@@ -0,0 +1,25 @@
+#![no_main]
+
+// Run with `cargo fuzz run reader`.
+// The lazy paths: reading just the header, walking the chunks, and seeking to samples.
+
+use std::io::Cursor;
+
+use libfuzzer_sys::fuzz_target;
+use wave_file_parser::riff::ChunkIterator;
+use wave_file_parser::{WaveFileParser, WaveReader};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = WaveFileParser::parse_header(data.to_vec());
+
+    if let Ok(chunks) = ChunkIterator::new(Cursor::new(data)) {
+        for chunk in chunks {
+            let _ = chunk;
+        }
+    }
+
+    if let Ok(mut reader) = WaveReader::new(Cursor::new(data)) {
+        let num_frames = reader.num_frames();
+        let _ = reader.read_samples(0..num_frames.min(1 << 16));
+    }
+});
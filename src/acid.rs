@@ -0,0 +1,86 @@
+// The `acid` chunk, written by ACID, Ableton Live and most loop libraries.
+// It says how a loop fits into a song: its tempo, how many beats long it is and its key.
+
+use crate::error::WaveError;
+
+const BYTES_ACID: usize = 24;
+
+const FLAG_ONE_SHOT: u32 = 0x01;
+const FLAG_ROOT_NOTE_SET: u32 = 0x02;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AcidChunk {
+    // One shot, root note set, stretch, disk based and so on
+    pub flags: u32,
+    // The MIDI note the loop is in. Only meaningful when the root note flag is set.
+    pub root_note: u16,
+    pub num_beats: u32,
+    pub meter_denominator: u16,
+    pub meter_numerator: u16,
+    pub tempo: f32,
+}
+
+impl AcidChunk {
+    pub fn tempo_bpm(&self) -> f32 {
+        self.tempo
+    }
+
+    pub fn beats(&self) -> u32 {
+        self.num_beats
+    }
+
+    // A one shot is played once as it is instead of being looped to the tempo
+    pub fn is_one_shot(&self) -> bool {
+        self.flags & FLAG_ONE_SHOT != 0
+    }
+
+    pub fn root_note(&self) -> Option<u16> {
+        if self.flags & FLAG_ROOT_NOTE_SET != 0 {
+            Some(self.root_note)
+        } else {
+            None
+        }
+    }
+}
+
+pub(crate) fn read_acid(contents: &[u8]) -> Result<AcidChunk, WaveError> {
+    if contents.len() < BYTES_ACID {
+        return Err(WaveError::InvalidChunkSize(contents.len() as u32));
+    }
+
+    let u16_at = |i: usize| u16::from_le_bytes([contents[i], contents[i + 1]]);
+    let u32_at = |i: usize| u32::from_le_bytes([contents[i], contents[i + 1], contents[i + 2], contents[i + 3]]);
+
+    // There are 6 bytes of who-knows-what after the root note
+    Ok(AcidChunk {
+        flags: u32_at(0),
+        root_note: u16_at(4),
+        num_beats: u32_at(12),
+        meter_denominator: u16_at(16),
+        meter_numerator: u16_at(18),
+        tempo: f32::from_bits(u32_at(20)),
+    })
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::read_acid;
+
+    #[test]
+    fn test_reading_acid() {
+        let mut contents = vec![0; 24];
+        contents[0..4].copy_from_slice(&0x02u32.to_le_bytes());
+        contents[4..6].copy_from_slice(&57u16.to_le_bytes());
+        contents[12..16].copy_from_slice(&8u32.to_le_bytes());
+        contents[16..18].copy_from_slice(&4u16.to_le_bytes());
+        contents[18..20].copy_from_slice(&4u16.to_le_bytes());
+        contents[20..24].copy_from_slice(&120.5f32.to_le_bytes());
+
+        let acid = read_acid(&contents).unwrap();
+
+        assert_eq!(120.5, acid.tempo_bpm());
+        assert_eq!(8, acid.beats());
+        assert_eq!(Some(57), acid.root_note());
+        assert!(!acid.is_one_shot());
+    }
+}
@@ -0,0 +1,92 @@
+// Metadata for the Audio Definition Model (ADM), as carried by BW64 broadcast files.
+// The `chna` chunk says which audio track (channel) belongs to which ADM object, and the
+// `axml` chunk holds the ADM description itself as XML.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::error::WaveError;
+
+// Bytes in each audioID record of the chna chunk
+const BYTES_AUDIO_ID: usize = 40;
+
+// One entry of the `chna` chunk, tying a track to the ADM elements that describe it.
+// The references are ADM ids like "ATU_00000001" or "AT_00031001_01".
+#[derive(Clone, Debug, PartialEq)]
+pub struct AudioId {
+    // The channel, counting from 1. 0 means the entry isn't used.
+    pub track_index: u16,
+    // audioTrackUID
+    pub uid: String,
+    // audioTrackFormatID or audioChannelFormatID
+    pub track_ref: String,
+    // audioPackFormatID
+    pub pack_ref: String,
+}
+
+// Reads the contents of a chna chunk: numTracks, numUIDs and then numUIDs audioID records.
+// Files often reserve room for more records than they use, so that ones past numUIDs are ignored.
+pub(crate) fn read_chna(contents: &[u8]) -> Result<Vec<AudioId>, WaveError> {
+    if contents.len() < 4 {
+        return Err(WaveError::InvalidChunkSize(contents.len() as u32));
+    }
+
+    let num_uids = u16::from_le_bytes([contents[2], contents[3]]) as usize;
+    let records = &contents[4..];
+    if records.len() < num_uids * BYTES_AUDIO_ID {
+        return Err(WaveError::UnexpectedEof);
+    }
+
+    Ok(records
+        .chunks_exact(BYTES_AUDIO_ID)
+        .take(num_uids)
+        .map(|record| AudioId {
+            track_index: u16::from_le_bytes([record[0], record[1]]),
+            uid: ascii_field(&record[2..14]),
+            track_ref: ascii_field(&record[14..28]),
+            pack_ref: ascii_field(&record[28..39]),
+        })
+        .collect())
+}
+
+// The fixed size text fields are null padded when the id is shorter than the field
+fn ascii_field(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).trim_end_matches('\0').to_string()
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::{read_chna, AudioId};
+    use crate::error::WaveError;
+
+    fn audio_id_record(track_index: u16, uid: &str, track_ref: &str, pack_ref: &str) -> Vec<u8> {
+        let mut record = track_index.to_le_bytes().to_vec();
+        record.extend_from_slice(uid.as_bytes());
+        record.extend_from_slice(track_ref.as_bytes());
+        record.extend_from_slice(pack_ref.as_bytes());
+        record.push(0);
+
+        record
+    }
+
+    #[test]
+    fn test_reading_chna_records() {
+        let mut contents = vec![1, 0, 1, 0];
+        contents.extend(audio_id_record(1, "ATU_00000001", "AT_00010001_01", "AP_00010001"));
+        // Room for a record that isn't used
+        contents.extend_from_slice(&[0; 40]);
+
+        let expected = AudioId {
+            track_index: 1,
+            uid: "ATU_00000001".to_string(),
+            track_ref: "AT_00010001_01".to_string(),
+            pack_ref: "AP_00010001".to_string(),
+        };
+        assert_eq!(vec![expected], read_chna(&contents).unwrap());
+    }
+
+    #[test]
+    fn test_reading_truncated_chna_returns_error() {
+        assert_eq!(Err(WaveError::UnexpectedEof), read_chna(&[1, 0, 1, 0, 1, 0]));
+    }
+}
@@ -0,0 +1,205 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::error::WaveError;
+use crate::parser::{fourcc, WaveFileParser};
+use crate::wave_file::{Sample, WaveFormatCategory};
+use crate::writer::{write_all, write_sample};
+
+// Adds frames onto the end of the samples of a file that's already there, like a recording that
+// picks up where the last one stopped. The frames go in the last data chunk, in the file's format.
+// Any chunks after the data are held on to and written back after the new frames by `finalize`,
+// along with the new sizes.
+//
+// Like `StreamingWaveWriter`, the sizes aren't right until `finalize`. An appender that is
+// dropped without being finalized also loses the chunks that came after the data.
+pub struct WaveAppender<F: Read + Write + Seek> {
+    file: F,
+    wave_format: WaveFormatCategory,
+    num_channels: usize,
+    bits_per_sample: u16,
+    block_align: u64,
+    riff_size: u32,
+    // Where the data chunk's size field is
+    data_size_offset: u64,
+    // The size of the data when the file was opened, and now
+    original_data_size: u64,
+    data_size: u64,
+    // Everything after the data chunk
+    trailer: Vec<u8>,
+    // Reused for each frame so we don't allocate every time
+    frame: Vec<u8>,
+}
+
+impl WaveAppender<File> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<WaveAppender<File>, WaveError> {
+        let file = OpenOptions::new().read(true).write(true).open(path).map_err(|e| WaveError::Io(e.kind()))?;
+
+        WaveAppender::new(file)
+    }
+}
+
+impl<F: Read + Write + Seek> WaveAppender<F> {
+    // Only little-endian RIFF files in PCM or float can be added to, and the data has to
+    // hold a whole number of frames
+    pub fn new(mut file: F) -> Result<WaveAppender<F>, WaveError> {
+        let info = WaveFileParser::header_from_reader(&mut file)?;
+        let wave_file = &info.wave_file;
+
+        let mut header = [0; 8];
+        file.seek(SeekFrom::Start(0)).map_err(|e| WaveError::Io(e.kind()))?;
+        file.read_exact(&mut header).map_err(|e| WaveError::Io(e.kind()))?;
+        if &header[..4] != b"RIFF" {
+            return Err(WaveError::UnexpectedChunk {
+                expected: *b"RIFF",
+                found: fourcc(&header),
+            });
+        }
+
+        // Compressed formats are decoded into PCM, but they can't be written
+        if WaveFormatCategory::from_tag(wave_file.format_tag) != Some(wave_file.wave_format) {
+            return Err(WaveError::UnsupportedFormat(wave_file.format_tag));
+        }
+
+        let (data_start, data_size) = match info.data_chunks.last() {
+            Some(&data_chunk) => data_chunk,
+            None => return Err(WaveError::MissingChunk(*b"data")),
+        };
+        let block_align = wave_file.block_align as u64;
+        if block_align == 0 || data_size % block_align != 0 {
+            return Err(WaveError::InvalidChunkSize(data_size.min(u32::MAX as u64) as u32));
+        }
+
+        // Hold on to whatever comes after the data, since the new frames go over it
+        let mut trailer = vec![];
+        file.seek(SeekFrom::Start(data_start + data_size + data_size % 2)).map_err(|e| WaveError::Io(e.kind()))?;
+        file.read_to_end(&mut trailer).map_err(|e| WaveError::Io(e.kind()))?;
+        file.seek(SeekFrom::Start(data_start + data_size)).map_err(|e| WaveError::Io(e.kind()))?;
+
+        Ok(WaveAppender {
+            file,
+            wave_format: wave_file.wave_format,
+            num_channels: wave_file.channels.len(),
+            bits_per_sample: wave_file.bits_per_sample,
+            block_align,
+            riff_size: u32::from_le_bytes([header[4], header[5], header[6], header[7]]),
+            data_size_offset: data_start - 4,
+            original_data_size: data_size,
+            data_size,
+            trailer,
+            frame: vec![],
+        })
+    }
+
+    // How many frames there are, counting the ones added so far
+    pub fn num_frames(&self) -> u64 {
+        self.data_size / self.block_align
+    }
+
+    // Append one sample per channel. The samples have to be of the kind the parser
+    // makes for this file, or it's a `SampleFormatMismatch`.
+    pub fn write_frame(&mut self, frame: &[Sample]) -> Result<(), WaveError> {
+        if frame.len() != self.num_channels {
+            return Err(WaveError::ChannelLengthMismatch);
+        }
+
+        let data_size = self.data_size + self.block_align;
+        if self.riff_size_for(data_size) > u32::MAX as u64 {
+            return Err(WaveError::InvalidChunkSize(u32::MAX));
+        }
+
+        self.frame.clear();
+        for sample in frame.iter() {
+            write_sample(&mut self.frame, sample, self.wave_format, self.bits_per_sample)?;
+        }
+        write_all(&mut self.file, &self.frame)?;
+
+        self.data_size = data_size;
+
+        Ok(())
+    }
+
+    // Write the pad byte if needed and the chunks that came after the data, fill in the sizes
+    // and hand back the file. The file is left positioned at the end.
+    pub fn finalize(mut self) -> Result<F, WaveError> {
+        if self.data_size % 2 == 1 {
+            write_all(&mut self.file, &[0])?;
+        }
+        write_all(&mut self.file, &self.trailer)?;
+        let end = self.file.stream_position().map_err(|e| WaveError::Io(e.kind()))?;
+
+        let riff_size = self.riff_size_for(self.data_size) as u32;
+        self.patch(4, riff_size)?;
+        self.patch(self.data_size_offset, self.data_size as u32)?;
+
+        self.file.seek(SeekFrom::Start(end)).map_err(|e| WaveError::Io(e.kind()))?;
+        self.file.flush().map_err(|e| WaveError::Io(e.kind()))?;
+
+        Ok(self.file)
+    }
+
+    // The RIFF size once the data is `data_size` bytes
+    fn riff_size_for(&self, data_size: u64) -> u64 {
+        let padded = |size: u64| size + size % 2;
+
+        self.riff_size as u64 + padded(data_size) - padded(self.original_data_size)
+    }
+
+    fn patch(&mut self, offset: u64, value: u32) -> Result<(), WaveError> {
+        self.file.seek(SeekFrom::Start(offset)).map_err(|e| WaveError::Io(e.kind()))?;
+        write_all(&mut self.file, &value.to_le_bytes())
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use std::io::Cursor;
+    use super::WaveAppender;
+    use crate::error::WaveError;
+    use crate::parser::WaveFileParser;
+    use crate::test_utils::{fmt_bytes, riff_bytes, wave_bytes};
+    use crate::wave_file::Sample;
+
+    #[test]
+    fn test_appending_frames() {
+        let bytes = wave_bytes(1, 2, 16, &[1, 0, 2, 0]);
+
+        let mut appender = WaveAppender::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(1, appender.num_frames());
+        for i in 3..6 {
+            appender.write_frame(&[Sample::BitDepth16(i), Sample::BitDepth16(-i)]).unwrap();
+        }
+        assert_eq!(4, appender.num_frames());
+
+        let bytes = appender.finalize().unwrap().into_inner();
+        let wave_file = WaveFileParser::parse(bytes.clone()).unwrap();
+
+        assert_eq!(&(bytes.len() as u32 - 8).to_le_bytes(), &bytes[4..8]);
+        assert_eq!(vec![1, 3, 4, 5].into_iter().map(Sample::BitDepth16).collect::<Vec<Sample>>(), wave_file.channels[0]);
+        assert_eq!(vec![2, -3, -4, -5].into_iter().map(Sample::BitDepth16).collect::<Vec<Sample>>(), wave_file.channels[1]);
+    }
+
+    #[test]
+    fn test_appending_keeps_the_chunks_after_the_data() {
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"data", &[0x80]), (b"LIST", b"INFOINAM\x04\x00\x00\x00Hum\0")]);
+
+        let mut appender = WaveAppender::new(Cursor::new(bytes.clone())).unwrap();
+        appender.write_frame(&[Sample::BitDepth8(0x81)]).unwrap();
+        let appended = appender.finalize().unwrap().into_inner();
+        let wave_file = WaveFileParser::parse(appended.clone()).unwrap();
+
+        // The pad byte makes room for the new sample
+        assert_eq!(bytes.len(), appended.len());
+        assert_eq!(vec![Sample::BitDepth8(0x80), Sample::BitDepth8(0x81)], wave_file.channels[0]);
+        assert_eq!(Some("Hum"), wave_file.metadata.title());
+    }
+
+    #[test]
+    fn test_appending_samples_of_the_wrong_kind_returns_error() {
+        let mut appender = WaveAppender::new(Cursor::new(wave_bytes(1, 1, 16, &[0, 0]))).unwrap();
+
+        assert_eq!(Err(WaveError::ChannelLengthMismatch), appender.write_frame(&[]));
+        assert_eq!(Err(WaveError::SampleFormatMismatch(16)), appender.write_frame(&[Sample::BitDepth8(0)]));
+    }
+}
@@ -0,0 +1,140 @@
+// The associated data list (a LIST chunk of type 'adtl') attaches text and other data to
+// cue points. Each of its sub-chunks starts with the id of the cue point it belongs to.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+#[cfg(feature = "std")]
+use crate::error::WaveError;
+#[cfg(feature = "std")]
+use crate::wave_file::WaveFile;
+#[cfg(feature = "std")]
+use crate::raw_chunk::write_chunk;
+#[cfg(feature = "std")]
+use crate::writer::write_all;
+
+// A `labl` chunk: the name of a cue point, like the marker names set in Audacity or Reaper
+#[derive(Clone, Debug, PartialEq)]
+pub struct Label {
+    pub cue_id: u32,
+    pub text: String,
+}
+
+// A `note` chunk: a comment on a cue point
+#[derive(Clone, Debug, PartialEq)]
+pub struct Note {
+    pub cue_id: u32,
+    pub text: String,
+}
+
+// An `ltxt` chunk: text for a stretch of audio starting at a cue point, like a region or a loop
+#[derive(Clone, Debug, PartialEq)]
+pub struct Region {
+    pub cue_id: u32,
+    // How long the region is, in samples
+    pub sample_length: u32,
+    // What the text is for, as a four character code. "rgn " is the usual one.
+    pub purpose: [u8; 4],
+    pub text: String,
+}
+
+// A `file` chunk: some other media (a picture, a MIDI file, ...) attached to a cue point
+#[derive(Clone, Debug, PartialEq)]
+pub struct EmbeddedFile {
+    pub cue_id: u32,
+    // What kind of file it is, as a four character code
+    pub media_type: [u8; 4],
+    pub data: Vec<u8>,
+}
+
+impl EmbeddedFile {
+    // Write the file out, say to disk so it can be opened with whatever handles it
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<(), WaveError> {
+        write_all(&mut writer, &self.data)?;
+
+        writer.flush().map_err(|e| WaveError::Io(e.kind()))
+    }
+}
+
+// The whole LIST 'adtl' chunk for the labels, notes, regions and embedded files of `wave_file`,
+// header included. The text is written null terminated.
+#[cfg(feature = "std")]
+pub(crate) fn adtl_list_bytes(wave_file: &WaveFile) -> Vec<u8> {
+    let mut list = b"adtl".to_vec();
+
+    for label in wave_file.labels.iter() {
+        write_chunk(&mut list, b"labl", &cue_text(label.cue_id, &label.text));
+    }
+    for note in wave_file.notes.iter() {
+        write_chunk(&mut list, b"note", &cue_text(note.cue_id, &note.text));
+    }
+    for region in wave_file.regions.iter() {
+        let mut contents = vec![];
+        contents.extend_from_slice(&region.cue_id.to_le_bytes());
+        contents.extend_from_slice(&region.sample_length.to_le_bytes());
+        contents.extend_from_slice(&region.purpose);
+        // wCountry, wLanguage, wDialect and wCodePage, left unset
+        contents.extend_from_slice(&[0; 8]);
+        contents.extend_from_slice(region.text.as_bytes());
+        contents.push(0);
+        write_chunk(&mut list, b"ltxt", &contents);
+    }
+    for file in wave_file.embedded_files.iter() {
+        let mut contents = vec![];
+        contents.extend_from_slice(&file.cue_id.to_le_bytes());
+        contents.extend_from_slice(&file.media_type);
+        contents.extend_from_slice(&file.data);
+        write_chunk(&mut list, b"file", &contents);
+    }
+
+    let mut bytes = vec![];
+    write_chunk(&mut bytes, b"LIST", &list);
+
+    bytes
+}
+
+// The contents of a labl or note chunk
+#[cfg(feature = "std")]
+fn cue_text(cue_id: u32, text: &str) -> Vec<u8> {
+    let mut contents = cue_id.to_le_bytes().to_vec();
+    contents.extend_from_slice(text.as_bytes());
+    contents.push(0);
+
+    contents
+}
+
+// The text in these chunks is null terminated. Anything past the null is ignored.
+// Nothing says which encoding it is in, so bytes that aren't UTF-8 are replaced.
+pub(crate) fn null_terminated_text(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+
+    String::from_utf8_lossy(&bytes[..end]).to_string()
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::{null_terminated_text, EmbeddedFile};
+
+    #[test]
+    fn test_text_stops_at_the_null() {
+        assert_eq!("Verse", null_terminated_text(b"Verse\0\0junk"));
+        assert_eq!("Chorus", null_terminated_text(b"Chorus"));
+    }
+
+    #[test]
+    fn test_writing_embedded_file_payload() {
+        let file = EmbeddedFile {
+            cue_id: 1,
+            media_type: *b"MIDI",
+            data: b"MThd".to_vec(),
+        };
+
+        let mut written = vec![];
+        file.write_to(&mut written).unwrap();
+
+        assert_eq!(b"MThd".to_vec(), written);
+    }
+}
@@ -0,0 +1,230 @@
+// The Broadcast Wave Format (EBU Tech 3285) adds a `bext` chunk describing where the audio
+// came from and, most usefully, where it sits on the timeline of the production.
+
+use alloc::string::String;
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::associated_data::null_terminated_text;
+use crate::error::WaveError;
+#[cfg(feature = "std")]
+use crate::raw_chunk::write_chunk;
+
+// Everything before the coding history
+const BYTES_BEXT_FIXED: usize = 602;
+// What a version 2 loudness field holds when it isn't being used
+const LOUDNESS_UNSET: i16 = 0x7fff;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BroadcastExtension {
+    pub description: String,
+    pub originator: String,
+    pub originator_reference: String,
+    // yyyy-mm-dd
+    pub origination_date: String,
+    // hh:mm:ss
+    pub origination_time: String,
+    // The position of the first sample, as a number of samples since midnight
+    pub time_reference: u64,
+    pub version: u16,
+    // The SMPTE UMID. All zeroes when there isn't one.
+    pub umid: [u8; 64],
+    // The loudness fields only mean something from version 2 on.
+    // They are in hundredths of a LUFS/LU/dBTP.
+    pub loudness_value: i16,
+    pub loudness_range: i16,
+    pub max_true_peak_level: i16,
+    pub max_momentary_loudness: i16,
+    pub max_short_term_loudness: i16,
+    // Lines of text describing each step the audio went through
+    pub coding_history: String,
+}
+
+// Version 2, with everything blank and the loudness fields marked as unused
+impl Default for BroadcastExtension {
+    fn default() -> Self {
+        BroadcastExtension {
+            description: String::new(),
+            originator: String::new(),
+            originator_reference: String::new(),
+            origination_date: String::new(),
+            origination_time: String::new(),
+            time_reference: 0,
+            version: 2,
+            umid: [0; 64],
+            loudness_value: LOUDNESS_UNSET,
+            loudness_range: LOUDNESS_UNSET,
+            max_true_peak_level: LOUDNESS_UNSET,
+            max_momentary_loudness: LOUDNESS_UNSET,
+            max_short_term_loudness: LOUDNESS_UNSET,
+            coding_history: String::new(),
+        }
+    }
+}
+
+impl BroadcastExtension {
+    // The time reference as a time since midnight
+    pub fn time_reference_duration(&self, sample_rate: u32) -> Duration {
+        if sample_rate == 0 {
+            return Duration::from_secs(0);
+        }
+
+        let rate = sample_rate as u64;
+        Duration::from_secs(self.time_reference / rate) + Duration::from_nanos((self.time_reference % rate) * 1_000_000_000 / rate)
+    }
+}
+
+pub(crate) fn read_bext(contents: &[u8]) -> Result<BroadcastExtension, WaveError> {
+    if contents.len() < BYTES_BEXT_FIXED {
+        return Err(WaveError::InvalidChunkSize(contents.len() as u32));
+    }
+
+    let u16_at = |i: usize| u16::from_le_bytes([contents[i], contents[i + 1]]);
+    let u32_at = |i: usize| u32::from_le_bytes([contents[i], contents[i + 1], contents[i + 2], contents[i + 3]]);
+
+    let mut umid = [0; 64];
+    umid.copy_from_slice(&contents[348..412]);
+
+    Ok(BroadcastExtension {
+        description: null_terminated_text(&contents[0..256]),
+        originator: null_terminated_text(&contents[256..288]),
+        originator_reference: null_terminated_text(&contents[288..320]),
+        origination_date: null_terminated_text(&contents[320..330]),
+        origination_time: null_terminated_text(&contents[330..338]),
+        time_reference: (u32_at(342) as u64) << 32 | u32_at(338) as u64,
+        version: u16_at(346),
+        umid,
+        loudness_value: u16_at(412) as i16,
+        loudness_range: u16_at(414) as i16,
+        max_true_peak_level: u16_at(416) as i16,
+        max_momentary_loudness: u16_at(418) as i16,
+        max_short_term_loudness: u16_at(420) as i16,
+        // 180 reserved bytes come before the coding history
+        coding_history: null_terminated_text(&contents[BYTES_BEXT_FIXED..]),
+    })
+}
+
+// The whole `bext` chunk, header included. The text fields are padded out with nulls, and
+// text that doesn't fit in its field is an error rather than being cut short. The loudness
+// fields are only written from version 2 on; before that those bytes are reserved.
+#[cfg(feature = "std")]
+pub(crate) fn bext_chunk_bytes(bext: &BroadcastExtension) -> Result<Vec<u8>, WaveError> {
+    let mut contents = Vec::with_capacity(BYTES_BEXT_FIXED + bext.coding_history.len());
+
+    fixed_text(&mut contents, "bext description", &bext.description, 256)?;
+    fixed_text(&mut contents, "bext originator", &bext.originator, 32)?;
+    fixed_text(&mut contents, "bext originator reference", &bext.originator_reference, 32)?;
+    fixed_text(&mut contents, "bext origination date", &bext.origination_date, 10)?;
+    fixed_text(&mut contents, "bext origination time", &bext.origination_time, 8)?;
+    // Low 32 bits first
+    contents.extend_from_slice(&(bext.time_reference as u32).to_le_bytes());
+    contents.extend_from_slice(&((bext.time_reference >> 32) as u32).to_le_bytes());
+    contents.extend_from_slice(&bext.version.to_le_bytes());
+    contents.extend_from_slice(&bext.umid);
+
+    let loudness = [bext.loudness_value, bext.loudness_range, bext.max_true_peak_level, bext.max_momentary_loudness, bext.max_short_term_loudness];
+    for &value in loudness.iter() {
+        let value = if bext.version >= 2 { value } else { 0 };
+        contents.extend_from_slice(&value.to_le_bytes());
+    }
+    contents.resize(BYTES_BEXT_FIXED, 0);
+
+    contents.extend_from_slice(bext.coding_history.as_bytes());
+
+    let mut bytes = vec![];
+    write_chunk(&mut bytes, b"bext", &contents);
+
+    Ok(bytes)
+}
+
+// `text` in a field of `size` bytes, padded with nulls
+#[cfg(feature = "std")]
+fn fixed_text(bytes: &mut Vec<u8>, field: &'static str, text: &str, size: usize) -> Result<(), WaveError> {
+    if text.len() > size {
+        return Err(WaveError::TextTooLong { field, limit: size });
+    }
+
+    bytes.extend_from_slice(text.as_bytes());
+    bytes.resize(bytes.len() + size - text.len(), 0);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use core::time::Duration;
+    use super::{bext_chunk_bytes, read_bext, BroadcastExtension};
+    use crate::error::WaveError;
+
+    #[test]
+    fn test_reading_bext_fields() {
+        let mut contents = vec![0; 602];
+        contents[..5].copy_from_slice(b"Scene");
+        contents[256..260].copy_from_slice(b"ACME");
+        contents[320..330].copy_from_slice(b"2024-05-06");
+        contents[330..338].copy_from_slice(b"12:34:56");
+        // 48000 * 3600 * 10, ten o'clock at 48kHz, doesn't fit in 32 bits
+        contents[338..346].copy_from_slice(&1_728_000_000u64.to_le_bytes());
+        contents[346..348].copy_from_slice(&2u16.to_le_bytes());
+        contents[412..414].copy_from_slice(&(-2300i16).to_le_bytes());
+        contents.extend_from_slice(b"A=PCM,F=48000\r\n");
+
+        let bext = read_bext(&contents).unwrap();
+
+        assert_eq!("Scene", bext.description);
+        assert_eq!("ACME", bext.originator);
+        assert_eq!("2024-05-06", bext.origination_date);
+        assert_eq!("12:34:56", bext.origination_time);
+        assert_eq!(1_728_000_000, bext.time_reference);
+        assert_eq!(Duration::from_secs(36000), bext.time_reference_duration(48000));
+        assert_eq!(2, bext.version);
+        assert_eq!(-2300, bext.loudness_value);
+        assert_eq!("A=PCM,F=48000\r\n", bext.coding_history);
+    }
+
+    #[test]
+    fn test_writing_and_reading_bext() {
+        let bext = BroadcastExtension {
+            description: "Scene 4, take 2".to_string(),
+            originator: "Field recorder".to_string(),
+            origination_date: "2024-05-06".to_string(),
+            origination_time: "12:34:56".to_string(),
+            time_reference: 1_728_000_000,
+            loudness_value: -2300,
+            coding_history: "A=PCM,F=48000,W=24,M=stereo\r\n".to_string(),
+            ..Default::default()
+        };
+
+        let bytes = bext_chunk_bytes(&bext).unwrap();
+
+        assert_eq!(b"bext", &bytes[..4]);
+        assert_eq!(&(602 + 29u32).to_le_bytes(), &bytes[4..8]);
+        // An odd sized chunk gets a pad byte
+        assert_eq!(8 + 602 + 29 + 1, bytes.len());
+        assert_eq!(bext, read_bext(&bytes[8..8 + 631]).unwrap());
+    }
+
+    #[test]
+    fn test_writing_version_1_bext_leaves_loudness_reserved() {
+        let bext = BroadcastExtension { version: 1, loudness_value: -2300, ..Default::default() };
+
+        let read = read_bext(&bext_chunk_bytes(&bext).unwrap()[8..]).unwrap();
+
+        assert_eq!(1, read.version);
+        assert_eq!(0, read.loudness_value);
+        assert_eq!(0, read.max_short_term_loudness);
+    }
+
+    #[test]
+    fn test_writing_bext_with_text_too_long_returns_error() {
+        let bext = BroadcastExtension { origination_date: "6th May 2024".to_string(), ..Default::default() };
+
+        assert_eq!(Err(WaveError::TextTooLong { field: "bext origination date", limit: 10 }), bext_chunk_bytes(&bext));
+    }
+
+    #[test]
+    fn test_reading_short_bext_returns_error() {
+        assert_eq!(Err(WaveError::InvalidChunkSize(10)), read_bext(&[0; 10]));
+    }
+}
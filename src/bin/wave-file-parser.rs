@@ -0,0 +1,25 @@
+use std::fs::File;
+use std::io::BufReader;
+use wave_file_parser::WaveFileParser;
+
+fn main() {
+    // Example:
+
+    // Get the first argument. We are assuming it will be a file to a .wav file.
+    let args: Vec<String> = std::env::args().collect();
+    let filename = &args[1];
+
+    // Stream the file into the parser
+    // Get a WaveFile structure back.
+    let wave_file = match WaveFileParser::from_reader(BufReader::new(File::open(filename).unwrap())) {
+        Ok(wave_file) => wave_file,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    println!("channels: {}", wave_file.channels.len());
+    println!("sample rate: {}", wave_file.sample_rate);
+    println!("bits per sample: {}", wave_file.bits_per_sample);
+}
@@ -0,0 +1,302 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::error::WaveError;
+
+// The most we allocate for a read before any of the bytes show up
+const MAX_PREALLOCATION: usize = 64 * 1024;
+
+// Anything a ByteStream can pull bytes out of.
+// This is our own trait rather than `Read` so that the parser works without std, on bytes
+// that are already in memory.
+pub(crate) trait Source {
+    // Add the next `count` bytes onto the end of `buf`. Returns how many were added, which is
+    // only less than `count` when the end of the source is reached.
+    fn read_into(&mut self, buf: &mut Vec<u8>, count: usize) -> Result<usize, WaveError>;
+
+    // Fill as much of `buf` as there are bytes left for. Returns how much was filled.
+    fn read_slice(&mut self, buf: &mut [u8]) -> Result<usize, WaveError>;
+
+    // Move ahead `count` bytes. Returns how many bytes were actually skipped, which is
+    // only less than `count` when the end of the source is reached.
+    fn skip_bytes(&mut self, count: u64) -> Result<u64, WaveError>;
+}
+
+// Bytes that are already in memory, either owned or borrowed
+pub(crate) struct InMemory<B> {
+    bytes: B,
+    position: usize,
+}
+
+impl<B: AsRef<[u8]>> InMemory<B> {
+    pub(crate) fn new(bytes: B) -> InMemory<B> {
+        InMemory { bytes, position: 0 }
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.as_ref().len() - self.position
+    }
+}
+
+impl<B: AsRef<[u8]>> Source for InMemory<B> {
+    fn read_into(&mut self, buf: &mut Vec<u8>, count: usize) -> Result<usize, WaveError> {
+        let count = count.min(self.remaining());
+        buf.extend_from_slice(&self.bytes.as_ref()[self.position..self.position + count]);
+        self.position += count;
+
+        Ok(count)
+    }
+
+    fn read_slice(&mut self, buf: &mut [u8]) -> Result<usize, WaveError> {
+        let count = buf.len().min(self.remaining());
+        buf[..count].copy_from_slice(&self.bytes.as_ref()[self.position..self.position + count]);
+        self.position += count;
+
+        Ok(count)
+    }
+
+    fn skip_bytes(&mut self, count: u64) -> Result<u64, WaveError> {
+        let skipped = count.min(self.remaining() as u64);
+        self.position += skipped as usize;
+
+        Ok(skipped)
+    }
+}
+
+// A reader that can only go forwards. Skipping means reading and throwing the bytes away.
+#[cfg(feature = "std")]
+pub(crate) struct ForwardOnly<R>(pub(crate) R);
+
+#[cfg(feature = "std")]
+impl<R: Read> Source for ForwardOnly<R> {
+    fn read_into(&mut self, buf: &mut Vec<u8>, count: usize) -> Result<usize, WaveError> {
+        (&mut self.0).take(count as u64).read_to_end(buf).map_err(|e| WaveError::Io(e.kind()))
+    }
+
+    fn read_slice(&mut self, buf: &mut [u8]) -> Result<usize, WaveError> {
+        read_fully(&mut self.0, buf)
+    }
+
+    fn skip_bytes(&mut self, count: u64) -> Result<u64, WaveError> {
+        io::copy(&mut (&mut self.0).take(count), &mut io::sink()).map_err(|e| WaveError::Io(e.kind()))
+    }
+}
+
+// `read_exact`, except that running out of bytes isn't an error. Returns how much was filled.
+#[cfg(feature = "std")]
+fn read_fully<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize, WaveError> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(count) => filled += count,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(WaveError::Io(e.kind())),
+        }
+    }
+
+    Ok(filled)
+}
+
+// A reader that can jump around. Skipping is just a seek, so whatever we skip is never read.
+#[cfg(feature = "std")]
+pub(crate) struct Seekable<R> {
+    pub(crate) inner: R,
+    len: u64,
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> Seekable<R> {
+    pub(crate) fn new(mut inner: R) -> io::Result<Seekable<R>> {
+        // Seeking past the end is not an error, so remember where the end is
+        let position = inner.stream_position()?;
+        let len = inner.seek(SeekFrom::End(0))?;
+        inner.seek(SeekFrom::Start(position))?;
+
+        Ok(Seekable { inner, len })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> Source for Seekable<R> {
+    fn read_into(&mut self, buf: &mut Vec<u8>, count: usize) -> Result<usize, WaveError> {
+        (&mut self.inner).take(count as u64).read_to_end(buf).map_err(|e| WaveError::Io(e.kind()))
+    }
+
+    fn read_slice(&mut self, buf: &mut [u8]) -> Result<usize, WaveError> {
+        read_fully(&mut self.inner, buf)
+    }
+
+    fn skip_bytes(&mut self, count: u64) -> Result<u64, WaveError> {
+        let (inner, len) = (&mut self.inner, self.len);
+        let mut skip = || -> io::Result<u64> {
+            let position = inner.stream_position()?;
+            let skipped = count.min(len.saturating_sub(position));
+            inner.seek(SeekFrom::Current(skipped as i64))?;
+
+            Ok(skipped)
+        };
+
+        skip().map_err(|e| WaveError::Io(e.kind()))
+    }
+}
+
+// A wrapper around a source of bytes that keeps track of how far into it we are.
+// The source is only ever read forwards, so it can be a file, a socket or a plain Vec
+// wrapped in a Cursor. Nothing is buffered beyond what the caller asks for.
+pub(crate) struct ByteStream<S> {
+    source: S,
+    pub(crate) offset: u64,
+    // Numbers are little-endian, except in RIFX files
+    pub(crate) big_endian: bool,
+}
+
+impl<S: Source> ByteStream<S> {
+    pub(crate) fn new(source: S) -> ByteStream<S> {
+        // The offset counts the bytes consumed so far
+        // It starts off at 0
+        ByteStream {
+            source,
+            offset: 0,
+            big_endian: false,
+        }
+    }
+
+    // Hand back the source, for when we are done parsing
+    #[cfg(feature = "std")]
+    pub(crate) fn into_inner(self) -> S {
+        self.source
+    }
+
+    // Read the next `count` bytes and update the offset
+    pub(crate) fn read(&mut self, count: usize) -> Result<Vec<u8>, WaveError> {
+        match self.read_or_eof(count)? {
+            Some(bytes_read) => Ok(bytes_read),
+            None => Err(WaveError::UnexpectedEof),
+        }
+    }
+
+    // Same as `read`, except that running into the end of the stream before reading
+    // a single byte is not an error. This is how we find out there are no more chunks.
+    pub(crate) fn read_or_eof(&mut self, count: usize) -> Result<Option<Vec<u8>>, WaveError> {
+        let bytes_read = self.read_up_to(count)?;
+        let filled = bytes_read.len();
+
+        if filled == count {
+            Ok(Some(bytes_read))
+        } else if filled == 0 {
+            Ok(None)
+        } else {
+            Err(WaveError::UnexpectedEof)
+        }
+    }
+
+    // Read the next `count` bytes, or as many as there are before the end of the stream
+    pub(crate) fn read_up_to(&mut self, count: usize) -> Result<Vec<u8>, WaveError> {
+        // The count usually comes straight from a chunk size, which could be anything.
+        // So the buffer grows as the bytes come in rather than being allocated up front.
+        let mut bytes_read = Vec::with_capacity(count.min(MAX_PREALLOCATION));
+        let filled = self.source.read_into(&mut bytes_read, count)?;
+
+        // A read updates the offset
+        self.offset += filled as u64;
+
+        Ok(bytes_read)
+    }
+
+    // Read the next `N` bytes into an array. Nothing gets allocated, which matters since this
+    // is how every field and every sample is read.
+    pub(crate) fn read_array<const N: usize>(&mut self) -> Result<[u8; N], WaveError> {
+        let mut bytes = [0; N];
+        let filled = self.source.read_slice(&mut bytes)?;
+        self.offset += filled as u64;
+
+        if filled == N {
+            Ok(bytes)
+        } else {
+            Err(WaveError::UnexpectedEof)
+        }
+    }
+
+    // The numbers below are little-endian, or big-endian in a RIFX file
+
+    pub(crate) fn read_u16(&mut self) -> Result<u16, WaveError> {
+        let bytes = self.read_array()?;
+
+        Ok(if self.big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) })
+    }
+
+    pub(crate) fn read_u32(&mut self) -> Result<u32, WaveError> {
+        let bytes = self.read_array()?;
+
+        Ok(if self.big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) })
+    }
+
+    pub(crate) fn read_u64(&mut self) -> Result<u64, WaveError> {
+        let bytes = self.read_array()?;
+
+        Ok(if self.big_endian { u64::from_be_bytes(bytes) } else { u64::from_le_bytes(bytes) })
+    }
+
+    // Move all the way to the end of the stream. Returns how many bytes that took.
+    pub(crate) fn skip_to_end(&mut self) -> Result<u64, WaveError> {
+        let skipped = self.source.skip_bytes(u64::MAX)?;
+        self.offset += skipped;
+
+        Ok(skipped)
+    }
+
+    // Move ahead `count` bytes without keeping them around
+    pub(crate) fn skip(&mut self, count: u64) -> Result<(), WaveError> {
+        let skipped = self.source.skip_bytes(count)?;
+
+        self.offset += skipped;
+
+        if skipped == count {
+            Ok(())
+        } else {
+            Err(WaveError::UnexpectedEof)
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::{ByteStream, InMemory};
+    use crate::error::WaveError;
+
+    #[test]
+    fn test_reading_numbers_in_either_byte_order() {
+        let bytes = vec![0x01, 0x02, 0xff, 0xff, 0x80, 0x01, 0x02, 0x03, 0x04];
+
+        let mut little = ByteStream::new(InMemory::new(&bytes[..]));
+        assert_eq!(Ok(0x0201), little.read_u16());
+        assert_eq!(Ok([0xff, 0xff, 0x80]), little.read_array());
+        assert_eq!(Ok(0x0403_0201), little.read_u32());
+        assert_eq!(9, little.offset);
+
+        let mut big = ByteStream::new(InMemory::new(&bytes[..]));
+        big.big_endian = true;
+        assert_eq!(Ok(0x0102), big.read_u16());
+        big.skip(3).unwrap();
+        assert_eq!(Ok(0x0102_0304), big.read_u32());
+    }
+
+    #[test]
+    fn test_reading_past_the_end_returns_error() {
+        let mut byte_stream = ByteStream::new(InMemory::new(vec![0x01, 0x02, 0x03]));
+
+        assert_eq!(Err(WaveError::UnexpectedEof), byte_stream.read_u32());
+        assert_eq!(3, byte_stream.offset);
+    }
+
+    #[test]
+    fn test_read_up_to_stops_at_the_end() {
+        let mut byte_stream = ByteStream::new(InMemory::new(vec![0x01, 0x02, 0x03]));
+
+        assert_eq!(Ok(vec![0x01, 0x02]), byte_stream.read_up_to(2));
+        assert_eq!(Ok(vec![0x03]), byte_stream.read_up_to(10));
+        assert_eq!(3, byte_stream.offset);
+    }
+}
@@ -0,0 +1,125 @@
+// The `cart` chunk (AES46) used by radio automation systems.
+// It describes a "cart", a piece of audio ready to go to air: what it is, when it may be
+// played, and timer markers for things like the intro and the segue point.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::associated_data::null_terminated_text;
+use crate::error::WaveError;
+
+// Everything before the tag text
+const BYTES_CART_FIXED: usize = 2048;
+const NUM_POST_TIMERS: usize = 8;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CartChunk {
+    // Four digits, like "0101"
+    pub version: String,
+    pub title: String,
+    pub artist: String,
+    pub cut_id: String,
+    pub client_id: String,
+    pub category: String,
+    pub classification: String,
+    pub out_cue: String,
+    // When the cart may be played. Dates are yyyy/mm/dd and times are hh:mm:ss.
+    pub start_date: String,
+    pub start_time: String,
+    pub end_date: String,
+    pub end_time: String,
+    pub producer_app_id: String,
+    pub producer_app_version: String,
+    pub user_def: String,
+    // The sample value that 0dB reference corresponds to
+    pub level_reference: i32,
+    // The timers that are in use. Unused timer slots are left out.
+    pub post_timers: Vec<CartTimer>,
+    pub url: String,
+    pub tag_text: String,
+}
+
+// A named position in the audio, like "SEG1" or "INT1"
+#[derive(Clone, Debug, PartialEq)]
+pub struct CartTimer {
+    pub usage: [u8; 4],
+    // In samples from the start of the audio
+    pub value: u32,
+}
+
+pub(crate) fn read_cart(contents: &[u8]) -> Result<CartChunk, WaveError> {
+    if contents.len() < BYTES_CART_FIXED {
+        return Err(WaveError::InvalidChunkSize(contents.len() as u32));
+    }
+
+    let text = |start: usize, end: usize| null_terminated_text(&contents[start..end]);
+    let u32_at = |i: usize| u32::from_le_bytes([contents[i], contents[i + 1], contents[i + 2], contents[i + 3]]);
+
+    let post_timers = (0..NUM_POST_TIMERS)
+        .map(|i| 684 + i * 8)
+        .filter(|&offset| contents[offset..offset + 4] != [0; 4])
+        .map(|offset| {
+            let mut usage = [0; 4];
+            usage.copy_from_slice(&contents[offset..offset + 4]);
+            CartTimer {
+                usage,
+                value: u32_at(offset + 4),
+            }
+        })
+        .collect();
+
+    // 276 reserved bytes sit between the timers and the URL
+    Ok(CartChunk {
+        version: text(0, 4),
+        title: text(4, 68),
+        artist: text(68, 132),
+        cut_id: text(132, 196),
+        client_id: text(196, 260),
+        category: text(260, 324),
+        classification: text(324, 388),
+        out_cue: text(388, 452),
+        start_date: text(452, 462),
+        start_time: text(462, 470),
+        end_date: text(470, 480),
+        end_time: text(480, 488),
+        producer_app_id: text(488, 552),
+        producer_app_version: text(552, 616),
+        user_def: text(616, 680),
+        level_reference: u32_at(680) as i32,
+        post_timers,
+        url: text(1024, 2048),
+        tag_text: text(2048, contents.len()),
+    })
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::{read_cart, CartTimer};
+    use crate::error::WaveError;
+
+    #[test]
+    fn test_reading_cart() {
+        let mut contents = vec![0; 2048];
+        contents[0..4].copy_from_slice(b"0101");
+        contents[4..12].copy_from_slice(b"Top Hits");
+        contents[132..137].copy_from_slice(b"CUT42");
+        contents[452..462].copy_from_slice(b"2024/01/01");
+        contents[692..696].copy_from_slice(b"SEG1");
+        contents[696..700].copy_from_slice(&44100u32.to_le_bytes());
+        contents.extend_from_slice(b"<tags/>");
+
+        let cart = read_cart(&contents).unwrap();
+
+        assert_eq!("0101", cart.version);
+        assert_eq!("Top Hits", cart.title);
+        assert_eq!("CUT42", cart.cut_id);
+        assert_eq!("2024/01/01", cart.start_date);
+        assert_eq!(vec![CartTimer { usage: *b"SEG1", value: 44100 }], cart.post_timers);
+        assert_eq!("<tags/>", cart.tag_text);
+    }
+
+    #[test]
+    fn test_reading_short_cart_returns_error() {
+        assert_eq!(Err(WaveError::InvalidChunkSize(4)), read_cart(b"0101"));
+    }
+}
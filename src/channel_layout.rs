@@ -0,0 +1,105 @@
+use alloc::vec::Vec;
+
+// The speaker positions that can be set in the dwChannelMask of an extensible `fmt ` chunk.
+// The values are the bits in the mask.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpeakerPosition {
+    FrontLeft = 0x1,
+    FrontRight = 0x2,
+    FrontCenter = 0x4,
+    LowFrequency = 0x8,
+    BackLeft = 0x10,
+    BackRight = 0x20,
+    FrontLeftOfCenter = 0x40,
+    FrontRightOfCenter = 0x80,
+    BackCenter = 0x100,
+    SideLeft = 0x200,
+    SideRight = 0x400,
+    TopCenter = 0x800,
+    TopFrontLeft = 0x1000,
+    TopFrontCenter = 0x2000,
+    TopFrontRight = 0x4000,
+    TopBackLeft = 0x8000,
+    TopBackCenter = 0x10000,
+    TopBackRight = 0x20000,
+}
+
+// In the order the channels are interleaved, which is the order of the bits in the mask
+const SPEAKER_POSITIONS: [SpeakerPosition; 18] = [
+    SpeakerPosition::FrontLeft,
+    SpeakerPosition::FrontRight,
+    SpeakerPosition::FrontCenter,
+    SpeakerPosition::LowFrequency,
+    SpeakerPosition::BackLeft,
+    SpeakerPosition::BackRight,
+    SpeakerPosition::FrontLeftOfCenter,
+    SpeakerPosition::FrontRightOfCenter,
+    SpeakerPosition::BackCenter,
+    SpeakerPosition::SideLeft,
+    SpeakerPosition::SideRight,
+    SpeakerPosition::TopCenter,
+    SpeakerPosition::TopFrontLeft,
+    SpeakerPosition::TopFrontCenter,
+    SpeakerPosition::TopFrontRight,
+    SpeakerPosition::TopBackLeft,
+    SpeakerPosition::TopBackCenter,
+    SpeakerPosition::TopBackRight,
+];
+
+// Which speaker each decoded channel is meant for.
+// The first channel goes with the lowest bit set in the channel mask, the second with the next
+// one and so on. Channels past the last bit set aren't meant for any particular speaker.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChannelLayout {
+    positions: Vec<Option<SpeakerPosition>>,
+}
+
+impl ChannelLayout {
+    pub fn from_mask(channel_mask: u32, num_channels: usize) -> ChannelLayout {
+        let mut assigned = SPEAKER_POSITIONS.iter().filter(|&&p| channel_mask & p as u32 != 0);
+
+        let positions = (0..num_channels).map(|_| assigned.next().cloned()).collect();
+
+        ChannelLayout { positions }
+    }
+
+    // One entry per channel, in channel order
+    pub fn positions(&self) -> &[Option<SpeakerPosition>] {
+        &self.positions
+    }
+
+    // The speaker the channel at `index` is meant for
+    pub fn speaker(&self, index: usize) -> Option<SpeakerPosition> {
+        self.positions.get(index).cloned().flatten()
+    }
+
+    // The index of the channel meant for `position`, if there is one
+    pub fn channel(&self, position: SpeakerPosition) -> Option<usize> {
+        self.positions.iter().position(|&p| p == Some(position))
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::{ChannelLayout, SpeakerPosition};
+
+    #[test]
+    fn test_layout_for_5_1_mask() {
+        // FL, FR, FC, LFE, BL, BR
+        let layout = ChannelLayout::from_mask(0x3f, 6);
+
+        assert_eq!(Some(SpeakerPosition::FrontLeft), layout.speaker(0));
+        assert_eq!(Some(SpeakerPosition::LowFrequency), layout.speaker(3));
+        assert_eq!(Some(SpeakerPosition::BackRight), layout.speaker(5));
+        assert_eq!(Some(2), layout.channel(SpeakerPosition::FrontCenter));
+        assert_eq!(None, layout.channel(SpeakerPosition::SideLeft));
+    }
+
+    #[test]
+    fn test_layout_with_more_channels_than_mask_bits() {
+        // FC only, plus two channels that don't go anywhere in particular
+        let layout = ChannelLayout::from_mask(0x4, 3);
+
+        assert_eq!(&[Some(SpeakerPosition::FrontCenter), None, None], layout.positions());
+    }
+}
@@ -0,0 +1,91 @@
+// G.711 A-law and µ-law, as used by telephony captures.
+// Each byte expands to a 16 bit linear sample. The expansion is done once, at compile time,
+// into a table that maps every possible byte to its sample.
+
+const ALAW_TABLE: [i16; 256] = build_alaw_table();
+const MULAW_TABLE: [i16; 256] = build_mulaw_table();
+
+pub(crate) fn alaw_to_linear(byte: u8) -> i16 {
+    ALAW_TABLE[byte as usize]
+}
+
+pub(crate) fn mulaw_to_linear(byte: u8) -> i16 {
+    MULAW_TABLE[byte as usize]
+}
+
+const fn build_alaw_table() -> [i16; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        // Every other bit is inverted in an A-law byte
+        let a_val = i as u8 ^ 0x55;
+
+        // The bottom 4 bits are the mantissa, the next 3 the segment (exponent)
+        let mut t = ((a_val & 0x0f) as i32) << 4;
+        let segment = (a_val & 0x70) >> 4;
+
+        match segment {
+            0 => t += 8,
+            1 => t += 0x108,
+            _ => {
+                t += 0x108;
+                t <<= segment - 1;
+            }
+        }
+
+        // The top bit being set means the sample is positive
+        table[i] = if a_val & 0x80 != 0 { t as i16 } else { -t as i16 };
+        i += 1;
+    }
+
+    table
+}
+
+const fn build_mulaw_table() -> [i16; 256] {
+    // Added before the shift so that the segments line up, and taken back off after
+    const BIAS: i32 = 0x84;
+
+    let mut table = [0; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        // All the bits are inverted in a µ-law byte
+        let u_val = !(i as u8);
+
+        // The bottom 4 bits are the mantissa, the next 3 the segment (exponent)
+        let mut t = (((u_val & 0x0f) as i32) << 3) + BIAS;
+        t <<= (u_val & 0x70) >> 4;
+
+        // The top bit being set means the sample is negative
+        table[i] = if u_val & 0x80 != 0 { (BIAS - t) as i16 } else { (t - BIAS) as i16 };
+        i += 1;
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::{alaw_to_linear, mulaw_to_linear};
+
+    #[test]
+    fn test_alaw_expansion() {
+        // The smallest magnitudes either side of 0
+        assert_eq!(8, alaw_to_linear(0xd5));
+        assert_eq!(-8, alaw_to_linear(0x55));
+        // The largest magnitudes
+        assert_eq!(32256, alaw_to_linear(0xaa));
+        assert_eq!(-32256, alaw_to_linear(0x2a));
+    }
+
+    #[test]
+    fn test_mulaw_expansion() {
+        // There are two encodings of 0
+        assert_eq!(0, mulaw_to_linear(0xff));
+        assert_eq!(0, mulaw_to_linear(0x7f));
+        // The largest magnitudes
+        assert_eq!(32124, mulaw_to_linear(0x80));
+        assert_eq!(-32124, mulaw_to_linear(0x00));
+    }
+}
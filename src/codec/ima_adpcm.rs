@@ -0,0 +1,133 @@
+// IMA (DVI) ADPCM, as found in a lot of game audio.
+// The data chunk is a sequence of blocks of `block_align` bytes. Each block starts with a
+// 4 byte header per channel holding the first sample and the step index. After that come
+// 4 bit codes, each one a difference from the previous sample.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::WaveError;
+use crate::wave_file::Sample;
+
+// How much the step index moves after each code
+const INDEX_TABLE: [i32; 16] = [-1, -1, -1, -1, 2, 4, 6, 8, -1, -1, -1, -1, 2, 4, 6, 8];
+
+// The step sizes the index picks between
+const STEP_TABLE: [i32; 89] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66,
+    73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408,
+    449, 494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066,
+    2272, 2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630,
+    9493, 10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794,
+    32767,
+];
+
+const BYTES_BLOCK_HEADER: usize = 4;
+
+// The decoder state for one channel. It is reset by every block header.
+struct ChannelState {
+    predictor: i32,
+    step_index: i32,
+}
+
+impl ChannelState {
+    fn decode(&mut self, code: u8) -> i16 {
+        let step = STEP_TABLE[self.step_index as usize];
+
+        // diff = (code + 0.5) * step / 4, done with shifts so it rounds like everybody else
+        let mut diff = step >> 3;
+        if code & 1 != 0 {
+            diff += step >> 2;
+        }
+        if code & 2 != 0 {
+            diff += step >> 1;
+        }
+        if code & 4 != 0 {
+            diff += step;
+        }
+        if code & 8 != 0 {
+            diff = -diff;
+        }
+
+        self.predictor = (self.predictor + diff).clamp(i16::MIN as i32, i16::MAX as i32);
+        self.step_index = (self.step_index + INDEX_TABLE[code as usize]).clamp(0, 88);
+
+        self.predictor as i16
+    }
+}
+
+// Decodes one block, appending the samples to `channels`.
+// The last block in a file can be shorter than `block_align`, so the block is decoded for
+// however many bytes it has.
+pub(crate) fn decode_block(block: &[u8], channels: &mut [Vec<Sample>]) -> Result<(), WaveError> {
+    let num_channels = channels.len();
+    if num_channels == 0 {
+        return Err(WaveError::UnsupportedChannelCount(0));
+    }
+    if block.len() < BYTES_BLOCK_HEADER * num_channels {
+        return Err(WaveError::UnexpectedEof);
+    }
+
+    // The header sample is the first sample of the block
+    let mut states = vec![];
+    for (i, channel) in channels.iter_mut().enumerate() {
+        let header = &block[i * BYTES_BLOCK_HEADER..(i + 1) * BYTES_BLOCK_HEADER];
+        let predictor = ((header[1] as u16) << 8 | header[0] as u16) as i16;
+
+        channel.push(Sample::BitDepth16(predictor));
+        states.push(ChannelState {
+            predictor: predictor as i32,
+            step_index: (header[2] as i32).clamp(0, 88),
+        });
+    }
+
+    // The codes come in groups of 4 bytes (8 samples) per channel, one channel after the other.
+    // Within a byte the low nibble comes first.
+    let group_size = 4 * num_channels;
+    for group in block[BYTES_BLOCK_HEADER * num_channels..].chunks_exact(group_size) {
+        for (i, channel) in channels.iter_mut().enumerate() {
+            for &byte in group[i * 4..(i + 1) * 4].iter() {
+                channel.push(Sample::BitDepth16(states[i].decode(byte & 0x0f)));
+                channel.push(Sample::BitDepth16(states[i].decode(byte >> 4)));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::decode_block;
+    use crate::wave_file::Sample;
+
+    #[test]
+    fn test_decoding_mono_block() {
+        // Predictor 100, step index 0, then codes 0x1 and 0x9 (+step/4ish then -step/4ish)
+        let block = [100, 0, 0, 0, 0x91, 0x00, 0x00, 0x00];
+        let mut channels = vec![vec![]];
+
+        decode_block(&block, &mut channels).unwrap();
+
+        // step 7: code 1 adds 7/8 + 7/4 = 0 + 1 = 1, index stays at 0.
+        // code 9 takes that away again. The zero codes add 7 >> 3 = 0.
+        assert_eq!(9, channels[0].len());
+        assert_eq!(Sample::BitDepth16(100), channels[0][0]);
+        assert_eq!(Sample::BitDepth16(101), channels[0][1]);
+        assert_eq!(Sample::BitDepth16(100), channels[0][2]);
+    }
+
+    #[test]
+    fn test_decoding_stereo_block_interleaving() {
+        // Left starts at 0, right at -1000. Each has one group of zero codes.
+        let block = [0, 0, 0, 0, 0x18, 0xfc, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let mut channels = vec![vec![], vec![]];
+
+        decode_block(&block, &mut channels).unwrap();
+
+        assert_eq!(9, channels[0].len());
+        assert_eq!(9, channels[1].len());
+        assert_eq!(Sample::BitDepth16(-1000), channels[1][0]);
+        assert_eq!(Sample::BitDepth16(-1000), channels[1][8]);
+    }
+}
@@ -0,0 +1,72 @@
+// Decoders for the compressed formats.
+// Each of these turns the bytes in the data chunk into plain Sample values.
+
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::WaveError;
+use crate::fmt_chunk::FmtChunk;
+use crate::wave_file::{Sample, WaveFormatCategory};
+
+pub(crate) mod g711;
+pub(crate) mod ima_adpcm;
+pub(crate) mod ms_adpcm;
+pub(crate) mod pcm;
+
+// A decoder for a format that packs its samples into blocks, like the ADPCM formats.
+// Implement this and register it with `WaveFileParser::register_codec` to read formats this
+// library doesn't know about (or to replace one of the built in decoders).
+//
+// The data chunk is handed over one block (`block_align` bytes) at a time. The last block
+// may be shorter. Each frame returned has to hold one sample per channel.
+pub trait WaveCodec {
+    fn decode_block(&mut self, block: &[u8], fmt: &FmtChunk) -> Result<Vec<Vec<Sample>>, WaveError>;
+}
+
+// The block decoders that come with the library
+pub(crate) fn builtin_codec(wave_format: WaveFormatCategory) -> Option<Box<dyn WaveCodec>> {
+    match wave_format {
+        WaveFormatCategory::WAVE_FORMAT_ADPCM => Some(Box::new(MsAdpcm {})),
+        WaveFormatCategory::WAVE_FORMAT_IMA_ADPCM => Some(Box::new(ImaAdpcm {})),
+        _ => None,
+    }
+}
+
+struct ImaAdpcm {}
+
+impl WaveCodec for ImaAdpcm {
+    fn decode_block(&mut self, block: &[u8], fmt: &FmtChunk) -> Result<Vec<Vec<Sample>>, WaveError> {
+        let mut channels = vec![vec![]; fmt.channels as usize];
+        ima_adpcm::decode_block(block, &mut channels)?;
+
+        Ok(into_frames(channels))
+    }
+}
+
+struct MsAdpcm {}
+
+impl WaveCodec for MsAdpcm {
+    fn decode_block(&mut self, block: &[u8], fmt: &FmtChunk) -> Result<Vec<Vec<Sample>>, WaveError> {
+        let coefficients = ms_adpcm::read_coefficients(&fmt.extension)?;
+
+        let mut channels = vec![vec![]; fmt.channels as usize];
+        ms_adpcm::decode_block(block, &coefficients, &mut channels)?;
+
+        Ok(into_frames(channels))
+    }
+}
+
+// Turns one vector per channel into one vector per frame
+fn into_frames(channels: Vec<Vec<Sample>>) -> Vec<Vec<Sample>> {
+    let num_frames = channels.iter().map(|c| c.len()).min().unwrap_or(0);
+    let mut frames = vec![Vec::with_capacity(channels.len()); num_frames];
+
+    for channel in channels {
+        for (frame, sample) in frames.iter_mut().zip(channel) {
+            frame.push(sample);
+        }
+    }
+
+    frames
+}
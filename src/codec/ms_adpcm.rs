@@ -0,0 +1,198 @@
+// Microsoft ADPCM, the one Windows Sound Recorder used to write.
+// Each block starts with a header per channel: which pair of coefficients to predict with,
+// the starting step size (delta) and the first two samples. After that come 4 bit codes,
+// each one a correction to the prediction made from the previous two samples.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::WaveError;
+use crate::wave_file::{Sample, WaveFormatCategory};
+
+// How much the delta is scaled by after each code, in 1/256ths
+const ADAPTATION_TABLE: [i32; 16] = [230, 230, 230, 230, 307, 409, 512, 614, 768, 614, 512, 409, 307, 230, 230, 230];
+
+// The coefficient pairs every encoder is supposed to use.
+// Files carry their own table in the fmt extension, which normally just repeats this one.
+pub(crate) const DEFAULT_COEFFICIENTS: [(i16, i16); 7] = [(256, 0), (512, -256), (0, 0), (192, 64), (240, 0), (460, -208), (392, -232)];
+
+// The most the delta can grow to. Past this, scaling it by the biggest entry of the adaptation
+// table would overflow. Nothing a real encoder makes comes near it.
+const MAX_DELTA: i32 = i32::MAX / 768;
+
+// Bytes of header per channel: predictor index, delta, sample 1 and sample 2
+const BYTES_BLOCK_HEADER: usize = 7;
+
+// Pulls the coefficient table out of the fmt extension (everything after cbSize).
+// The extension is wSamplesPerBlock, wNumCoef and then wNumCoef pairs of coefficients.
+pub(crate) fn read_coefficients(extension: &[u8]) -> Result<Vec<(i16, i16)>, WaveError> {
+    if extension.len() < 4 {
+        return Ok(DEFAULT_COEFFICIENTS.to_vec());
+    }
+
+    let num_coefficients = u16::from_le_bytes([extension[2], extension[3]]) as usize;
+    let table = &extension[4..];
+    if table.len() < num_coefficients * 4 {
+        return Err(WaveError::UnexpectedEof);
+    }
+
+    Ok(table
+        .chunks_exact(4)
+        .take(num_coefficients)
+        .map(|c| (i16::from_le_bytes([c[0], c[1]]), i16::from_le_bytes([c[2], c[3]])))
+        .collect())
+}
+
+// The decoder state for one channel. It is reset by every block header.
+struct ChannelState {
+    coefficients: (i32, i32),
+    delta: i32,
+    sample1: i32,
+    sample2: i32,
+}
+
+impl ChannelState {
+    fn decode(&mut self, code: u8) -> i16 {
+        // The code is a signed 4 bit number
+        let signed_code = if code & 8 != 0 { code as i32 - 16 } else { code as i32 };
+
+        // The coefficients come from the file, so the sum can be more than 32 bits
+        let prediction = (self.sample1 as i64 * self.coefficients.0 as i64 + self.sample2 as i64 * self.coefficients.1 as i64) >> 8;
+        let sample = (prediction + signed_code as i64 * self.delta as i64).clamp(i16::MIN as i64, i16::MAX as i64) as i32;
+
+        self.sample2 = self.sample1;
+        self.sample1 = sample;
+        self.delta = ((ADAPTATION_TABLE[code as usize] * self.delta) >> 8).clamp(16, MAX_DELTA);
+
+        sample as i16
+    }
+}
+
+// Decodes one block, appending the samples to `channels`.
+// The last block in a file can be shorter than `block_align`, so the block is decoded for
+// however many bytes it has.
+pub(crate) fn decode_block(block: &[u8], coefficients: &[(i16, i16)], channels: &mut [Vec<Sample>]) -> Result<(), WaveError> {
+    let num_channels = channels.len();
+    if num_channels == 0 {
+        return Err(WaveError::UnsupportedChannelCount(0));
+    }
+    if block.len() < BYTES_BLOCK_HEADER * num_channels {
+        return Err(WaveError::UnexpectedEof);
+    }
+
+    // The header fields are each stored for all the channels before moving on to the next field
+    let read_i16 = |field_offset: usize, channel: usize| {
+        let i = field_offset * num_channels + 2 * channel;
+        i16::from_le_bytes([block[i], block[i + 1]]) as i32
+    };
+
+    let mut states = vec![];
+    for (i, channel) in channels.iter_mut().enumerate() {
+        let predictor = block[i] as usize;
+        // A predictor past the end of the table points at coefficients we don't have
+        let (coefficient1, coefficient2) = match coefficients.get(predictor) {
+            Some(&pair) => pair,
+            None => return Err(WaveError::UnsupportedFormat(WaveFormatCategory::WAVE_FORMAT_ADPCM as u16)),
+        };
+
+        let state = ChannelState {
+            coefficients: (coefficient1 as i32, coefficient2 as i32),
+            delta: read_i16(1, i),
+            sample1: read_i16(3, i),
+            sample2: read_i16(5, i),
+        };
+
+        // The older sample comes first
+        channel.push(Sample::BitDepth16(state.sample2 as i16));
+        channel.push(Sample::BitDepth16(state.sample1 as i16));
+        states.push(state);
+    }
+
+    // The codes go round the channels in order, high nibble first
+    let codes = block[BYTES_BLOCK_HEADER * num_channels..].iter().flat_map(|&byte| [byte >> 4, byte & 0x0f]);
+    for (i, code) in codes.enumerate() {
+        let channel = i % num_channels;
+        let sample = states[channel].decode(code);
+
+        channels[channel].push(Sample::BitDepth16(sample));
+    }
+
+    // With an odd number of channels a block can end part way through a frame
+    let num_frames = channels.iter().map(|c| c.len()).min().unwrap_or(0);
+    for channel in channels.iter_mut() {
+        channel.truncate(num_frames);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::{decode_block, read_coefficients, DEFAULT_COEFFICIENTS};
+    use crate::error::WaveError;
+    use crate::wave_file::Sample;
+
+    #[test]
+    fn test_decoding_mono_block() {
+        // Predictor 0 (256, 0) repeats the last sample. Delta 16, samples 100 and 50.
+        // Code 0x1 adds one delta and code 0xf takes one away.
+        let block = [0, 16, 0, 100, 0, 50, 0, 0x1f];
+        let mut channels = vec![vec![]];
+
+        decode_block(&block, &DEFAULT_COEFFICIENTS, &mut channels).unwrap();
+
+        let expected: Vec<Sample> = [50, 100, 116, 100].iter().map(|&s| Sample::BitDepth16(s)).collect();
+        assert_eq!(expected, channels[0]);
+    }
+
+    #[test]
+    fn test_decoding_stereo_block_interleaving() {
+        // Left uses predictor 0 and right predictor 2 (0, 0), which always predicts silence.
+        let block = [0, 2, 16, 0, 16, 0, 10, 0, 20, 0, 1, 0, 2, 0, 0x11];
+        let mut channels = vec![vec![], vec![]];
+
+        decode_block(&block, &DEFAULT_COEFFICIENTS, &mut channels).unwrap();
+
+        assert_eq!(vec![Sample::BitDepth16(1), Sample::BitDepth16(10), Sample::BitDepth16(26)], channels[0]);
+        assert_eq!(vec![Sample::BitDepth16(2), Sample::BitDepth16(20), Sample::BitDepth16(16)], channels[1]);
+    }
+
+    #[test]
+    fn test_decoding_with_unknown_predictor_returns_error() {
+        let block = [7, 16, 0, 100, 0, 50, 0, 0x1f];
+        let mut channels = vec![vec![]];
+
+        assert_eq!(Err(WaveError::UnsupportedFormat(0x0002)), decode_block(&block, &DEFAULT_COEFFICIENTS, &mut channels));
+    }
+
+    #[test]
+    fn test_decoding_with_extreme_coefficients_and_samples() {
+        // Coefficients of -32768 times samples of -32768, twice over, is more than an i32 holds
+        let block = [0, 16, 0, 0x00, 0x80, 0x00, 0x80, 0x00];
+        let mut channels = vec![vec![]];
+
+        decode_block(&block, &[(i16::MIN, i16::MIN)], &mut channels).unwrap();
+
+        assert_eq!(Sample::BitDepth16(i16::MAX), channels[0][2]);
+    }
+
+    #[test]
+    fn test_decoding_with_growing_delta() {
+        // The biggest delta there is, and codes that keep making it 3 times bigger
+        let mut block = vec![0, 0xff, 0x7f, 0, 0, 0, 0];
+        block.extend_from_slice(&[0x88; 32]);
+        let mut channels = vec![vec![]];
+
+        decode_block(&block, &DEFAULT_COEFFICIENTS, &mut channels).unwrap();
+
+        assert_eq!(66, channels[0].len());
+    }
+
+    #[test]
+    fn test_reading_coefficients_from_extension() {
+        // wSamplesPerBlock = 2, wNumCoef = 1, then (512, -256)
+        let extension = [2, 0, 1, 0, 0x00, 0x02, 0x00, 0xff];
+
+        assert_eq!(vec![(512, -256)], read_coefficients(&extension).unwrap());
+    }
+}
@@ -0,0 +1,118 @@
+// The formats with one sample after another: integer PCM, IEEE float and G.711.
+// What each sample looks like is worked out once for the data chunk, then every sample is
+// decoded straight out of a slice of the chunk.
+
+use crate::codec::g711;
+use crate::error::WaveError;
+use crate::parser::is_block_compressed;
+use crate::wave_file::{Sample, WaveFormatCategory};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum SampleEncoding {
+    Unsigned8,
+    Signed16,
+    Signed24,
+    Signed32,
+    Float32,
+    Float64,
+    ALaw,
+    MuLaw,
+}
+
+impl SampleEncoding {
+    pub(crate) fn new(wave_format: WaveFormatCategory, bit_depth: u16) -> Result<SampleEncoding, WaveError> {
+        // These can't be decoded one sample at a time
+        if is_block_compressed(wave_format) {
+            return Err(WaveError::UnsupportedFormat(wave_format as u16));
+        }
+
+        match (wave_format, bit_depth) {
+            // IEEE floats only come in two sizes
+            (WaveFormatCategory::WAVE_FORMAT_IEEE_FLOAT, 32) => Ok(SampleEncoding::Float32),
+            (WaveFormatCategory::WAVE_FORMAT_IEEE_FLOAT, 64) => Ok(SampleEncoding::Float64),
+            (WaveFormatCategory::WAVE_FORMAT_IEEE_FLOAT, _) => Err(WaveError::UnsupportedBitDepth(bit_depth)),
+            (WaveFormatCategory::WAVE_FORMAT_ALAW, _) => Ok(SampleEncoding::ALaw),
+            (WaveFormatCategory::WAVE_FORMAT_MULAW, _) => Ok(SampleEncoding::MuLaw),
+            (_, 1..=8) => Ok(SampleEncoding::Unsigned8),
+            (_, 9..=16) => Ok(SampleEncoding::Signed16),
+            (_, 17..=24) => Ok(SampleEncoding::Signed24),
+            (_, 25..=32) => Ok(SampleEncoding::Signed32),
+            _ => Err(WaveError::UnsupportedBitDepth(bit_depth)),
+        }
+    }
+
+    // How many bytes each sample takes up
+    pub(crate) fn size(self) -> usize {
+        match self {
+            SampleEncoding::Unsigned8 | SampleEncoding::ALaw | SampleEncoding::MuLaw => 1,
+            SampleEncoding::Signed16 => 2,
+            SampleEncoding::Signed24 => 3,
+            SampleEncoding::Signed32 | SampleEncoding::Float32 => 4,
+            SampleEncoding::Float64 => 8,
+        }
+    }
+
+    // `bytes` has to be exactly `size()` long
+    pub(crate) fn decode(self, bytes: &[u8], big_endian: bool) -> Sample {
+        match self {
+            SampleEncoding::Unsigned8 => Sample::BitDepth8(bytes[0]),
+            SampleEncoding::ALaw => Sample::BitDepth16(g711::alaw_to_linear(bytes[0])),
+            SampleEncoding::MuLaw => Sample::BitDepth16(g711::mulaw_to_linear(bytes[0])),
+            SampleEncoding::Signed16 => {
+                let bytes = [bytes[0], bytes[1]];
+                Sample::BitDepth16(if big_endian { i16::from_be_bytes(bytes) } else { i16::from_le_bytes(bytes) })
+            }
+            SampleEncoding::Signed24 => {
+                // Put the bytes in the top of the i32 and let the arithmetic shift copy the sign bit down
+                let value = if big_endian {
+                    i32::from_be_bytes([bytes[0], bytes[1], bytes[2], 0])
+                } else {
+                    i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]])
+                };
+                Sample::BitDepth24(value >> 8)
+            }
+            SampleEncoding::Signed32 => Sample::BitDepth32(to_u32(bytes, big_endian) as i32),
+            SampleEncoding::Float32 => Sample::Float32(f32::from_bits(to_u32(bytes, big_endian))),
+            SampleEncoding::Float64 => {
+                let mut array = [0; 8];
+                array.copy_from_slice(bytes);
+                Sample::Float64(f64::from_bits(if big_endian { u64::from_be_bytes(array) } else { u64::from_le_bytes(array) }))
+            }
+        }
+    }
+}
+
+fn to_u32(bytes: &[u8], big_endian: bool) -> u32 {
+    let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+
+    if big_endian {
+        u32::from_be_bytes(bytes)
+    } else {
+        u32::from_le_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::SampleEncoding;
+    use crate::error::WaveError;
+    use crate::wave_file::{Sample, WaveFormatCategory};
+
+    #[test]
+    fn test_encoding_for_format_and_bit_depth() {
+        assert_eq!(Ok(SampleEncoding::Signed16), SampleEncoding::new(WaveFormatCategory::WAVE_FORMAT_PCM, 12));
+        assert_eq!(Ok(SampleEncoding::Float64), SampleEncoding::new(WaveFormatCategory::WAVE_FORMAT_IEEE_FLOAT, 64));
+        assert_eq!(Err(WaveError::UnsupportedBitDepth(16)), SampleEncoding::new(WaveFormatCategory::WAVE_FORMAT_IEEE_FLOAT, 16));
+        assert_eq!(Err(WaveError::UnsupportedBitDepth(40)), SampleEncoding::new(WaveFormatCategory::WAVE_FORMAT_PCM, 40));
+        assert_eq!(Err(WaveError::UnsupportedFormat(0x11)), SampleEncoding::new(WaveFormatCategory::WAVE_FORMAT_IMA_ADPCM, 4));
+    }
+
+    #[test]
+    fn test_decoding_samples() {
+        assert_eq!(Sample::BitDepth16(-2), SampleEncoding::Signed16.decode(&[0xfe, 0xff], false));
+        assert_eq!(Sample::BitDepth24(-8_388_608), SampleEncoding::Signed24.decode(&[0x00, 0x00, 0x80], false));
+        assert_eq!(Sample::BitDepth24(0x12_3456), SampleEncoding::Signed24.decode(&[0x12, 0x34, 0x56], true));
+        assert_eq!(Sample::Float32(1.0), SampleEncoding::Float32.decode(&1.0f32.to_le_bytes(), false));
+        assert_eq!(Sample::Float64(-0.5), SampleEncoding::Float64.decode(&(-0.5f64).to_be_bytes(), true));
+    }
+}
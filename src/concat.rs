@@ -0,0 +1,197 @@
+use core::convert::TryFrom;
+
+use crate::convert::{FromSample, I24};
+use crate::error::WaveError;
+use crate::wave_file::{Sample, WaveFile, WaveFormatCategory};
+
+impl WaveFile {
+    // The files one after the other, say to join up a recording that was split over several files.
+    // They have to have the same sample rate, channels and sample format. Everything but the
+    // samples and the markers comes from the first file.
+    //
+    // The cue points of the later files (and their labels, notes, regions and so on) are moved
+    // along to where their file starts. Their ids are moved up past the ids of the files before
+    // them, so they don't clash.
+    pub fn concat(files: &[WaveFile]) -> Result<WaveFile, WaveError> {
+        WaveFile::join(files, false)
+    }
+
+    // Same as `concat`, but files with a different sample format are converted to the format
+    // of the first file with `FromSample`. The sample rate and channels still have to match.
+    pub fn concat_converting(files: &[WaveFile]) -> Result<WaveFile, WaveError> {
+        WaveFile::join(files, true)
+    }
+
+    fn join(files: &[WaveFile], convert: bool) -> Result<WaveFile, WaveError> {
+        let first = match files.first() {
+            Some(first) => first,
+            None => return Ok(Default::default()),
+        };
+
+        for file in files[1..].iter() {
+            if file.sample_rate != first.sample_rate {
+                return Err(WaveError::FormatMismatch("sample rate"));
+            }
+            if file.channels.len() != first.channels.len() {
+                return Err(WaveError::FormatMismatch("number of channels"));
+            }
+            let same_format = file.wave_format == first.wave_format && file.bits_per_sample == first.bits_per_sample;
+            if !same_format && !convert {
+                return Err(WaveError::FormatMismatch("sample format"));
+            }
+        }
+
+        let mut joined = first.slice_frames(0..first.num_frames());
+        let mut last_id = max_cue_id(first).map_or(0, |id| id as u64);
+
+        for file in files[1..].iter() {
+            let start = joined.num_frames();
+
+            for (joined_channel, channel) in joined.channels.iter_mut().zip(file.channels.iter()) {
+                let length = channel.len().min(file.num_frames() as usize);
+                if convert {
+                    joined_channel.extend(channel[..length].iter().map(|s| convert_sample(s.clone(), first.wave_format, first.bits_per_sample)));
+                } else {
+                    joined_channel.extend_from_slice(&channel[..length]);
+                }
+            }
+
+            // Cue positions are 32 bit, so markers that end up past that are left behind
+            let id_offset = last_id;
+            let new_id = |cue_id: u32| u32::try_from(cue_id as u64 + id_offset).ok();
+            let moved = |position: u32| u32::try_from(position as u64 + start).ok();
+            let kept = |cue_id: u32| {
+                file.cue_points.iter().any(|c| c.id == cue_id && moved(c.sample_offset).is_some()) && new_id(cue_id).is_some()
+            };
+
+            for cue_point in file.cue_points.iter().filter(|c| kept(c.id)) {
+                let mut cue_point = cue_point.clone();
+                cue_point.id = new_id(cue_point.id).unwrap_or_default();
+                cue_point.position = moved(cue_point.position).unwrap_or(u32::MAX);
+                cue_point.sample_offset = moved(cue_point.sample_offset).unwrap_or_default();
+                joined.cue_points.push(cue_point);
+            }
+            for label in file.labels.iter().filter(|l| kept(l.cue_id)) {
+                let mut label = label.clone();
+                label.cue_id = new_id(label.cue_id).unwrap_or_default();
+                joined.labels.push(label);
+            }
+            for note in file.notes.iter().filter(|n| kept(n.cue_id)) {
+                let mut note = note.clone();
+                note.cue_id = new_id(note.cue_id).unwrap_or_default();
+                joined.notes.push(note);
+            }
+            for region in file.regions.iter().filter(|r| kept(r.cue_id)) {
+                let mut region = region.clone();
+                region.cue_id = new_id(region.cue_id).unwrap_or_default();
+                joined.regions.push(region);
+            }
+            for embedded_file in file.embedded_files.iter().filter(|e| kept(e.cue_id)) {
+                let mut embedded_file = embedded_file.clone();
+                embedded_file.cue_id = new_id(embedded_file.cue_id).unwrap_or_default();
+                joined.embedded_files.push(embedded_file);
+            }
+            for segment in file.playlist.iter().filter(|p| kept(p.cue_id)) {
+                let mut segment = segment.clone();
+                segment.cue_id = new_id(segment.cue_id).unwrap_or_default();
+                joined.playlist.push(segment);
+            }
+
+            if let Some(max_id) = max_cue_id(file) {
+                last_id += max_id as u64;
+            }
+        }
+
+        if joined.fact_sample_count.is_some() {
+            joined.fact_sample_count = Some(joined.num_frames().min(u32::MAX as u64) as u32);
+        }
+
+        Ok(joined)
+    }
+}
+
+fn max_cue_id(wave_file: &WaveFile) -> Option<u32> {
+    wave_file.cue_points.iter().map(|c| c.id).max()
+}
+
+// `sample` as the kind of sample the parser makes for `wave_format` and `bits_per_sample`
+fn convert_sample(sample: Sample, wave_format: WaveFormatCategory, bits_per_sample: u16) -> Sample {
+    let is_float = wave_format == WaveFormatCategory::WAVE_FORMAT_IEEE_FLOAT;
+
+    match (is_float, bits_per_sample) {
+        (true, 64) => Sample::Float64(f64::from_sample(sample)),
+        (true, _) => Sample::Float32(f32::from_sample(sample)),
+        (false, 0..=8) => Sample::BitDepth8(u8::from_sample(sample)),
+        (false, 9..=16) => Sample::BitDepth16(i16::from_sample(sample)),
+        (false, 17..=24) => Sample::BitDepth24(I24::from_sample(sample).get()),
+        (false, _) => Sample::BitDepth32(i32::from_sample(sample)),
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use crate::associated_data::Label;
+    use crate::cue::CuePoint;
+    use crate::error::WaveError;
+    use crate::wave_file::{Sample, WaveFile, WaveFormatCategory};
+
+    fn cue_point(id: u32, sample_offset: u32) -> CuePoint {
+        CuePoint {
+            id,
+            position: sample_offset,
+            chunk_id: *b"data",
+            chunk_start: 0,
+            block_start: 0,
+            sample_offset,
+        }
+    }
+
+    fn file(samples: &[i16], cue_points: Vec<CuePoint>, labels: &[(u32, &str)]) -> WaveFile {
+        WaveFile {
+            channels: vec![samples.iter().cloned().map(Sample::BitDepth16).collect()],
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            cue_points,
+            labels: labels.iter().map(|&(cue_id, text)| Label { cue_id, text: text.to_string() }).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_joining_files_moves_the_markers() {
+        let first = file(&[1, 2, 3], vec![cue_point(1, 0), cue_point(2, 2)], &[(1, "start"), (2, "end of one")]);
+        let second = file(&[4, 5], vec![cue_point(1, 1)], &[(1, "in two")]);
+
+        let joined = WaveFile::concat(&[first, second]).unwrap();
+
+        assert_eq!((1..6).map(Sample::BitDepth16).collect::<Vec<Sample>>(), joined.channels[0]);
+        assert_eq!(vec![cue_point(1, 0), cue_point(2, 2), cue_point(3, 4)], joined.cue_points);
+        assert_eq!(Some("in two".to_string()), joined.markers()[2].label);
+    }
+
+    #[test]
+    fn test_joining_files_in_different_formats() {
+        let first = file(&[0, 16384], vec![], &[]);
+        let second = WaveFile {
+            channels: vec![vec![Sample::Float32(-0.5)]],
+            wave_format: WaveFormatCategory::WAVE_FORMAT_IEEE_FLOAT,
+            sample_rate: 44100,
+            bits_per_sample: 32,
+            ..Default::default()
+        };
+
+        assert_eq!(Err(WaveError::FormatMismatch("sample format")), WaveFile::concat(&[file(&[0], vec![], &[]), second.slice_frames(0..1)]).map(|_| ()));
+
+        let joined = WaveFile::concat_converting(&[first, second]).unwrap();
+        assert_eq!(vec![Sample::BitDepth16(0), Sample::BitDepth16(16384), Sample::BitDepth16(-16384)], joined.channels[0]);
+    }
+
+    #[test]
+    fn test_joining_files_with_different_sample_rates_returns_error() {
+        let mut second = file(&[1], vec![], &[]);
+        second.sample_rate = 48000;
+
+        assert_eq!(Err(WaveError::FormatMismatch("sample rate")), WaveFile::concat(&[file(&[1], vec![], &[]), second]).map(|_| ()));
+        assert_eq!(0, WaveFile::concat(&[]).unwrap().num_frames());
+    }
+}
@@ -0,0 +1,258 @@
+// Converting samples between types, so code can pick the type it works in once and get
+// everything in that type, whatever the file holds:
+//
+//     let channels: Vec<Vec<i16>> = wave_file.samples_as::<i16>();
+//     let sample: f32 = 16_384i16.to_sample();
+//
+// The rules are the usual ones. Integers fill their whole range, floats go from -1.0 to 1.0,
+// and 8 bit samples are unsigned, centered on 128. Going to a smaller integer drops the low bits,
+// going to a bigger one shifts them in as zeros. Floats out of range are clamped when converted
+// to integers, and converting between floats leaves them alone.
+
+use crate::wave_file::Sample;
+
+// A 24 bit sample, held in an i32. Kept between `I24::MIN` and `I24::MAX`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct I24(i32);
+
+impl I24 {
+    pub const MIN: I24 = I24(-8_388_608);
+    pub const MAX: I24 = I24(8_388_607);
+
+    // Values outside of the 24 bit range are clamped
+    pub fn new(value: i32) -> I24 {
+        I24(value.clamp(I24::MIN.0, I24::MAX.0))
+    }
+
+    pub fn get(self) -> i32 {
+        self.0
+    }
+}
+
+// Make a `Self` out of a sample of type `S`
+pub trait FromSample<S> {
+    fn from_sample(sample: S) -> Self;
+}
+
+// The other way round, like `Into` is for `From`. Implemented for everything `FromSample` is.
+pub trait ToSample<T> {
+    fn to_sample(self) -> T;
+}
+
+impl<S, T: FromSample<S>> ToSample<T> for S {
+    fn to_sample(self) -> T {
+        T::from_sample(self)
+    }
+}
+
+mod sealed {
+    // What every sample type knows about itself. Everything else is built from these.
+    // Integers go through an i32 that uses the full 32 bit range, so that converting between
+    // them is exact. Anything to do with floats goes through an f64.
+    pub trait Primitive: Copy {
+        const IS_FLOAT: bool;
+
+        fn to_i32(self) -> i32;
+        fn from_i32(value: i32) -> Self;
+        fn to_f64(self) -> f64;
+        fn from_f64(value: f64) -> Self;
+    }
+}
+
+use self::sealed::Primitive;
+
+// Scale a float between -1.0 and 1.0 to an integer with `bits` bits, clamping as it goes
+fn f64_to_int(value: f64, bits: u32) -> i64 {
+    let scale = (1i64 << (bits - 1)) as f64;
+    let value = if value.is_nan() { 0.0 } else { value.clamp(-1.0, 1.0) };
+
+    // Rounded half away from zero. `f64::round` needs std.
+    let scaled = value * scale;
+    let rounded = (if scaled < 0.0 { scaled - 0.5 } else { scaled + 0.5 }) as i64;
+
+    rounded.clamp(-(1i64 << (bits - 1)), (1i64 << (bits - 1)) - 1)
+}
+
+fn i32_to_f64(value: i32) -> f64 {
+    value as f64 / 2_147_483_648.0
+}
+
+impl Primitive for u8 {
+    const IS_FLOAT: bool = false;
+
+    fn to_i32(self) -> i32 {
+        (self as i32 - 128) << 24
+    }
+
+    fn from_i32(value: i32) -> u8 {
+        ((value >> 24) + 128) as u8
+    }
+
+    fn to_f64(self) -> f64 {
+        i32_to_f64(self.to_i32())
+    }
+
+    fn from_f64(value: f64) -> u8 {
+        (f64_to_int(value, 8) + 128) as u8
+    }
+}
+
+impl Primitive for i16 {
+    const IS_FLOAT: bool = false;
+
+    fn to_i32(self) -> i32 {
+        (self as i32) << 16
+    }
+
+    fn from_i32(value: i32) -> i16 {
+        (value >> 16) as i16
+    }
+
+    fn to_f64(self) -> f64 {
+        i32_to_f64(self.to_i32())
+    }
+
+    fn from_f64(value: f64) -> i16 {
+        f64_to_int(value, 16) as i16
+    }
+}
+
+impl Primitive for I24 {
+    const IS_FLOAT: bool = false;
+
+    fn to_i32(self) -> i32 {
+        self.0 << 8
+    }
+
+    fn from_i32(value: i32) -> I24 {
+        I24(value >> 8)
+    }
+
+    fn to_f64(self) -> f64 {
+        i32_to_f64(self.to_i32())
+    }
+
+    fn from_f64(value: f64) -> I24 {
+        I24(f64_to_int(value, 24) as i32)
+    }
+}
+
+impl Primitive for i32 {
+    const IS_FLOAT: bool = false;
+
+    fn to_i32(self) -> i32 {
+        self
+    }
+
+    fn from_i32(value: i32) -> i32 {
+        value
+    }
+
+    fn to_f64(self) -> f64 {
+        i32_to_f64(self)
+    }
+
+    fn from_f64(value: f64) -> i32 {
+        f64_to_int(value, 32) as i32
+    }
+}
+
+impl Primitive for f32 {
+    const IS_FLOAT: bool = true;
+
+    fn to_i32(self) -> i32 {
+        f64_to_int(self as f64, 32) as i32
+    }
+
+    fn from_i32(value: i32) -> f32 {
+        i32_to_f64(value) as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn from_f64(value: f64) -> f32 {
+        value as f32
+    }
+}
+
+impl Primitive for f64 {
+    const IS_FLOAT: bool = true;
+
+    fn to_i32(self) -> i32 {
+        f64_to_int(self, 32) as i32
+    }
+
+    fn from_i32(value: i32) -> f64 {
+        i32_to_f64(value)
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    fn from_f64(value: f64) -> f64 {
+        value
+    }
+}
+
+impl<S: Primitive, T: Primitive> FromSample<S> for T {
+    fn from_sample(sample: S) -> T {
+        if S::IS_FLOAT || T::IS_FLOAT {
+            T::from_f64(sample.to_f64())
+        } else {
+            T::from_i32(sample.to_i32())
+        }
+    }
+}
+
+impl<T: Primitive> FromSample<Sample> for T {
+    fn from_sample(sample: Sample) -> T {
+        match sample {
+            Sample::BitDepth8(s) => T::from_sample(s),
+            Sample::BitDepth16(s) => T::from_sample(s),
+            Sample::BitDepth24(s) => T::from_sample(I24::new(s)),
+            Sample::BitDepth32(s) => T::from_sample(s),
+            Sample::Float32(s) => T::from_sample(s),
+            Sample::Float64(s) => T::from_sample(s),
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::{FromSample, ToSample, I24};
+    use crate::wave_file::Sample;
+
+    #[test]
+    fn test_integers_convert_by_shifting() {
+        assert_eq!(0u8, i16::MIN.to_sample());
+        assert_eq!(128u8, 0i16.to_sample());
+        assert_eq!(255u8, i32::MAX.to_sample());
+        assert_eq!(i16::MIN, 0u8.to_sample());
+        assert_eq!(0x1234i16, I24::new(0x12_3456).to_sample());
+        assert_eq!(0x1234_0000i32, 0x1234i16.to_sample());
+        assert_eq!(I24::MAX, i32::MAX.to_sample());
+    }
+
+    #[test]
+    fn test_floats_scale_and_clamp() {
+        assert_eq!(-1.0f32, i16::MIN.to_sample());
+        assert_eq!(-0.5f64, 64u8.to_sample());
+        assert_eq!(i16::MAX, 2.0f32.to_sample());
+        assert_eq!(i16::MIN, (-1.0f64).to_sample());
+        assert_eq!(16_384i16, 0.5f32.to_sample());
+        assert_eq!(I24::MIN, f32::NEG_INFINITY.to_sample());
+        assert_eq!(128u8, f64::NAN.to_sample());
+        assert_eq!(1.5f32, 1.5f64.to_sample());
+    }
+
+    #[test]
+    fn test_from_sample_enum() {
+        assert_eq!(-1.0f32, f32::from_sample(Sample::BitDepth8(0)));
+        assert_eq!(0x7fi16, i16::from_sample(Sample::BitDepth24(0x7f_ff)));
+        assert_eq!(I24::new(-2), I24::from_sample(Sample::BitDepth24(-2)));
+        assert_eq!(0x80u8, u8::from_sample(Sample::Float32(0.0)));
+    }
+}
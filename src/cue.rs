@@ -0,0 +1,71 @@
+// Cue points mark positions in the audio, like the markers set in an editor.
+// Other chunks refer to them by id: the playlist plays the audio between them, and the
+// associated data list attaches labels and notes to them.
+
+use alloc::string::String;
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use crate::raw_chunk::write_chunk;
+
+// One record from the `cue ` chunk
+#[derive(Clone, Debug, PartialEq)]
+pub struct CuePoint {
+    // What the other chunks use to refer to this cue point
+    pub id: u32,
+    // The sample the cue point is at, when the audio is played in playlist order
+    pub position: u32,
+    // Which chunk the cue point is in: "data" or "slnt"
+    pub chunk_id: [u8; 4],
+    // Where that chunk starts. 0 when there is only one data chunk.
+    pub chunk_start: u32,
+    // Where the block holding the cue point starts, for compressed formats. 0 otherwise.
+    pub block_start: u32,
+    // The sample the cue point is at, counting from `block_start`
+    pub sample_offset: u32,
+}
+
+// A cue point together with everything the associated data list says about it.
+// This is what `WaveFile::markers` hands back, so nobody has to match up cue ids by hand.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Marker {
+    pub cue_id: u32,
+    // The sample the marker is at, counting from the start of its data chunk
+    pub position_samples: u32,
+    pub label: Option<String>,
+    pub note: Option<String>,
+    // How long the region starting at the marker is, when there is an ltxt chunk for it
+    pub region_length: Option<u32>,
+}
+
+// One entry of the `plst` chunk: play from a cue point for a while, some number of times
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlaylistSegment {
+    // The cue point the segment starts at
+    pub cue_id: u32,
+    // How long the segment is, in samples
+    pub length_samples: u32,
+    // How many times to play it
+    pub loop_count: u32,
+}
+
+// The whole `cue ` chunk for `cue_points`, header included
+#[cfg(feature = "std")]
+pub(crate) fn cue_chunk_bytes(cue_points: &[CuePoint]) -> Vec<u8> {
+    let mut contents = vec![];
+    contents.extend_from_slice(&(cue_points.len() as u32).to_le_bytes());
+    for cue_point in cue_points.iter() {
+        contents.extend_from_slice(&cue_point.id.to_le_bytes());
+        contents.extend_from_slice(&cue_point.position.to_le_bytes());
+        contents.extend_from_slice(&cue_point.chunk_id);
+        contents.extend_from_slice(&cue_point.chunk_start.to_le_bytes());
+        contents.extend_from_slice(&cue_point.block_start.to_le_bytes());
+        contents.extend_from_slice(&cue_point.sample_offset.to_le_bytes());
+    }
+
+    let mut bytes = vec![];
+    write_chunk(&mut bytes, b"cue ", &contents);
+
+    bytes
+}
@@ -0,0 +1,121 @@
+// Chunks this library doesn't know about, picked apart by code that does.
+// Register a `ChunkHandler` for a chunk id with `WaveFileParser::register_chunk` and whatever it
+// makes out of the chunk ends up in `WaveFile::custom_chunks`, where it can be had back by type:
+//
+//     parser.register_chunk(*b"umid", |contents: &[u8]| Ok(Box::new(Umid::from_bytes(contents)?) as Box<dyn Any + Send + Sync>));
+//     let wave_file = parser.read_from(reader)?;
+//     let umid: Option<&Umid> = wave_file.custom_chunks.get(b"umid");
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::any::Any;
+
+use crate::error::WaveError;
+
+// Turns the contents of a chunk (without the id, size or pad byte) into a value.
+// An error stops the parse, like an error in any other chunk would.
+// The value has to be `Send + Sync`, so the WaveFile it ends up in can go to other threads.
+pub trait ChunkHandler {
+    fn parse_chunk(&mut self, contents: &[u8]) -> Result<Box<dyn Any + Send + Sync>, WaveError>;
+}
+
+impl<F> ChunkHandler for F
+where
+    F: FnMut(&[u8]) -> Result<Box<dyn Any + Send + Sync>, WaveError>,
+{
+    fn parse_chunk(&mut self, contents: &[u8]) -> Result<Box<dyn Any + Send + Sync>, WaveError> {
+        self(contents)
+    }
+}
+
+// The values made by the registered chunk handlers, by chunk id, in file order
+#[derive(Default)]
+pub struct CustomChunks {
+    values: Vec<([u8; 4], Box<dyn Any + Send + Sync>)>,
+}
+
+impl CustomChunks {
+    pub fn new() -> CustomChunks {
+        Default::default()
+    }
+
+    pub fn insert(&mut self, id: [u8; 4], value: Box<dyn Any + Send + Sync>) {
+        self.values.push((id, value));
+    }
+
+    // The value for the first `id` chunk, if there is one and it's a `T`
+    pub fn get<T: Any>(&self, id: &[u8; 4]) -> Option<&T> {
+        self.get_all(id).next()
+    }
+
+    // The values for every `id` chunk that are a `T`, for chunks that can show up more than once
+    pub fn get_all<'a, T: Any>(&'a self, id: &[u8; 4]) -> impl Iterator<Item = &'a T> + 'a {
+        let id = *id;
+        self.values.iter().filter(move |(i, _)| *i == id).filter_map(|(_, value)| value.downcast_ref())
+    }
+
+    // The chunk ids, in file order
+    pub fn ids(&self) -> impl Iterator<Item = &[u8; 4]> {
+        self.values.iter().map(|(id, _)| id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use std::any::Any;
+    use super::CustomChunks;
+    use crate::error::WaveError;
+    use crate::parser::WaveFileParser;
+    use crate::test_utils::{fmt_bytes, riff_bytes};
+
+    #[derive(Debug, PartialEq)]
+    struct Umid(Vec<u8>);
+
+    #[test]
+    fn test_getting_values_by_type() {
+        let mut custom_chunks = CustomChunks::new();
+        custom_chunks.insert(*b"umid", Box::new(Umid(vec![1])));
+        custom_chunks.insert(*b"umid", Box::new(Umid(vec![2])));
+        custom_chunks.insert(*b"cnt ", Box::new(3u32));
+
+        assert_eq!(Some(&Umid(vec![1])), custom_chunks.get(b"umid"));
+        assert_eq!(2, custom_chunks.get_all::<Umid>(b"umid").count());
+        assert_eq!(Some(&3u32), custom_chunks.get(b"cnt "));
+        // The wrong type is as good as not being there
+        assert_eq!(None, custom_chunks.get::<u16>(b"cnt "));
+        assert_eq!(vec![b"umid", b"umid", b"cnt "], custom_chunks.ids().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_registered_chunk_handler() {
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"umid", &[1, 2, 3]), (b"data", &[0x80; 2])]);
+
+        let mut parser = WaveFileParser::new();
+        parser.register_chunk(*b"umid", |contents: &[u8]| Ok(Box::new(Umid(contents.to_vec())) as Box<dyn Any + Send + Sync>));
+        let wave_file = parser.read_bytes(bytes).unwrap();
+
+        assert_eq!(Some(&Umid(vec![1, 2, 3])), wave_file.custom_chunks.get(b"umid"));
+        // The chunk is still kept as it is, so writing the file back out doesn't lose it
+        assert_eq!(*b"umid", wave_file.extra_chunks[0].id);
+    }
+
+    #[test]
+    fn test_chunk_handler_error_stops_the_parse() {
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"umid", &[1]), (b"data", &[0x80; 2])]);
+
+        let mut parser = WaveFileParser::new();
+        parser.register_chunk(*b"umid", |contents: &[u8]| -> Result<Box<dyn Any + Send + Sync>, WaveError> {
+            Err(WaveError::InvalidChunkSize(contents.len() as u32))
+        });
+
+        assert_eq!(Err(WaveError::InvalidChunkSize(1)), parser.read_bytes(bytes).map(|_| ()).map_err(|e| e.kind().clone()));
+    }
+}
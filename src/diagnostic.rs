@@ -0,0 +1,39 @@
+use alloc::string::String;
+use core::fmt;
+
+// Something off about a file that didn't stop it from being parsed.
+// Plenty of software writes files like these, and plenty of software reads them without
+// complaint. They're worth knowing about when checking a file, not when playing it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Diagnostic {
+    // The RIFF chunk size doesn't match the number of bytes that are actually there.
+    // Sizes are counted from the start of the file.
+    RiffSizeMismatch { declared: u64, actual: u64 },
+    // dwAverageBytesPerSec should be the sample rate times the block align
+    ByteRateMismatch { declared: u32, expected: u32 },
+    // wBlockAlign should be the number of channels times the bytes in each sample
+    BlockAlignMismatch { declared: u16, expected: u16 },
+    // An odd sized chunk isn't followed by its pad byte. Holds where the chunk starts.
+    MissingPadByte { chunk_id: [u8; 4], offset: u64 },
+    // The data chunk holds a frame that is cut short. Holds the size of the data chunk.
+    PartialFrame { data_size: u64, block_align: u16 },
+    // The file ends before the data chunk does. Only the whole frames that are there were read.
+    TruncatedData { declared: u64, actual: u64 },
+    // `WaveFileParser::parse_repair` didn't believe the data chunk size and used the rest of
+    // the file instead
+    DataSizeRepaired { declared: u64, repaired: u64 },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Diagnostic::RiffSizeMismatch { declared, actual } => write!(f, "RIFF chunk says the file is {} bytes, but it is {}", declared, actual),
+            Diagnostic::ByteRateMismatch { declared, expected } => write!(f, "byte rate is {}, expected {}", declared, expected),
+            Diagnostic::BlockAlignMismatch { declared, expected } => write!(f, "block align is {}, expected {}", declared, expected),
+            Diagnostic::MissingPadByte { chunk_id, offset } => write!(f, "'{}' chunk at {} is missing its pad byte", String::from_utf8_lossy(chunk_id), offset),
+            Diagnostic::PartialFrame { data_size, block_align } => write!(f, "data chunk of {} bytes is not a multiple of the block align {}", data_size, block_align),
+            Diagnostic::TruncatedData { declared, actual } => write!(f, "data chunk should hold {} bytes, but the file ends after {}", declared, actual),
+            Diagnostic::DataSizeRepaired { declared, repaired } => write!(f, "data chunk size {} was repaired to {}", declared, repaired),
+        }
+    }
+}
@@ -0,0 +1,348 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::bext::{self, BroadcastExtension};
+use crate::cue::{self, CuePoint};
+use crate::error::WaveError;
+use crate::metadata::Metadata;
+use crate::parser::{fourcc, WaveFileParser};
+use crate::writer::write_all;
+
+// Changes the metadata, the broadcast extension or the cue points of a file where it is.
+// Only the chunks that changed are written. A chunk that still fits where it was is written over
+// the old one, with a JUNK chunk taking up whatever room is left. One that doesn't fit goes in a
+// JUNK chunk big enough for it, or at the end of the file, and the old one becomes JUNK.
+// The samples are never read or moved.
+//
+//     let mut editor = WaveEditor::open("take3.wav")?;
+//     editor.metadata.set_title("Take 3".to_string());
+//     editor.save()?;
+//
+// Only RIFF files can be edited. RIFX, RF64 and Wave64 files are `UnexpectedChunk`.
+pub struct WaveEditor<F> {
+    file: F,
+    riff_size: u32,
+    chunks: Vec<EditorChunk>,
+    pub metadata: Metadata,
+    pub broadcast_extension: Option<BroadcastExtension>,
+    pub cue_points: Vec<CuePoint>,
+    // What's in the file right now, to tell what changed
+    saved_metadata: Metadata,
+    saved_broadcast_extension: Option<BroadcastExtension>,
+    saved_cue_points: Vec<CuePoint>,
+}
+
+// Where a top level chunk is
+#[derive(Clone, Copy, Debug)]
+struct EditorChunk {
+    id: [u8; 4],
+    // The type of a LIST chunk, like "INFO"
+    list_type: Option<[u8; 4]>,
+    // Where the chunk header starts
+    offset: u64,
+    // The whole chunk: the header, the contents and the pad byte
+    total_size: u64,
+}
+
+impl WaveEditor<File> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<WaveEditor<File>, WaveError> {
+        let file = OpenOptions::new().read(true).write(true).open(path).map_err(|e| WaveError::Io(e.kind()))?;
+
+        WaveEditor::new(file)
+    }
+}
+
+impl<F: Read + Write + Seek> WaveEditor<F> {
+    pub fn new(mut file: F) -> Result<WaveEditor<F>, WaveError> {
+        let info = WaveFileParser::header_from_reader(&mut file)?;
+
+        let mut editor = WaveEditor {
+            file,
+            riff_size: 0,
+            chunks: vec![],
+            metadata: info.wave_file.metadata.clone(),
+            broadcast_extension: info.wave_file.broadcast_extension.clone(),
+            cue_points: info.wave_file.cue_points.clone(),
+            saved_metadata: info.wave_file.metadata,
+            saved_broadcast_extension: info.wave_file.broadcast_extension,
+            saved_cue_points: info.wave_file.cue_points,
+        };
+        editor.read_chunks()?;
+
+        Ok(editor)
+    }
+
+    // Write out whatever changed. Emptying the metadata or the cue points, or taking the
+    // broadcast extension away, turns its chunk into JUNK.
+    pub fn save(&mut self) -> Result<(), WaveError> {
+        if self.metadata != self.saved_metadata {
+            let bytes = if self.metadata.is_empty() { None } else { Some(self.metadata.to_bytes()) };
+            self.replace_chunk(*b"LIST", Some(*b"INFO"), bytes)?;
+            self.saved_metadata = self.metadata.clone();
+        }
+
+        if self.broadcast_extension != self.saved_broadcast_extension {
+            let bytes = match self.broadcast_extension.as_ref() {
+                Some(bext) => Some(bext::bext_chunk_bytes(bext)?),
+                None => None,
+            };
+            self.replace_chunk(*b"bext", None, bytes)?;
+            self.saved_broadcast_extension = self.broadcast_extension.clone();
+        }
+
+        if self.cue_points != self.saved_cue_points {
+            let bytes = if self.cue_points.is_empty() { None } else { Some(cue::cue_chunk_bytes(&self.cue_points)) };
+            self.replace_chunk(*b"cue ", None, bytes)?;
+            self.saved_cue_points = self.cue_points.clone();
+        }
+
+        self.file.flush().map_err(|e| WaveError::Io(e.kind()))
+    }
+
+    pub fn into_inner(self) -> F {
+        self.file
+    }
+
+    // Walks the chunk headers, seeking past the contents
+    fn read_chunks(&mut self) -> Result<(), WaveError> {
+        let mut header = [0; 12];
+        self.read_at(0, &mut header)?;
+        if &header[..4] != b"RIFF" {
+            return Err(WaveError::UnexpectedChunk {
+                expected: *b"RIFF",
+                found: fourcc(&header),
+            });
+        }
+
+        self.riff_size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        self.chunks.clear();
+
+        let end = 8 + self.riff_size as u64;
+        let mut offset = 12;
+        while offset + 8 <= end {
+            let mut chunk_header = [0; 12];
+            let size = if offset + 12 <= end { 12 } else { 8 };
+            self.read_at(offset, &mut chunk_header[..size])?;
+
+            let id = fourcc(&chunk_header);
+            let contents_size = u32::from_le_bytes([chunk_header[4], chunk_header[5], chunk_header[6], chunk_header[7]]) as u64;
+            let list_type = if &id == b"LIST" && contents_size >= 4 { Some(fourcc(&chunk_header[8..])) } else { None };
+            let total_size = 8 + contents_size + contents_size % 2;
+
+            self.chunks.push(EditorChunk {
+                id,
+                list_type,
+                offset,
+                total_size,
+            });
+            offset += total_size;
+        }
+
+        Ok(())
+    }
+
+    // Puts `bytes` (a whole chunk) in place of the chunk with `id` and `list_type`,
+    // or takes that chunk away when there are no bytes
+    fn replace_chunk(&mut self, id: [u8; 4], list_type: Option<[u8; 4]>, bytes: Option<Vec<u8>>) -> Result<(), WaveError> {
+        let index = self.chunks.iter().position(|c| c.id == id && (list_type.is_none() || c.list_type == list_type));
+
+        if let Some(index) = index {
+            // A JUNK chunk right after it is room to grow into
+            let mut chunk = self.chunks[index];
+            if let Some(next) = self.chunks.get(index + 1).filter(|c| &c.id == b"JUNK") {
+                chunk.total_size += next.total_size;
+            }
+
+            match bytes {
+                Some(ref bytes) if fits(bytes.len() as u64, chunk.total_size) => return self.write_in(chunk.offset, chunk.total_size, bytes),
+                _ => {
+                    self.write_junk(chunk.offset, chunk.total_size)?;
+                    self.read_chunks()?;
+                }
+            }
+        }
+
+        let bytes = match bytes {
+            Some(bytes) => bytes,
+            None => return Ok(()),
+        };
+
+        // Any JUNK chunk that's big enough will do, or else the end of the file
+        let junk = self.chunks.iter().find(|c| &c.id == b"JUNK" && fits(bytes.len() as u64, c.total_size)).copied();
+        match junk {
+            Some(junk) => self.write_in(junk.offset, junk.total_size, &bytes),
+            None => self.append(&bytes),
+        }
+    }
+
+    // Writes `bytes` at `offset`, and turns the rest of the `room` bytes there into JUNK
+    fn write_in(&mut self, offset: u64, room: u64, bytes: &[u8]) -> Result<(), WaveError> {
+        self.write_at(offset, bytes)?;
+        if room > bytes.len() as u64 {
+            self.write_junk(offset + bytes.len() as u64, room - bytes.len() as u64)?;
+        }
+
+        self.read_chunks()
+    }
+
+    fn append(&mut self, bytes: &[u8]) -> Result<(), WaveError> {
+        // The last chunk might be missing its pad byte
+        let mut end = 8 + self.riff_size as u64;
+        if end % 2 == 1 {
+            self.write_at(end, &[0])?;
+            end += 1;
+        }
+
+        let riff_size = end - 8 + bytes.len() as u64;
+        if riff_size > u32::MAX as u64 {
+            return Err(WaveError::InvalidChunkSize(u32::MAX));
+        }
+
+        self.write_at(end, bytes)?;
+        self.write_at(4, &(riff_size as u32).to_le_bytes())?;
+
+        self.read_chunks()
+    }
+
+    // A JUNK chunk taking up `total_size` bytes, with the old contents blanked out
+    fn write_junk(&mut self, offset: u64, total_size: u64) -> Result<(), WaveError> {
+        let mut bytes = vec![0; total_size as usize];
+        bytes[..4].copy_from_slice(b"JUNK");
+        bytes[4..8].copy_from_slice(&(total_size as u32 - 8).to_le_bytes());
+
+        self.write_at(offset, &bytes)
+    }
+
+    fn read_at(&mut self, offset: u64, bytes: &mut [u8]) -> Result<(), WaveError> {
+        self.file.seek(SeekFrom::Start(offset)).map_err(|e| WaveError::Io(e.kind()))?;
+        self.file.read_exact(bytes).map_err(|e| match e.kind() {
+            std::io::ErrorKind::UnexpectedEof => WaveError::UnexpectedEof,
+            kind => WaveError::Io(kind),
+        })
+    }
+
+    fn write_at(&mut self, offset: u64, bytes: &[u8]) -> Result<(), WaveError> {
+        self.file.seek(SeekFrom::Start(offset)).map_err(|e| WaveError::Io(e.kind()))?;
+        write_all(&mut self.file, bytes)
+    }
+}
+
+// Whether a chunk of `size` bytes fits in `room` bytes, with what's left over
+// big enough for a JUNK chunk header
+fn fits(size: u64, room: u64) -> bool {
+    size == room || size + 8 <= room
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use std::io::Cursor;
+    use super::WaveEditor;
+    use crate::bext::BroadcastExtension;
+    use crate::cue::CuePoint;
+    use crate::parser::WaveFileParser;
+    use crate::wave_file::{Sample, WaveFile};
+    use crate::writer::WaveFileWriter;
+
+    fn wave_file() -> WaveFile {
+        let mut wave_file = WaveFile {
+            channels: vec![(0..100).map(Sample::BitDepth16).collect()],
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            ..Default::default()
+        };
+        wave_file.metadata.set_title("A rather long working title".to_string());
+
+        wave_file
+    }
+
+    // Where the samples are, so we can check they didn't move
+    fn data_offset(bytes: &[u8]) -> usize {
+        bytes.windows(4).position(|w| w == b"data").unwrap()
+    }
+
+    #[test]
+    fn test_shorter_metadata_is_written_in_place() {
+        let bytes = WaveFileWriter::to_bytes(&wave_file()).unwrap();
+        let data = data_offset(&bytes);
+
+        let mut editor = WaveEditor::new(Cursor::new(bytes.clone())).unwrap();
+        assert_eq!(Some("A rather long working title"), editor.metadata.title());
+        editor.metadata.set_title("Take 3".to_string());
+        editor.save().unwrap();
+        let edited = editor.into_inner().into_inner();
+
+        assert_eq!(bytes.len(), edited.len());
+        assert_eq!(&bytes[data..], &edited[data..]);
+
+        let parsed = WaveFileParser::parse(edited).unwrap();
+        assert_eq!(Some("Take 3"), parsed.metadata.title());
+        assert_eq!(wave_file().channels, parsed.channels);
+    }
+
+    #[test]
+    fn test_chunks_that_dont_fit_go_at_the_end() {
+        let bytes = WaveFileWriter::to_bytes(&wave_file()).unwrap();
+        let data = data_offset(&bytes);
+
+        let mut editor = WaveEditor::new(Cursor::new(bytes.clone())).unwrap();
+        editor.metadata.set_comment("Recorded on the roof, mind the pigeons".to_string());
+        editor.broadcast_extension = Some(BroadcastExtension {
+            description: "Roof".to_string(),
+            ..Default::default()
+        });
+        editor.cue_points = vec![CuePoint {
+            id: 1,
+            position: 50,
+            chunk_id: *b"data",
+            chunk_start: 0,
+            block_start: 0,
+            sample_offset: 50,
+        }];
+        editor.save().unwrap();
+        let edited = editor.into_inner().into_inner();
+
+        // The samples didn't move. The bigger LIST chunk and the bext chunk went at the end,
+        // and the cue chunk fit where the old LIST chunk was.
+        assert_eq!(&bytes[data..data + 208], &edited[data..data + 208]);
+        assert_eq!(b"cue ", &edited[12 + 24..12 + 28]);
+        assert_eq!(b"JUNK", &edited[12 + 24 + 36..12 + 24 + 40]);
+        assert_eq!(&(edited.len() as u32 - 8).to_le_bytes(), &edited[4..8]);
+
+        let parsed = WaveFileParser::parse(edited).unwrap();
+        assert_eq!(Some("A rather long working title"), parsed.metadata.title());
+        assert_eq!(Some("Recorded on the roof, mind the pigeons"), parsed.metadata.comment());
+        assert_eq!(Some("Roof".to_string()), parsed.broadcast_extension.map(|b| b.description));
+        assert_eq!(50, parsed.cue_points[0].sample_offset);
+        assert_eq!(wave_file().channels, parsed.channels);
+    }
+
+    #[test]
+    fn test_removed_chunks_become_junk_and_junk_gets_reused() {
+        let bytes = WaveFileWriter::to_bytes(&wave_file()).unwrap();
+
+        let mut editor = WaveEditor::new(Cursor::new(bytes.clone())).unwrap();
+        editor.metadata = Default::default();
+        editor.save().unwrap();
+        let edited = editor.into_inner().into_inner();
+
+        assert_eq!(bytes.len(), edited.len());
+        assert!(WaveFileParser::parse(edited.clone()).unwrap().metadata.is_empty());
+
+        // The cue chunk fits in the JUNK the LIST chunk left behind
+        let mut editor = WaveEditor::new(Cursor::new(edited)).unwrap();
+        editor.cue_points = vec![CuePoint {
+            id: 7,
+            position: 0,
+            chunk_id: *b"data",
+            chunk_start: 0,
+            block_start: 0,
+            sample_offset: 10,
+        }];
+        editor.save().unwrap();
+        let edited = editor.into_inner().into_inner();
+
+        assert_eq!(bytes.len(), edited.len());
+        assert_eq!(7, WaveFileParser::parse(edited).unwrap().cue_points[0].id);
+    }
+}
@@ -0,0 +1,114 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::error::Error;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+// Everything that can go wrong while parsing (or writing) a '.wav' file.
+// Parsing stops at the first error and hands it back to the caller instead of panicking.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WaveError {
+    // The bytes ran out before we finished reading something we expected to be there
+    UnexpectedEof,
+    // A required chunk (or form/list type) could not be found. Holds the four character code.
+    MissingChunk([u8; 4]),
+    // Something other than the chunk (or form type) that has to come next
+    UnexpectedChunk { expected: [u8; 4], found: [u8; 4] },
+    // The `fmt ` chunk describes an encoding this library cannot decode. Holds the wFormatTag.
+    UnsupportedFormat(u16),
+    // The samples use a bit depth this library cannot decode
+    UnsupportedBitDepth(u16),
+    // The samples are spread over a number of channels this library cannot decode
+    UnsupportedChannelCount(u16),
+    // A chunk claims to be larger than the data that contains it
+    InvalidChunkSize(u32),
+    // The data holds fewer samples per channel than the fact chunk says. Holds the fact count.
+    SampleCountMismatch(u32),
+    // The file goes over one of the limits set in ParserOptions. Holds what the limit is on,
+    // and what it was set to.
+    LimitExceeded { name: &'static str, limit: u64 },
+    // The file has more chunks than `ParserOptions::max_chunks` allows. Holds the limit.
+    TooManyChunks(usize),
+    // The channels handed to the writer don't all hold the same number of samples
+    ChannelLengthMismatch,
+    // A sample handed to the writer doesn't match the bits per sample. Holds the bits per sample.
+    SampleFormatMismatch(u16),
+    // Text handed to the writer doesn't fit in the fixed size field it goes in.
+    // Holds the field and how many bytes fit.
+    TextTooLong { field: &'static str, limit: usize },
+    // Files being joined together aren't in the same format. Holds what differs, like "sample rate".
+    FormatMismatch(&'static str),
+    // The underlying reader or writer failed for a reason other than running out of bytes.
+    // Only readers and writers from std can fail like this.
+    #[cfg(feature = "std")]
+    Io(io::ErrorKind),
+    // Where in the file one of the other errors happened. Errors from parsing a whole file
+    // come wrapped in this. `offset` is the number of bytes read before things went wrong,
+    // and `chunk_id` the chunk being read at the time.
+    Context { offset: u64, chunk_id: Option<[u8; 4]>, error: Box<WaveError> },
+}
+
+impl WaveError {
+    // What went wrong, minus the context. This is the one to match on.
+    pub fn kind(&self) -> &WaveError {
+        match self {
+            WaveError::Context { error, .. } => error.kind(),
+            error => error,
+        }
+    }
+
+    // How far into the file the error happened, if we know
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            WaveError::Context { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+
+    // The chunk being read when the error happened, if we know
+    pub fn chunk_id(&self) -> Option<[u8; 4]> {
+        match self {
+            WaveError::Context { chunk_id, .. } => *chunk_id,
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for WaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WaveError::UnexpectedEof => write!(f, "unexpected end of file"),
+            WaveError::MissingChunk(id) => write!(f, "could not find '{}' chunk", String::from_utf8_lossy(id)),
+            WaveError::UnexpectedChunk { expected, found } => {
+                write!(f, "expected '{}' but found '{}'", String::from_utf8_lossy(expected), String::from_utf8_lossy(found))
+            }
+            WaveError::UnsupportedFormat(tag) => write!(f, "unsupported wave format 0x{:04x}", tag),
+            WaveError::UnsupportedBitDepth(bits) => write!(f, "unsupported bit depth {}", bits),
+            WaveError::UnsupportedChannelCount(count) => write!(f, "unsupported number of channels {}", count),
+            WaveError::InvalidChunkSize(size) => write!(f, "invalid chunk size {}", size),
+            WaveError::SampleCountMismatch(count) => write!(f, "data holds fewer than the {} samples the fact chunk says", count),
+            WaveError::LimitExceeded { name, limit } => write!(f, "more than the {} {} allowed", limit, name),
+            WaveError::TooManyChunks(max) => write!(f, "more than {} chunks", max),
+            WaveError::ChannelLengthMismatch => write!(f, "channels do not have the same number of samples"),
+            WaveError::SampleFormatMismatch(bits) => write!(f, "sample does not match {} bits per sample", bits),
+            WaveError::TextTooLong { field, limit } => write!(f, "{} is longer than {} bytes", field, limit),
+            WaveError::FormatMismatch(what) => write!(f, "files do not have the same {}", what),
+            #[cfg(feature = "std")]
+            WaveError::Io(kind) => write!(f, "i/o error: {:?}", kind),
+            WaveError::Context { offset, chunk_id: Some(id), error } => {
+                write!(f, "{} at byte {} (in '{}' chunk)", error, offset, String::from_utf8_lossy(id))
+            }
+            WaveError::Context { offset, chunk_id: None, error } => write!(f, "{} at byte {}", error, offset),
+        }
+    }
+}
+
+impl Error for WaveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WaveError::Context { error, .. } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
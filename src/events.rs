@@ -0,0 +1,270 @@
+// A pull parser: the file as a stream of events, for code that wants to react to each part of
+// it as it comes rather than wait for a whole `WaveFile`. Nothing is decoded or copied but the
+// fmt chunk; the samples and the other chunks come out as slices of the bytes.
+//
+//     let events = WaveEvents::new(&bytes)?;
+//     for event in events {
+//         match event? {
+//             WaveEvent::FmtParsed(fmt) => ...,
+//             WaveEvent::Samples(samples) => ...,
+//             _ => {}
+//         }
+//     }
+
+use crate::byte_stream::InMemory;
+use crate::error::WaveError;
+use crate::fmt_chunk::FmtChunk;
+use crate::options::ParserOptions;
+use crate::parser::{fourcc, ChunkParser};
+use crate::wave_file::WaveFile;
+
+// The most sample bytes handed out in one `Samples` event
+const MAX_SAMPLES_EVENT: usize = 64 * 1024;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum WaveEvent<'a> {
+    // A chunk's id and size. Everything up to the matching `ChunkEnd` is about this chunk.
+    ChunkStart([u8; 4], u32),
+    // The fmt chunk, picked apart
+    FmtParsed(FmtChunk),
+    // Some of the data chunk, as it is in the file. A data chunk comes out as one or more of
+    // these, each holding whole frames unless the chunk ends part way through one.
+    Samples(&'a [u8]),
+    // The contents of any chunk other than fmt and data, as they are in the file.
+    // Comes even when the chunk is empty.
+    ChunkData(&'a [u8]),
+    ChunkEnd,
+}
+
+// What comes next
+enum State<'a> {
+    ChunkHeader,
+    Fmt(&'a [u8]),
+    Samples(&'a [u8]),
+    ChunkData(&'a [u8]),
+    ChunkEnd,
+    Done,
+}
+
+pub struct WaveEvents<'a> {
+    bytes: &'a [u8],
+    // Where the next chunk header is
+    offset: usize,
+    // Where the RIFF chunk ends
+    end: usize,
+    form_type: [u8; 4],
+    big_endian: bool,
+    // From the fmt chunk, to keep the `Samples` events to whole frames
+    block_align: usize,
+    // Whether the chunk we're in runs past the end of the bytes
+    truncated: bool,
+    state: State<'a>,
+}
+
+impl<'a> WaveEvents<'a> {
+    // Reads the RIFF header. RIFX and RF64 files work too. In an RF64 file the data chunk
+    // doesn't have a real size, so it's taken to run to the end of the bytes.
+    pub fn new(bytes: &'a [u8]) -> Result<WaveEvents<'a>, WaveError> {
+        if bytes.len() < 12 {
+            return Err(WaveError::UnexpectedEof);
+        }
+
+        let big_endian = match &bytes[..4] {
+            b"RIFF" | b"RF64" | b"BW64" => false,
+            b"RIFX" => true,
+            _ => return Err(WaveError::UnexpectedChunk {
+                expected: *b"RIFF",
+                found: fourcc(bytes),
+            }),
+        };
+
+        let riff_size = read_u32(&bytes[4..8], big_endian);
+        let end = match riff_size {
+            0 | u32::MAX => bytes.len(),
+            riff_size => (8 + riff_size as usize).min(bytes.len()),
+        };
+
+        Ok(WaveEvents {
+            bytes,
+            offset: 12,
+            end,
+            form_type: fourcc(&bytes[8..]),
+            big_endian,
+            block_align: 0,
+            truncated: false,
+            state: State::ChunkHeader,
+        })
+    }
+
+    // What kind of RIFF file it is. "WAVE" for a '.wav' file.
+    pub fn form_type(&self) -> [u8; 4] {
+        self.form_type
+    }
+
+    fn next_event(&mut self) -> Result<Option<WaveEvent<'a>>, WaveError> {
+        match self.state {
+            State::ChunkHeader => self.read_chunk_header(),
+            State::Fmt(contents) => {
+                let fmt = self.read_fmt(contents)?;
+                self.block_align = fmt.block_align as usize;
+                self.state = State::ChunkEnd;
+
+                Ok(Some(WaveEvent::FmtParsed(fmt)))
+            }
+            State::Samples(rest) if !rest.is_empty() => {
+                // As many whole frames as fit, or what's left of the chunk
+                let mut size = rest.len().min(MAX_SAMPLES_EVENT);
+                if self.block_align > 0 && size >= self.block_align {
+                    size -= size % self.block_align;
+                }
+                let (samples, rest) = rest.split_at(size);
+                self.state = State::Samples(rest);
+
+                Ok(Some(WaveEvent::Samples(samples)))
+            }
+            State::ChunkData(contents) => {
+                self.state = State::ChunkEnd;
+
+                Ok(Some(WaveEvent::ChunkData(contents)))
+            }
+            State::Samples(_) | State::ChunkEnd => {
+                // Whatever there was of a cut off chunk has been handed out
+                if self.truncated {
+                    return Err(WaveError::UnexpectedEof);
+                }
+                self.state = State::ChunkHeader;
+
+                Ok(Some(WaveEvent::ChunkEnd))
+            }
+            State::Done => Ok(None),
+        }
+    }
+
+    fn read_chunk_header(&mut self) -> Result<Option<WaveEvent<'a>>, WaveError> {
+        if self.offset >= self.end {
+            return Ok(None);
+        }
+        if self.end - self.offset < 8 {
+            return Err(WaveError::UnexpectedEof);
+        }
+
+        let id = fourcc(&self.bytes[self.offset..]);
+        let size = read_u32(&self.bytes[self.offset + 4..], self.big_endian);
+        let start = self.offset + 8;
+
+        // The data chunk of an RF64 file (or one from a streaming writer) runs to the end
+        let wanted = if id == *b"data" && size == u32::MAX { self.end - start } else { size as usize };
+        let contents = &self.bytes[start..start + wanted.min(self.end - start)];
+        self.truncated = contents.len() < wanted;
+
+        // On to the next chunk, past the pad byte if there is one
+        self.offset = start + contents.len() + contents.len() % 2;
+
+        self.state = match &id {
+            b"fmt " => State::Fmt(contents),
+            b"data" => State::Samples(contents),
+            _ => State::ChunkData(contents),
+        };
+
+        Ok(Some(WaveEvent::ChunkStart(id, size)))
+    }
+
+    // The fmt chunk is picked apart by the parser, so it comes out the same as it would there
+    fn read_fmt(&self, contents: &[u8]) -> Result<FmtChunk, WaveError> {
+        if self.truncated {
+            return Err(WaveError::UnexpectedEof);
+        }
+
+        let mut parser = ChunkParser::new(InMemory::new(contents));
+        parser.byte_stream.big_endian = self.big_endian;
+        // Whether a format can be decoded is up to whoever gets the events
+        parser.options = ParserOptions::new().allow_unknown_formats(true);
+
+        parser.read_fmt_contents(&mut WaveFile::default(), contents.len() as u32)?;
+
+        parser.fmt.ok_or(WaveError::MissingChunk(*b"fmt "))
+    }
+}
+
+impl<'a> Iterator for WaveEvents<'a> {
+    type Item = Result<WaveEvent<'a>, WaveError>;
+
+    // Stops after the first error, since there is no telling what comes after it
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = self.next_event().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.state = State::Done;
+        }
+
+        result
+    }
+}
+
+fn read_u32(bytes: &[u8], big_endian: bool) -> u32 {
+    let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+
+    if big_endian {
+        u32::from_be_bytes(bytes)
+    } else {
+        u32::from_le_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::{WaveEvent, WaveEvents};
+    use crate::error::WaveError;
+    use crate::fmt_chunk::FmtChunk;
+    use crate::test_utils::{fmt_bytes, riff_bytes};
+
+    #[test]
+    fn test_events_for_a_file() {
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 2, 16)), (b"junk", b"abc"), (b"data", &[1, 2, 3, 4, 5, 6, 7, 8])]);
+
+        let events = WaveEvents::new(&bytes).unwrap();
+        assert_eq!(*b"WAVE", events.form_type());
+
+        let events: Vec<WaveEvent> = events.map(|e| e.unwrap()).collect();
+        assert_eq!(
+            vec![
+                WaveEvent::ChunkStart(*b"fmt ", 16),
+                WaveEvent::FmtParsed(FmtChunk::pcm(2, 8000, 16).unwrap()),
+                WaveEvent::ChunkEnd,
+                WaveEvent::ChunkStart(*b"junk", 3),
+                WaveEvent::ChunkData(b"abc"),
+                WaveEvent::ChunkEnd,
+                WaveEvent::ChunkStart(*b"data", 8),
+                WaveEvent::Samples(&[1, 2, 3, 4, 5, 6, 7, 8]),
+                WaveEvent::ChunkEnd,
+            ],
+            events
+        );
+    }
+
+    #[test]
+    fn test_samples_come_in_whole_frames() {
+        // 3 byte frames don't divide the 64 KiB evenly
+        let data = vec![0; 99_999];
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 24)), (b"data", &data)]);
+
+        let sizes: Vec<usize> = WaveEvents::new(&bytes)
+            .unwrap()
+            .filter_map(|e| match e.unwrap() {
+                WaveEvent::Samples(samples) => Some(samples.len()),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(vec![65_535, 34_464], sizes);
+    }
+
+    #[test]
+    fn test_truncated_data_chunk_ends_with_error() {
+        let mut bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"data", &[0x80; 4])]);
+        bytes.truncate(bytes.len() - 1);
+
+        let events: Vec<_> = WaveEvents::new(&bytes).unwrap().skip(3).collect();
+
+        assert_eq!(vec![Ok(WaveEvent::ChunkStart(*b"data", 4)), Ok(WaveEvent::Samples(&[0x80; 3])), Err(WaveError::UnexpectedEof)], events);
+    }
+}
@@ -0,0 +1,195 @@
+// A C interface, for using the parser from C and C++ as a shared library.
+// Turn on the `ffi` feature and build a shared library with
+// `cargo rustc --lib --features ffi --crate-type cdylib`. The functions are cbindgen friendly:
+//
+//     WaveFile *wave = NULL;
+//     int32_t error = wave_parse(bytes, len, &wave);
+//     if (error != WAVE_OK) { puts(wave_error_message(error)); return; }
+//     size_t frames = wave_num_frames(wave);
+//     ...
+//     wave_free(wave);
+//
+// A `WaveFile` pointer is owned by the caller once `wave_parse` hands it over, and has to go back
+// to `wave_free` exactly once. Every function that takes one is fine with it being null.
+// The safety requirements are the usual ones for C: pointers point at what they say they do.
+#![allow(clippy::missing_safety_doc)]
+
+use std::os::raw::c_char;
+use std::ptr;
+use std::slice;
+
+use crate::convert::FromSample;
+use crate::error::WaveError;
+use crate::parser::WaveFileParser;
+use crate::wave_file::WaveFile;
+
+// The error codes. These never change value, so they're safe to bake into C code.
+pub const WAVE_OK: i32 = 0;
+pub const WAVE_ERROR_NULL_POINTER: i32 = 1;
+pub const WAVE_ERROR_UNEXPECTED_EOF: i32 = 2;
+pub const WAVE_ERROR_MISSING_CHUNK: i32 = 3;
+pub const WAVE_ERROR_UNEXPECTED_CHUNK: i32 = 4;
+pub const WAVE_ERROR_UNSUPPORTED_FORMAT: i32 = 5;
+pub const WAVE_ERROR_UNSUPPORTED_BIT_DEPTH: i32 = 6;
+pub const WAVE_ERROR_UNSUPPORTED_CHANNEL_COUNT: i32 = 7;
+pub const WAVE_ERROR_INVALID_CHUNK_SIZE: i32 = 8;
+pub const WAVE_ERROR_SAMPLE_COUNT_MISMATCH: i32 = 9;
+pub const WAVE_ERROR_LIMIT_EXCEEDED: i32 = 10;
+pub const WAVE_ERROR_OTHER: i32 = 11;
+
+fn error_code(error: &WaveError) -> i32 {
+    match error.kind() {
+        WaveError::UnexpectedEof => WAVE_ERROR_UNEXPECTED_EOF,
+        WaveError::MissingChunk(_) => WAVE_ERROR_MISSING_CHUNK,
+        WaveError::UnexpectedChunk { .. } => WAVE_ERROR_UNEXPECTED_CHUNK,
+        WaveError::UnsupportedFormat(_) => WAVE_ERROR_UNSUPPORTED_FORMAT,
+        WaveError::UnsupportedBitDepth(_) => WAVE_ERROR_UNSUPPORTED_BIT_DEPTH,
+        WaveError::UnsupportedChannelCount(_) => WAVE_ERROR_UNSUPPORTED_CHANNEL_COUNT,
+        WaveError::InvalidChunkSize(_) => WAVE_ERROR_INVALID_CHUNK_SIZE,
+        WaveError::SampleCountMismatch(_) => WAVE_ERROR_SAMPLE_COUNT_MISMATCH,
+        WaveError::LimitExceeded { .. } | WaveError::TooManyChunks(_) => WAVE_ERROR_LIMIT_EXCEEDED,
+        _ => WAVE_ERROR_OTHER,
+    }
+}
+
+// Parse the `len` bytes at `bytes`. On success `*out` is set to the parsed file, otherwise it's
+// set to null and the error code comes back. The bytes are copied, so they can go right after.
+// The default limits apply, so a file that would decode to more than 4 GiB of samples is
+// `WAVE_ERROR_LIMIT_EXCEEDED`.
+#[no_mangle]
+pub unsafe extern "C" fn wave_parse(bytes: *const u8, len: usize, out: *mut *mut WaveFile) -> i32 {
+    if out.is_null() {
+        return WAVE_ERROR_NULL_POINTER;
+    }
+    *out = ptr::null_mut();
+    if bytes.is_null() {
+        return WAVE_ERROR_NULL_POINTER;
+    }
+
+    match WaveFileParser::parse(slice::from_raw_parts(bytes, len).to_vec()) {
+        Ok(wave_file) => {
+            *out = Box::into_raw(Box::new(wave_file));
+            WAVE_OK
+        }
+        Err(error) => error_code(&error),
+    }
+}
+
+// Give back a file from `wave_parse`
+#[no_mangle]
+pub unsafe extern "C" fn wave_free(wave_file: *mut WaveFile) {
+    if !wave_file.is_null() {
+        drop(Box::from_raw(wave_file));
+    }
+}
+
+// A description of an error code, as a static null terminated string
+#[no_mangle]
+pub extern "C" fn wave_error_message(code: i32) -> *const c_char {
+    let message: &'static [u8] = match code {
+        WAVE_OK => b"no error\0",
+        WAVE_ERROR_NULL_POINTER => b"null pointer\0",
+        WAVE_ERROR_UNEXPECTED_EOF => b"unexpected end of file\0",
+        WAVE_ERROR_MISSING_CHUNK => b"missing chunk\0",
+        WAVE_ERROR_UNEXPECTED_CHUNK => b"unexpected chunk\0",
+        WAVE_ERROR_UNSUPPORTED_FORMAT => b"unsupported format\0",
+        WAVE_ERROR_UNSUPPORTED_BIT_DEPTH => b"unsupported bit depth\0",
+        WAVE_ERROR_UNSUPPORTED_CHANNEL_COUNT => b"unsupported channel count\0",
+        WAVE_ERROR_INVALID_CHUNK_SIZE => b"invalid chunk size\0",
+        WAVE_ERROR_SAMPLE_COUNT_MISMATCH => b"sample count mismatch\0",
+        WAVE_ERROR_LIMIT_EXCEEDED => b"limit exceeded\0",
+        _ => b"unknown error\0",
+    };
+
+    message.as_ptr() as *const c_char
+}
+
+// The accessors below return 0 for a null file
+
+#[no_mangle]
+pub unsafe extern "C" fn wave_num_channels(wave_file: *const WaveFile) -> u16 {
+    wave_file.as_ref().map_or(0, |w| w.channels.len() as u16)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wave_sample_rate(wave_file: *const WaveFile) -> u32 {
+    wave_file.as_ref().map_or(0, |w| w.sample_rate)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wave_bits_per_sample(wave_file: *const WaveFile) -> u16 {
+    wave_file.as_ref().map_or(0, |w| w.bits_per_sample)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wave_format_tag(wave_file: *const WaveFile) -> u16 {
+    wave_file.as_ref().map_or(0, |w| w.format_tag)
+}
+
+// The number of frames (one sample per channel)
+#[no_mangle]
+pub unsafe extern "C" fn wave_num_frames(wave_file: *const WaveFile) -> u64 {
+    wave_file.as_ref().map_or(0, |w| w.num_frames())
+}
+
+// Copy up to `len` samples of `channel`, scaled to between -1.0 and 1.0, into `out`.
+// Returns how many were copied: 0 when the channel doesn't exist.
+#[no_mangle]
+pub unsafe extern "C" fn wave_read_channel_f32(wave_file: *const WaveFile, channel: usize, out: *mut f32, len: usize) -> usize {
+    let channel = match wave_file.as_ref().and_then(|w| w.channels.get(channel)) {
+        Some(channel) if !out.is_null() => channel,
+        _ => return 0,
+    };
+
+    let out = slice::from_raw_parts_mut(out, len);
+    for (out, sample) in out.iter_mut().zip(channel.iter()) {
+        *out = f32::from_sample(sample.clone());
+    }
+
+    len.min(channel.len())
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use std::ffi::CStr;
+    use std::ptr;
+    use super::*;
+    use crate::testing::WaveFixture;
+
+    #[test]
+    fn test_parse_read_and_free() {
+        let bytes = WaveFixture::new(2, 8, 8000).num_frames(3).to_bytes();
+        let mut wave_file = ptr::null_mut();
+
+        unsafe {
+            assert_eq!(WAVE_OK, wave_parse(bytes.as_ptr(), bytes.len(), &mut wave_file));
+            assert_eq!(2, wave_num_channels(wave_file));
+            assert_eq!(8000, wave_sample_rate(wave_file));
+            assert_eq!(3, wave_num_frames(wave_file));
+
+            let mut samples = [9.0f32; 4];
+            assert_eq!(3, wave_read_channel_f32(wave_file, 1, samples.as_mut_ptr(), samples.len()));
+            assert_eq!([-121.0 / 128.0, -120.0 / 128.0, -119.0 / 128.0, 9.0], samples);
+            assert_eq!(0, wave_read_channel_f32(wave_file, 2, samples.as_mut_ptr(), samples.len()));
+
+            wave_free(wave_file);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let bytes = WaveFixture::new(1, 16, 8000).num_frames(10).to_bytes();
+        let mut wave_file = ptr::null_mut();
+
+        unsafe {
+            assert_eq!(WAVE_ERROR_UNEXPECTED_EOF, wave_parse(bytes.as_ptr(), 20, &mut wave_file));
+            assert!(wave_file.is_null());
+            assert_eq!(WAVE_ERROR_NULL_POINTER, wave_parse(ptr::null(), 0, &mut wave_file));
+            assert_eq!(0, wave_num_frames(ptr::null()));
+            wave_free(ptr::null_mut());
+
+            let message = CStr::from_ptr(wave_error_message(WAVE_ERROR_UNEXPECTED_EOF));
+            assert_eq!("unexpected end of file", message.to_str().unwrap());
+        }
+    }
+}
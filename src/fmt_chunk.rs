@@ -0,0 +1,158 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::WaveError;
+use crate::parser::{KSDATAFORMAT_SUBTYPE_SUFFIX, WAVE_FORMAT_EXTENSIBLE};
+use crate::wave_file::WaveFormatCategory;
+
+// The fields of the `fmt ` chunk, which describe how the samples are laid out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FmtChunk {
+    pub format_tag: u16,
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub byte_rate: u32,
+    pub block_align: u16,
+    pub bits_per_sample: u16,
+    // Whatever comes after cbSize. Compressed formats keep the settings for their
+    // decoder here. Empty for PCM and float.
+    pub extension: Vec<u8>,
+}
+
+impl FmtChunk {
+    // A PCM format description, with the block align and byte rate worked out for you
+    pub fn pcm(channels: u16, sample_rate: u32, bits_per_sample: u16) -> Result<FmtChunk, WaveError> {
+        FmtChunk::new(WaveFormatCategory::WAVE_FORMAT_PCM, channels, sample_rate, bits_per_sample)
+    }
+
+    // Same as `pcm`, but for any of the formats this library can write.
+    // That is PCM and IEEE float, the compressed formats can only be read.
+    pub fn new(wave_format: WaveFormatCategory, channels: u16, sample_rate: u32, bits_per_sample: u16) -> Result<FmtChunk, WaveError> {
+        match wave_format {
+            WaveFormatCategory::WAVE_FORMAT_PCM | WaveFormatCategory::WAVE_FORMAT_IEEE_FLOAT => {}
+            _ => return Err(WaveError::UnsupportedFormat(wave_format as u16)),
+        }
+        if wave_format == WaveFormatCategory::WAVE_FORMAT_IEEE_FLOAT && bits_per_sample != 32 && bits_per_sample != 64 {
+            return Err(WaveError::UnsupportedBitDepth(bits_per_sample));
+        }
+
+        if channels == 0 {
+            return Err(WaveError::UnsupportedChannelCount(channels));
+        }
+
+        let block_align = channels as u32 * bytes_per_sample(bits_per_sample)?;
+        if block_align > u16::MAX as u32 {
+            return Err(WaveError::UnsupportedChannelCount(channels));
+        }
+
+        Ok(FmtChunk {
+            format_tag: wave_format as u16,
+            channels,
+            sample_rate,
+            byte_rate: sample_rate.saturating_mul(block_align),
+            block_align: block_align as u16,
+            bits_per_sample,
+            extension: vec![],
+        })
+    }
+
+    // The same format as WAVE_FORMAT_EXTENSIBLE, with the format tag moved into the SubFormat GUID.
+    // Needed for more than two channels or more than 16 bits per sample, and for saying how many
+    // of the bits are used or which speakers the channels are for. A mask of 0 leaves that open.
+    pub fn extensible(mut self, valid_bits_per_sample: u16, channel_mask: u32) -> FmtChunk {
+        let format_tag = self.sub_format_tag();
+
+        self.extension = vec![];
+        self.extension.extend_from_slice(&valid_bits_per_sample.to_le_bytes());
+        self.extension.extend_from_slice(&channel_mask.to_le_bytes());
+        self.extension.extend_from_slice(&format_tag.to_le_bytes());
+        self.extension.extend_from_slice(&KSDATAFORMAT_SUBTYPE_SUFFIX);
+        self.format_tag = WAVE_FORMAT_EXTENSIBLE;
+
+        self
+    }
+
+    // The SubFormat GUID of an extensible format, as it appears in the file
+    pub fn sub_format(&self) -> Option<[u8; 16]> {
+        match self.extension.get(6..22) {
+            Some(sub_format) if self.format_tag == WAVE_FORMAT_EXTENSIBLE => {
+                let mut guid = [0; 16];
+                guid.copy_from_slice(sub_format);
+                Some(guid)
+            }
+            _ => None,
+        }
+    }
+
+    // The format tag, or for an extensible format, the one in the SubFormat GUID
+    pub(crate) fn sub_format_tag(&self) -> u16 {
+        match self.extension.get(6..22) {
+            Some(sub_format) if self.format_tag == WAVE_FORMAT_EXTENSIBLE && sub_format[2..] == KSDATAFORMAT_SUBTYPE_SUFFIX => {
+                u16::from_le_bytes([sub_format[0], sub_format[1]])
+            }
+            _ => self.format_tag,
+        }
+    }
+
+    // The whole chunk, header included, as it would appear in a file.
+    // Formats other than PCM are supposed to carry a cbSize field, even if it's 0.
+    #[cfg(feature = "std")]
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let is_pcm = self.format_tag == WaveFormatCategory::WAVE_FORMAT_PCM as u16 && self.extension.is_empty();
+        let mut bytes = vec![];
+
+        let size = if is_pcm { 16 } else { 18 + self.extension.len() as u32 };
+        bytes.extend_from_slice(b"fmt ");
+        bytes.extend_from_slice(&size.to_le_bytes());
+        bytes.extend_from_slice(&self.format_tag.to_le_bytes());
+        bytes.extend_from_slice(&self.channels.to_le_bytes());
+        bytes.extend_from_slice(&self.sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&self.byte_rate.to_le_bytes());
+        bytes.extend_from_slice(&self.block_align.to_le_bytes());
+        bytes.extend_from_slice(&self.bits_per_sample.to_le_bytes());
+        if !is_pcm {
+            bytes.extend_from_slice(&(self.extension.len() as u16).to_le_bytes());
+            bytes.extend_from_slice(&self.extension);
+            if self.extension.len() % 2 == 1 {
+                bytes.push(0);
+            }
+        }
+
+        bytes
+    }
+}
+
+// How many bytes a single sample takes up in the data chunk
+pub(crate) fn bytes_per_sample(bits_per_sample: u16) -> Result<u32, WaveError> {
+    match bits_per_sample {
+        1..=8 => Ok(1),
+        9..=16 => Ok(2),
+        17..=24 => Ok(3),
+        25..=32 => Ok(4),
+        33..=64 => Ok(8),
+        _ => Err(WaveError::UnsupportedBitDepth(bits_per_sample)),
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::FmtChunk;
+    use crate::parser::WAVE_FORMAT_EXTENSIBLE;
+    use crate::wave_file::WaveFormatCategory;
+
+    #[test]
+    fn test_extensible_keeps_the_format_in_the_sub_format() {
+        let fmt = FmtChunk::new(WaveFormatCategory::WAVE_FORMAT_IEEE_FLOAT, 6, 48000, 32).unwrap().extensible(32, 0x3f);
+
+        assert_eq!(WAVE_FORMAT_EXTENSIBLE, fmt.format_tag);
+        assert_eq!(WaveFormatCategory::WAVE_FORMAT_IEEE_FLOAT as u16, fmt.sub_format_tag());
+        assert_eq!(24, fmt.block_align);
+
+        let bytes = fmt.to_bytes();
+        // 18 bytes of the plain format, then the 22 byte extension
+        assert_eq!(&40u32.to_le_bytes(), &bytes[4..8]);
+        assert_eq!(&22u16.to_le_bytes(), &bytes[24..26]);
+        assert_eq!(&0x3fu32.to_le_bytes(), &bytes[28..32]);
+        assert_eq!(&[0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00], &bytes[32..40]);
+    }
+}
@@ -0,0 +1,253 @@
+use alloc::vec::Vec;
+
+use crate::wave_file::Sample;
+
+// One sample from each channel, all at the same point in time
+#[derive(Clone, Copy, Debug)]
+pub struct Frame<'a> {
+    channels: &'a [Vec<Sample>],
+    index: usize,
+}
+
+impl<'a> Frame<'a> {
+    // Which frame this is, counting from the start of the file
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    // The number of channels
+    pub fn len(&self) -> usize {
+        self.channels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+    }
+
+    // The sample for `channel`
+    pub fn get(&self, channel: usize) -> Option<&'a Sample> {
+        self.channels.get(channel).and_then(|c| c.get(self.index))
+    }
+
+    // The samples in channel order
+    pub fn iter(&self) -> impl Iterator<Item = &'a Sample> {
+        let index = self.index;
+        self.channels.iter().map(move |c| &c[index])
+    }
+}
+
+// Walks the channels a frame at a time. Made by `WaveFile::frames`.
+// Stops at the end of the shortest channel.
+pub struct Frames<'a> {
+    channels: &'a [Vec<Sample>],
+    next: usize,
+    len: usize,
+}
+
+impl<'a> Frames<'a> {
+    pub(crate) fn new(channels: &'a [Vec<Sample>]) -> Frames<'a> {
+        let len = channels.iter().map(|c| c.len()).min().unwrap_or(0);
+
+        Frames { channels, next: 0, len }
+    }
+}
+
+impl<'a> Iterator for Frames<'a> {
+    type Item = Frame<'a>;
+
+    fn next(&mut self) -> Option<Frame<'a>> {
+        if self.next >= self.len {
+            return None;
+        }
+
+        let frame = Frame {
+            channels: self.channels,
+            index: self.next,
+        };
+        self.next += 1;
+
+        Some(frame)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.next;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Frames<'_> {}
+
+// A block of frames: the same stretch of each channel
+#[derive(Clone, Copy, Debug)]
+pub struct Window<'a> {
+    channels: &'a [Vec<Sample>],
+    start: usize,
+    end: usize,
+}
+
+impl<'a> Window<'a> {
+    // The first frame in the window, counting from the start of the file
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    // The number of frames
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    // The samples of `channel` in the window
+    pub fn channel(&self, channel: usize) -> Option<&'a [Sample]> {
+        self.channels.get(channel).map(|c| &c[self.start..self.end])
+    }
+
+    // The samples of each channel in the window, in channel order
+    pub fn channels(&self) -> impl Iterator<Item = &'a [Sample]> {
+        let (start, end) = (self.start, self.end);
+        self.channels.iter().map(move |c| &c[start..end])
+    }
+}
+
+// Walks the channels in blocks of `window_size` frames, each one starting `hop_size` frames
+// after the last. Made by `WaveFile::windows`.
+// Only whole windows come out, so the frames at the end that don't fill one are left out.
+pub struct Windows<'a> {
+    channels: &'a [Vec<Sample>],
+    window_size: usize,
+    hop_size: usize,
+    next: usize,
+    len: usize,
+}
+
+impl<'a> Windows<'a> {
+    pub(crate) fn new(channels: &'a [Vec<Sample>], window_size: usize, hop_size: usize) -> Windows<'a> {
+        assert!(window_size > 0, "window size can't be 0");
+        assert!(hop_size > 0, "hop size can't be 0");
+
+        let len = channels.iter().map(|c| c.len()).min().unwrap_or(0);
+
+        Windows {
+            channels,
+            window_size,
+            hop_size,
+            next: 0,
+            len,
+        }
+    }
+
+    // A window or hop so big it runs past the end of a usize is as good as running past the
+    // end of the channels
+    fn remaining(&self) -> usize {
+        match self.next.checked_add(self.window_size) {
+            Some(end) if end <= self.len => (self.len - end) / self.hop_size + 1,
+            _ => 0,
+        }
+    }
+}
+
+impl<'a> Iterator for Windows<'a> {
+    type Item = Window<'a>;
+
+    fn next(&mut self) -> Option<Window<'a>> {
+        if self.remaining() == 0 {
+            return None;
+        }
+
+        let window = Window {
+            channels: self.channels,
+            start: self.next,
+            end: self.next + self.window_size,
+        };
+        self.next = self.next.saturating_add(self.hop_size);
+
+        Some(window)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.remaining();
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Windows<'_> {}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::{Frames, Windows};
+    use crate::wave_file::Sample;
+
+    #[test]
+    fn test_frames_line_up_the_channels() {
+        let channels = vec![
+            vec![Sample::BitDepth16(1), Sample::BitDepth16(2)],
+            vec![Sample::BitDepth16(-1), Sample::BitDepth16(-2)],
+        ];
+
+        let frames: Vec<Vec<Sample>> = Frames::new(&channels).map(|f| f.iter().cloned().collect()).collect();
+
+        assert_eq!(vec![vec![Sample::BitDepth16(1), Sample::BitDepth16(-1)], vec![Sample::BitDepth16(2), Sample::BitDepth16(-2)]], frames);
+    }
+
+    #[test]
+    fn test_frames_stop_at_the_shortest_channel() {
+        let channels = vec![vec![Sample::BitDepth8(1); 3], vec![Sample::BitDepth8(2); 2]];
+        let mut frames = Frames::new(&channels);
+
+        assert_eq!(2, frames.len());
+        let second = frames.nth(1).unwrap();
+        assert_eq!(1, second.index());
+        assert_eq!(Some(&Sample::BitDepth8(2)), second.get(1));
+        assert_eq!(None, second.get(2));
+        assert!(frames.next().is_none());
+    }
+
+    #[test]
+    fn test_windows_hop_along_the_channels() {
+        let channels = vec![(0..10).map(Sample::BitDepth8).collect::<Vec<Sample>>(), (10..20).map(Sample::BitDepth8).collect()];
+
+        let windows: Vec<(usize, Vec<&[Sample]>)> = Windows::new(&channels, 4, 3).map(|w| (w.start(), w.channels().collect())).collect();
+
+        // Frame 9 doesn't fill a window, so it's left out
+        assert_eq!(3, windows.len());
+        assert_eq!(6, windows[2].0);
+        assert_eq!(&(6..10).map(Sample::BitDepth8).collect::<Vec<Sample>>()[..], windows[2].1[0]);
+        assert_eq!(&(16..20).map(Sample::BitDepth8).collect::<Vec<Sample>>()[..], windows[2].1[1]);
+    }
+
+    #[test]
+    fn test_overlapping_windows() {
+        let channels = vec![vec![Sample::BitDepth16(0); 1024]];
+        let mut windows = Windows::new(&channels, 256, 128);
+
+        assert_eq!(7, windows.len());
+        let last = windows.nth(6).unwrap();
+        assert_eq!(768, last.start());
+        assert_eq!(Some(256), last.channel(0).map(|c| c.len()));
+        assert_eq!(None, last.channel(1));
+        assert!(windows.next().is_none());
+    }
+
+    #[test]
+    fn test_no_windows_when_the_file_is_too_short() {
+        let channels = vec![vec![Sample::BitDepth8(0); 3]];
+
+        assert_eq!(0, Windows::new(&channels, 4, 1).count());
+        assert_eq!(0, Windows::new(&[], 4, 1).count());
+    }
+
+    #[test]
+    fn test_windows_with_huge_sizes() {
+        let channels = vec![vec![Sample::BitDepth8(0); 10]];
+
+        let mut windows = Windows::new(&channels, 4, usize::MAX);
+        assert_eq!(Some(0), windows.next().map(|w| w.start()));
+        assert!(windows.next().is_none());
+        assert_eq!(0, windows.len());
+
+        assert_eq!(0, Windows::new(&channels, usize::MAX, 1).count());
+    }
+}
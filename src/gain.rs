@@ -0,0 +1,116 @@
+// Turning the volume up or down. Only with std, since working out the gain from decibels
+// needs `f64::powf`.
+
+use crate::convert::{FromSample, I24};
+use crate::wave_file::{Sample, WaveFile};
+
+// Where soft clipping starts to bend the samples, as a fraction of full scale
+const SOFT_CLIP_KNEE: f64 = 0.5;
+
+impl WaveFile {
+    // Scales every sample by `db` decibels: 6 about doubles it, -6 about halves it.
+    // Integer samples that go past full scale are clamped to the most their bit depth holds.
+    // Float samples are left as they come out, past 1.0 or not, since float files can hold that.
+    pub fn apply_gain_db(&mut self, db: f32) {
+        self.scale(db, false);
+    }
+
+    // Same as `apply_gain_db`, but samples past half of full scale are squeezed smoothly towards
+    // full scale instead of being cut off there, which sounds a lot less harsh. This goes for
+    // float samples too, so they stay between -1.0 and 1.0.
+    pub fn apply_gain_db_soft_clipped(&mut self, db: f32) {
+        self.scale(db, true);
+    }
+
+    fn scale(&mut self, db: f32, soft_clip: bool) {
+        let gain = 10f64.powf(db as f64 / 20.0);
+
+        for channel in self.channels.iter_mut() {
+            for sample in channel.iter_mut() {
+                let mut value = f64::from_sample(sample.clone()) * gain;
+                if soft_clip {
+                    value = soft_clipped(value);
+                }
+
+                *sample = match *sample {
+                    Sample::BitDepth8(_) => Sample::BitDepth8(u8::from_sample(value)),
+                    Sample::BitDepth16(_) => Sample::BitDepth16(i16::from_sample(value)),
+                    Sample::BitDepth24(_) => Sample::BitDepth24(I24::from_sample(value).get()),
+                    Sample::BitDepth32(_) => Sample::BitDepth32(i32::from_sample(value)),
+                    Sample::Float32(_) => Sample::Float32(value as f32),
+                    Sample::Float64(_) => Sample::Float64(value),
+                };
+            }
+        }
+
+        // The peaks have moved
+        self.peak_envelope = None;
+    }
+}
+
+// Straight through up to the knee, then bending over so it gets closer and closer to full scale
+// without getting there. The bend starts at the same slope, so there's no kink at the knee.
+fn soft_clipped(value: f64) -> f64 {
+    let magnitude = value.abs();
+    if magnitude <= SOFT_CLIP_KNEE {
+        return value;
+    }
+
+    let over = (magnitude - SOFT_CLIP_KNEE) / (1.0 - SOFT_CLIP_KNEE);
+    let bent = SOFT_CLIP_KNEE + (1.0 - SOFT_CLIP_KNEE) * over / (1.0 + over);
+
+    bent.copysign(value)
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use crate::convert::FromSample;
+    use crate::wave_file::{Sample, WaveFile, WaveFormatCategory};
+
+    #[test]
+    fn test_gain_in_db() {
+        let mut wave_file = WaveFile {
+            channels: vec![vec![Sample::BitDepth16(1000), Sample::BitDepth16(-1000)], vec![Sample::BitDepth8(0x90), Sample::BitDepth8(0x80)]],
+            ..Default::default()
+        };
+
+        wave_file.apply_gain_db(-6.0206);
+
+        assert_eq!(vec![Sample::BitDepth16(500), Sample::BitDepth16(-500)], wave_file.channels[0]);
+        assert_eq!(vec![Sample::BitDepth8(0x88), Sample::BitDepth8(0x80)], wave_file.channels[1]);
+    }
+
+    #[test]
+    fn test_gain_clamps_integers_but_not_floats() {
+        let mut wave_file = WaveFile {
+            channels: vec![vec![Sample::BitDepth24(8_000_000), Sample::BitDepth24(-8_000_000)]],
+            ..Default::default()
+        };
+        wave_file.apply_gain_db(12.0);
+        assert_eq!(vec![Sample::BitDepth24(8_388_607), Sample::BitDepth24(-8_388_608)], wave_file.channels[0]);
+
+        let mut wave_file = WaveFile {
+            channels: vec![vec![Sample::Float32(0.75)]],
+            wave_format: WaveFormatCategory::WAVE_FORMAT_IEEE_FLOAT,
+            ..Default::default()
+        };
+        wave_file.apply_gain_db(6.0206);
+        assert_eq!(vec![Sample::Float32(1.5)], wave_file.channels[0]);
+    }
+
+    #[test]
+    fn test_soft_clipped_gain() {
+        let mut wave_file = WaveFile {
+            channels: vec![vec![Sample::Float64(0.2), Sample::Float64(0.9), Sample::Float64(-0.9)]],
+            ..Default::default()
+        };
+
+        wave_file.apply_gain_db_soft_clipped(20.0);
+
+        let samples: Vec<f64> = wave_file.channels[0].iter().map(|s| f64::from_sample(s.clone())).collect();
+        // 2.0 is 3 past the knee, so it bends to 3/4 of the way from the knee to full scale
+        assert!((samples[0] - 0.875).abs() < 1e-9);
+        assert!(samples[1] > samples[0] && samples[1] < 1.0);
+        assert_eq!(-samples[1], samples[2]);
+    }
+}
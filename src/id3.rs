@@ -0,0 +1,165 @@
+// An ID3v2 tag, as some consumer tools embed in an `id3 ` chunk.
+// The tag is kept as it is so it can be handed to a proper ID3 library. The accessors only
+// pull out the common text frames, which covers what most people want from it.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+const BYTES_HEADER: usize = 10;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Id3Tag {
+    // The whole tag, header included
+    pub bytes: Vec<u8>,
+}
+
+impl Id3Tag {
+    // 3 for ID3v2.3, 4 for ID3v2.4
+    pub fn major_version(&self) -> Option<u8> {
+        if self.bytes.len() >= BYTES_HEADER && &self.bytes[..3] == b"ID3" {
+            Some(self.bytes[3])
+        } else {
+            None
+        }
+    }
+
+    // TIT2
+    pub fn title(&self) -> Option<String> {
+        self.text_frame(b"TIT2")
+    }
+
+    // TPE1
+    pub fn artist(&self) -> Option<String> {
+        self.text_frame(b"TPE1")
+    }
+
+    // TALB
+    pub fn album(&self) -> Option<String> {
+        self.text_frame(b"TALB")
+    }
+
+    // The text of a T___ frame
+    pub fn text_frame(&self, id: &[u8; 4]) -> Option<String> {
+        self.frame(id).and_then(decode_text)
+    }
+
+    // The contents of the first frame with `id`.
+    // Unsynchronised and compressed tags aren't handled, those give back None.
+    pub fn frame(&self, id: &[u8; 4]) -> Option<&[u8]> {
+        let version = self.major_version()?;
+        if version != 3 && version != 4 {
+            return None;
+        }
+
+        let flags = self.bytes[5];
+        if flags & 0x80 != 0 {
+            return None;
+        }
+
+        let tag_end = (BYTES_HEADER + synchsafe(&self.bytes[6..10]) as usize).min(self.bytes.len());
+        let mut offset = BYTES_HEADER;
+
+        // Skip the extended header. Its size counts itself in v2.4 but not in v2.3.
+        if flags & 0x40 != 0 {
+            let size_bytes = self.bytes.get(offset..offset + 4)?;
+            offset += match version {
+                3 => 4 + u32::from_be_bytes([size_bytes[0], size_bytes[1], size_bytes[2], size_bytes[3]]) as usize,
+                _ => synchsafe(size_bytes) as usize,
+            };
+        }
+
+        while offset + BYTES_HEADER <= tag_end {
+            let header = &self.bytes[offset..offset + BYTES_HEADER];
+            // The rest of the tag is padding
+            if header[0] == 0 {
+                return None;
+            }
+
+            let size = match version {
+                3 => u32::from_be_bytes([header[4], header[5], header[6], header[7]]),
+                _ => synchsafe(&header[4..8]),
+            } as usize;
+            let contents = self.bytes.get(offset + BYTES_HEADER..offset + BYTES_HEADER + size)?;
+
+            if &header[..4] == id {
+                return Some(contents);
+            }
+            offset += BYTES_HEADER + size;
+        }
+
+        None
+    }
+}
+
+// Sizes in ID3 headers only use the low 7 bits of each byte
+fn synchsafe(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0, |size, &b| size << 7 | (b & 0x7f) as u32)
+}
+
+// Text frames start with a byte saying how the text is encoded
+fn decode_text(contents: &[u8]) -> Option<String> {
+    let (&encoding, text) = contents.split_first()?;
+
+    let text = match encoding {
+        // ISO-8859-1, which maps straight onto the first 256 code points
+        0 => text.iter().map(|&b| b as char).collect(),
+        // UTF-16 with a byte order mark, or big-endian without one
+        1 | 2 => {
+            let big_endian = encoding == 2 || text.starts_with(&[0xfe, 0xff]);
+            let text = if text.starts_with(&[0xfe, 0xff]) || text.starts_with(&[0xff, 0xfe]) { &text[2..] } else { text };
+            let units: Vec<u16> = text
+                .chunks_exact(2)
+                .map(|u| if big_endian { u16::from_be_bytes([u[0], u[1]]) } else { u16::from_le_bytes([u[0], u[1]]) })
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        3 => String::from_utf8_lossy(text).to_string(),
+        _ => return None,
+    };
+
+    Some(text.trim_end_matches('\0').to_string())
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::Id3Tag;
+
+    // An ID3v2.3 tag with the given text frames, all ISO-8859-1
+    fn id3_bytes(frames: &[(&[u8; 4], &str)]) -> Vec<u8> {
+        let mut body = vec![];
+        for (id, text) in frames.iter() {
+            body.extend_from_slice(*id);
+            body.extend_from_slice(&(1 + text.len() as u32).to_be_bytes());
+            body.extend_from_slice(&[0, 0, 0]);
+            body.extend_from_slice(text.as_bytes());
+        }
+        // Some padding
+        body.extend_from_slice(&[0; 10]);
+
+        let mut bytes = b"ID3\x03\x00\x00".to_vec();
+        bytes.extend_from_slice(&[0, 0, 0, body.len() as u8]);
+        bytes.extend_from_slice(&body);
+
+        bytes
+    }
+
+    #[test]
+    fn test_reading_text_frames() {
+        let tag = Id3Tag {
+            bytes: id3_bytes(&[(b"TIT2", "Song"), (b"TPE1", "Singer")]),
+        };
+
+        assert_eq!(Some(3), tag.major_version());
+        assert_eq!(Some("Song".to_string()), tag.title());
+        assert_eq!(Some("Singer".to_string()), tag.artist());
+        assert_eq!(None, tag.album());
+    }
+
+    #[test]
+    fn test_reading_utf16_text_frame() {
+        let mut bytes = b"ID3\x04\x00\x00\x00\x00\x00\x13TALB\x00\x00\x00\x09\x00\x00\x01\xff\xfeA\x00l\x00b\x00".to_vec();
+        bytes.push(0);
+
+        assert_eq!(Some("Alb".to_string()), Id3Tag { bytes }.album());
+    }
+}
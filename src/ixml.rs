@@ -0,0 +1,85 @@
+// iXML, the production metadata field recorders write: project, scene, take, track names...
+// The XML is kept as it is. The accessors pick values out of it with a simple tag search,
+// which is enough for the flat elements recorders write. Anything fancier needs an XML parser.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ixml {
+    pub xml: String,
+}
+
+impl Ixml {
+    // The text of the first <tag> element
+    pub fn value(&self, tag: &str) -> Option<&str> {
+        self.values(tag).into_iter().next()
+    }
+
+    // The text of every <tag> element, in document order
+    pub fn values(&self, tag: &str) -> Vec<&str> {
+        let open = format!("<{}>", tag);
+        let close = format!("</{}>", tag);
+
+        let mut values = vec![];
+        let mut rest = &self.xml[..];
+        while let Some(start) = rest.find(&open) {
+            rest = &rest[start + open.len()..];
+            match rest.find(&close) {
+                Some(end) => {
+                    values.push(rest[..end].trim());
+                    rest = &rest[end + close.len()..];
+                }
+                None => break,
+            }
+        }
+
+        values
+    }
+
+    pub fn project(&self) -> Option<&str> {
+        self.value("PROJECT")
+    }
+
+    pub fn scene(&self) -> Option<&str> {
+        self.value("SCENE")
+    }
+
+    pub fn take(&self) -> Option<&str> {
+        self.value("TAKE")
+    }
+
+    pub fn tape(&self) -> Option<&str> {
+        self.value("TAPE")
+    }
+
+    pub fn note(&self) -> Option<&str> {
+        self.value("NOTE")
+    }
+
+    // The names of the tracks in the TRACK_LIST, in track order
+    pub fn track_names(&self) -> Vec<&str> {
+        self.values("NAME")
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::Ixml;
+
+    #[test]
+    fn test_picking_values_out_of_ixml() {
+        let ixml = Ixml {
+            xml: "<BWFXML><PROJECT>Feature</PROJECT><SCENE>12A</SCENE><TAKE>3</TAKE>\
+                  <TRACK_LIST><TRACK><NAME>Boom</NAME></TRACK><TRACK><NAME>Lav 1</NAME></TRACK></TRACK_LIST></BWFXML>"
+                .to_string(),
+        };
+
+        assert_eq!(Some("Feature"), ixml.project());
+        assert_eq!(Some("12A"), ixml.scene());
+        assert_eq!(Some("3"), ixml.take());
+        assert_eq!(None, ixml.tape());
+        assert_eq!(vec!["Boom", "Lav 1"], ixml.track_names());
+    }
+}
@@ -0,0 +1,241 @@
+// How a file was laid out, byte for byte, so that writing a file parsed with
+// `WaveFileParser::parse_lossless` gives back exactly what was read: every chunk in its order,
+// the pad bytes, the JUNK, the chunks nobody knows about and whatever trails the RIFF chunk.
+//
+// Only the samples are written from the `WaveFile`, so they can still be changed. Everything
+// else goes out as it was in the file, and changes to the other fields are not written.
+// The samples are only written for little-endian RIFF files in PCM or float. In anything else
+// (RIFX, RF64, compressed formats) the data chunks are kept as they are too.
+
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+#[cfg(feature = "std")]
+use crate::error::WaveError;
+use crate::parser::fourcc;
+use crate::wave_file::{WaveFile, WaveFormatCategory};
+#[cfg(feature = "std")]
+use crate::writer::{write_all, write_sample};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileLayout {
+    parts: Vec<LayoutPart>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum LayoutPart {
+    // Bytes from the file, as they were
+    Bytes(Vec<u8>),
+    // A data chunk, written from the samples
+    Data {
+        // The id and the size as they were in the file
+        header: [u8; 8],
+        // How many frames the chunk held
+        frames: u64,
+        // Bytes after the last whole frame
+        leftover: Vec<u8>,
+        // The pad byte, if there was one
+        pad: Option<u8>,
+    },
+}
+
+impl FileLayout {
+    // Splits `bytes` up around the data chunks. `wave_file` is what the parser made of them.
+    pub(crate) fn new(bytes: &[u8], wave_file: &WaveFile) -> FileLayout {
+        let writable_format = match WaveFormatCategory::from_tag(wave_file.format_tag) {
+            Some(wave_format) => wave_format == wave_file.wave_format,
+            None => false,
+        };
+        let block_align = wave_file.block_align as usize;
+
+        if bytes.len() < 12 || &bytes[..4] != b"RIFF" || !writable_format || block_align == 0 {
+            return FileLayout { parts: vec![LayoutPart::Bytes(bytes.to_vec())] };
+        }
+
+        let riff_end = (8 + read_u32(&bytes[4..]) as usize).min(bytes.len());
+        let mut parts = vec![];
+        // Where the bytes that haven't been put in a part yet start
+        let mut start = 0;
+        let mut offset = 12;
+
+        while offset + 8 <= riff_end {
+            let id = fourcc(&bytes[offset..]);
+            let size = read_u32(&bytes[offset + 4..]) as usize;
+            let contents = offset + 8;
+            let end = contents.saturating_add(size).min(bytes.len());
+
+            if &id == b"data" {
+                // The data chunk of a file from a streaming writer can run to the end
+                let end = if size == u32::MAX as usize { riff_end } else { end };
+                let data_size = end - contents;
+
+                parts.push(LayoutPart::Bytes(bytes[start..offset].to_vec()));
+                let mut header = [0; 8];
+                header.copy_from_slice(&bytes[offset..contents]);
+                parts.push(LayoutPart::Data {
+                    header,
+                    frames: (data_size / block_align) as u64,
+                    leftover: bytes[end - data_size % block_align..end].to_vec(),
+                    pad: if data_size % 2 == 1 { bytes.get(end).copied() } else { None },
+                });
+
+                offset = end + data_size % 2;
+                start = offset.min(bytes.len());
+            } else {
+                offset = end + size % 2;
+            }
+        }
+
+        parts.push(LayoutPart::Bytes(bytes[start..].to_vec()));
+
+        FileLayout { parts }
+    }
+
+    // Writes the file back out, with the samples from `wave_file` in the data chunks. Each data
+    // chunk gets as many frames as it had, and the last one gets whatever is left over.
+    // When the number of frames changes, the sizes are changed to match.
+    #[cfg(feature = "std")]
+    pub(crate) fn write<W: Write>(&self, wave_file: &WaveFile, writer: &mut W) -> Result<(), WaveError> {
+        let num_frames = wave_file.channels.first().map_or(0, |c| c.len() as u64);
+        if wave_file.channels.iter().any(|c| c.len() as u64 != num_frames) {
+            return Err(WaveError::ChannelLengthMismatch);
+        }
+
+        let block_align = wave_file.block_align as u64;
+        let last_data = self.parts.iter().rposition(|p| matches!(p, LayoutPart::Data { .. }));
+
+        // How many frames go in each part, and how much the RIFF chunk grows or shrinks by
+        let mut frames_per_part = vec![];
+        let mut remaining = num_frames;
+        let mut size_change: i64 = 0;
+        for (i, part) in self.parts.iter().enumerate() {
+            let frames = match part {
+                LayoutPart::Data { frames, .. } if Some(i) == last_data => remaining,
+                LayoutPart::Data { frames, .. } => (*frames).min(remaining),
+                LayoutPart::Bytes(_) => 0,
+            };
+            remaining -= frames;
+
+            if let LayoutPart::Data { frames: old_frames, leftover, .. } = part {
+                let old_size = old_frames * block_align + leftover.len() as u64;
+                let new_size = frames * block_align + leftover.len() as u64;
+                size_change += (new_size + new_size % 2) as i64 - (old_size + old_size % 2) as i64;
+            }
+            frames_per_part.push(frames);
+        }
+
+        let mut next_frame = 0;
+        for (part, &frames) in self.parts.iter().zip(frames_per_part.iter()) {
+            match part {
+                LayoutPart::Bytes(bytes) if size_change != 0 && bytes.starts_with(b"RIFF") => {
+                    let riff_size = read_u32(&bytes[4..]) as i64 + size_change;
+                    if riff_size < 0 || riff_size > u32::MAX as i64 {
+                        return Err(WaveError::InvalidChunkSize(u32::MAX));
+                    }
+
+                    write_all(writer, &bytes[..4])?;
+                    write_all(writer, &(riff_size as u32).to_le_bytes())?;
+                    write_all(writer, &bytes[8..])?;
+                }
+                LayoutPart::Bytes(bytes) => write_all(writer, bytes)?,
+                LayoutPart::Data { header, frames: old_frames, leftover, pad } => {
+                    let data_size = frames * block_align + leftover.len() as u64;
+                    if frames == *old_frames {
+                        write_all(writer, header)?;
+                    } else if data_size > u32::MAX as u64 {
+                        return Err(WaveError::InvalidChunkSize(u32::MAX));
+                    } else {
+                        write_all(writer, &header[..4])?;
+                        write_all(writer, &(data_size as u32).to_le_bytes())?;
+                    }
+
+                    let mut frame = vec![];
+                    for i in next_frame..next_frame + frames {
+                        frame.clear();
+                        for channel in wave_file.channels.iter() {
+                            write_sample(&mut frame, &channel[i as usize], wave_file.wave_format, wave_file.bits_per_sample)?;
+                        }
+                        write_all(writer, &frame)?;
+                    }
+                    next_frame += frames;
+
+                    write_all(writer, leftover)?;
+                    // The pad byte goes back the way it was, even when it was missing
+                    match pad {
+                        Some(pad) if frames == *old_frames => write_all(writer, &[*pad])?,
+                        _ if data_size % 2 == 1 && frames != *old_frames => write_all(writer, &[0])?,
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use crate::parser::WaveFileParser;
+    use crate::test_utils::{fmt_bytes, riff_bytes};
+    use crate::wave_file::Sample;
+    use crate::writer::WaveFileWriter;
+
+    #[test]
+    fn test_lossless_round_trip_is_byte_identical() {
+        let mut bytes = riff_bytes(&[
+            (b"JUNK", &[0; 28]),
+            (b"fmt ", &fmt_bytes(1, 2, 16)),
+            (b"LIST", b"INFOINAM\x04\x00\x00\x00Hum\0"),
+            (b"data", &[1, 0, 2, 0, 3, 0, 4, 0, 5]),
+            (b"odd!", b"x"),
+        ]);
+        // A pad byte that isn't 0, and some junk past the end of the RIFF chunk
+        let pad = 12 + 36 + 24 + 28 + 8 + 9;
+        bytes[pad] = 0xee;
+        bytes.extend_from_slice(b"trailing");
+
+        let wave_file = WaveFileParser::parse_lossless(bytes.clone()).unwrap();
+
+        assert_eq!(bytes, WaveFileWriter::to_bytes(&wave_file).unwrap());
+    }
+
+    #[test]
+    fn test_lossless_round_trip_with_changed_samples() {
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"data", &[0x80; 4]), (b"afte", b"rwards")]);
+
+        let mut wave_file = WaveFileParser::parse_lossless(bytes.clone()).unwrap();
+        wave_file.channels[0][1] = Sample::BitDepth8(0x81);
+        wave_file.channels[0].push(Sample::BitDepth8(0x82));
+
+        let written = WaveFileWriter::to_bytes(&wave_file).unwrap();
+        let parsed = WaveFileParser::parse(written.clone()).unwrap();
+
+        assert_eq!(wave_file.channels, parsed.channels);
+        // One more sample and a pad byte
+        assert_eq!(bytes.len() + 2, written.len());
+        assert_eq!(&(written.len() as u32 - 8).to_le_bytes(), &written[4..8]);
+        assert_eq!(b"rwards", &written[written.len() - 6..]);
+    }
+
+    #[test]
+    fn test_lossless_round_trip_of_big_endian_file_keeps_the_data() {
+        let mut bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 16)), (b"data", &[0, 1, 0, 2])]);
+        bytes[..4].copy_from_slice(b"RIFX");
+        // Every number big-endian
+        for &(offset, width) in [(4, 4), (16, 4), (20, 2), (22, 2), (24, 4), (28, 4), (32, 2), (34, 2), (40, 4)].iter() {
+            bytes[offset..offset + width].reverse();
+        }
+
+        let wave_file = WaveFileParser::parse_lossless(bytes.clone()).unwrap();
+
+        assert_eq!(vec![Sample::BitDepth16(1), Sample::BitDepth16(2)], wave_file.channels[0]);
+        assert_eq!(bytes, WaveFileWriter::to_bytes(&wave_file).unwrap());
+    }
+}
@@ -0,0 +1,159 @@
+// The peak envelope chunk (`levl`, EBU Tech 3285 supplement 3).
+// It holds a precomputed overview of the audio: the peak level of every block of frames,
+// so an editor can draw the waveform without reading all of the samples.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::associated_data::null_terminated_text;
+use crate::error::WaveError;
+
+const BYTES_LEVL_HEADER: usize = 128;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeakEnvelope {
+    pub version: u32,
+    // 1 for 8 bit peak values, 2 for 16 bit ones
+    pub format: u32,
+    // 1 when only the positive peak is stored, 2 when the negative one is as well
+    pub points_per_value: u32,
+    // How many frames each peak covers
+    pub block_size: u32,
+    pub peak_channels: u32,
+    pub num_peak_frames: u32,
+    // Which peak frame holds the highest peak
+    pub position_peak_of_peaks: u32,
+    // When the envelope was made, as "yyyy:mm:dd:hh:mm:ss:uuu"
+    pub timestamp: String,
+    // One vector of peaks per channel
+    pub peaks: Vec<Vec<Peak>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Peak {
+    pub positive: u16,
+    // Absolute value of the negative peak. None when the envelope only has positive peaks.
+    pub negative: Option<u16>,
+}
+
+pub(crate) fn read_levl(contents: &[u8]) -> Result<PeakEnvelope, WaveError> {
+    if contents.len() < BYTES_LEVL_HEADER {
+        return Err(WaveError::InvalidChunkSize(contents.len() as u32));
+    }
+
+    let u32_at = |i: usize| u32::from_le_bytes([contents[i], contents[i + 1], contents[i + 2], contents[i + 3]]);
+
+    let format = u32_at(4);
+    let points_per_value = u32_at(8);
+    let peak_channels = u32_at(16);
+    let num_peak_frames = u32_at(20);
+    let offset_to_peaks = u32_at(28) as usize;
+
+    let bytes_per_point = match format {
+        1 => 1,
+        2 => 2,
+        _ => return Err(WaveError::UnsupportedFormat(format as u16)),
+    };
+    if points_per_value != 1 && points_per_value != 2 {
+        return Err(WaveError::InvalidChunkSize(contents.len() as u32));
+    }
+    // Every peak channel is a vector, so a made up count can't be allowed to run into the
+    // billions. A file can't have more channels than fit in the fmt chunk anyway.
+    if peak_channels == 0 || peak_channels > u16::MAX as u32 {
+        return Err(WaveError::InvalidChunkSize(contents.len() as u32));
+    }
+
+    // The peaks go frame by frame, and within a frame channel by channel, positive peak first
+    let bytes_per_peak = bytes_per_point * points_per_value as usize;
+    let peak_data = contents.get(offset_to_peaks.max(BYTES_LEVL_HEADER)..).unwrap_or(&[]);
+    let total_peaks = peak_channels as u64 * num_peak_frames as u64;
+    if total_peaks * bytes_per_peak as u64 > peak_data.len() as u64 {
+        return Err(WaveError::UnexpectedEof);
+    }
+
+    let read_point = |bytes: &[u8]| match bytes_per_point {
+        1 => bytes[0] as u16,
+        _ => u16::from_le_bytes([bytes[0], bytes[1]]),
+    };
+
+    // Room for the peaks that are there, not the ones the header claims
+    let frames_present = peak_data.len() / bytes_per_peak / peak_channels as usize;
+    let mut peaks: Vec<Vec<Peak>> = (0..peak_channels).map(|_| Vec::with_capacity(frames_present.min(num_peak_frames as usize))).collect();
+    for (i, peak) in peak_data.chunks_exact(bytes_per_peak).take(total_peaks as usize).enumerate() {
+        peaks[i % peak_channels as usize].push(Peak {
+            positive: read_point(&peak[..bytes_per_point]),
+            negative: if points_per_value == 2 { Some(read_point(&peak[bytes_per_point..])) } else { None },
+        });
+    }
+
+    Ok(PeakEnvelope {
+        version: u32_at(0),
+        format,
+        points_per_value,
+        block_size: u32_at(12),
+        peak_channels,
+        num_peak_frames,
+        position_peak_of_peaks: u32_at(24),
+        timestamp: null_terminated_text(&contents[32..60]),
+        peaks,
+    })
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::{read_levl, Peak};
+    use crate::error::WaveError;
+
+    fn levl_header(format: u32, points_per_value: u32, channels: u32, frames: u32) -> Vec<u8> {
+        let mut contents = vec![0; 128];
+        contents[4..8].copy_from_slice(&format.to_le_bytes());
+        contents[8..12].copy_from_slice(&points_per_value.to_le_bytes());
+        contents[12..16].copy_from_slice(&256u32.to_le_bytes());
+        contents[16..20].copy_from_slice(&channels.to_le_bytes());
+        contents[20..24].copy_from_slice(&frames.to_le_bytes());
+        contents[28..32].copy_from_slice(&128u32.to_le_bytes());
+
+        contents
+    }
+
+    #[test]
+    fn test_reading_stereo_16bit_peaks() {
+        let mut contents = levl_header(2, 2, 2, 1);
+        for point in [100u16, 90, 200, 180].iter() {
+            contents.extend_from_slice(&point.to_le_bytes());
+        }
+
+        let envelope = read_levl(&contents).unwrap();
+
+        assert_eq!(256, envelope.block_size);
+        assert_eq!(vec![Peak { positive: 100, negative: Some(90) }], envelope.peaks[0]);
+        assert_eq!(vec![Peak { positive: 200, negative: Some(180) }], envelope.peaks[1]);
+    }
+
+    #[test]
+    fn test_reading_mono_8bit_positive_peaks() {
+        let mut contents = levl_header(1, 1, 1, 3);
+        contents.extend_from_slice(&[10, 20, 30]);
+
+        let envelope = read_levl(&contents).unwrap();
+
+        assert_eq!(vec![10, 20, 30], envelope.peaks[0].iter().map(|p| p.positive).collect::<Vec<_>>());
+        assert_eq!(None, envelope.peaks[0][0].negative);
+    }
+
+    #[test]
+    fn test_reading_levl_with_missing_peaks_returns_error() {
+        let contents = levl_header(1, 1, 1, 3);
+
+        assert_eq!(Err(WaveError::UnexpectedEof), read_levl(&contents));
+    }
+
+    #[test]
+    fn test_reading_levl_with_no_channels_returns_error() {
+        let mut contents = levl_header(2, 2, 0, u32::MAX);
+        contents.extend_from_slice(&[0; 20]);
+
+        assert_eq!(Err(WaveError::InvalidChunkSize(148)), read_levl(&contents));
+        assert_eq!(Err(WaveError::InvalidChunkSize(128)), read_levl(&levl_header(1, 1, u32::MAX, 0)));
+    }
+}
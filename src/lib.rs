@@ -0,0 +1,109 @@
+//! A library for parsing (and writing) '.wav' files.
+//! [This](http://www-mmsp.ece.mcgill.ca/Documents/AudioFormats/WAVE/Docs/riffmci.pdf) is the file specification that was followed.
+
+// The `WaveFormatCategory`  enum has non-camel cased type names.
+// This disables the default warning rust gives for such situtations
+#![allow(non_camel_case_types)]
+// Without the `std` feature only the parts that work on bytes in memory are left: parsing,
+// the header and sample conversions. Anything that reads or writes through `std::io` goes.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+mod acid;
+mod adm;
+#[cfg(feature = "std")]
+mod appender;
+mod associated_data;
+mod bext;
+mod byte_stream;
+mod cart;
+mod channel_layout;
+mod codec;
+mod concat;
+mod convert;
+mod cue;
+mod custom_chunk;
+mod diagnostic;
+#[cfg(feature = "std")]
+mod editor;
+mod error;
+mod events;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod fmt_chunk;
+mod frame;
+#[cfg(feature = "std")]
+mod gain;
+mod id3;
+mod ixml;
+mod layout;
+mod levl;
+mod metadata;
+mod options;
+mod pad;
+mod parser;
+mod playback;
+mod raw_chunk;
+#[cfg(feature = "std")]
+mod reader;
+#[cfg(feature = "std")]
+pub mod riff;
+mod sampler;
+mod simd;
+mod slice;
+#[cfg(feature = "std")]
+mod streaming_writer;
+mod summary;
+#[cfg(test)]
+mod test_utils;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+mod visitor;
+mod wave64;
+mod wave_file;
+mod wave_file_ref;
+mod wave_info;
+#[cfg(feature = "std")]
+mod writer;
+
+pub use acid::AcidChunk;
+pub use adm::AudioId;
+#[cfg(feature = "std")]
+pub use appender::WaveAppender;
+pub use associated_data::{EmbeddedFile, Label, Note, Region};
+pub use bext::BroadcastExtension;
+pub use cart::{CartChunk, CartTimer};
+pub use channel_layout::{ChannelLayout, SpeakerPosition};
+pub use codec::WaveCodec;
+pub use convert::{FromSample, ToSample, I24};
+pub use cue::{CuePoint, Marker, PlaylistSegment};
+pub use custom_chunk::{ChunkHandler, CustomChunks};
+pub use diagnostic::Diagnostic;
+#[cfg(feature = "std")]
+pub use editor::WaveEditor;
+pub use error::WaveError;
+pub use events::{WaveEvent, WaveEvents};
+pub use fmt_chunk::FmtChunk;
+pub use frame::{Frame, Frames, Window, Windows};
+pub use id3::Id3Tag;
+pub use ixml::Ixml;
+pub use layout::FileLayout;
+pub use levl::{Peak, PeakEnvelope};
+pub use metadata::Metadata;
+pub use options::{ParserOptions, WriterOptions, DEFAULT_MAX_SAMPLE_MEMORY, MAX_DATA_ALIGNMENT};
+pub use parser::WaveFileParser;
+pub use playback::PlaybackCursor;
+pub use raw_chunk::RawChunk;
+#[cfg(feature = "std")]
+pub use reader::WaveReader;
+pub use sampler::{Instrument, SampleLoop, SamplerChunk};
+#[cfg(feature = "std")]
+pub use streaming_writer::StreamingWaveWriter;
+pub use summary::WaveSummary;
+pub use visitor::ChunkVisitor;
+pub use wave_file::{Sample, WaveFile, WaveFormatCategory};
+pub use wave_file_ref::WaveFileRef;
+pub use wave_info::WaveInfo;
+#[cfg(feature = "std")]
+pub use writer::WaveFileWriter;
@@ -0,0 +1,163 @@
+// The text metadata from the LIST INFO chunk: title, artist, date and so on.
+// Each entry is keyed by the four character code of its INFO sub-chunk (INAM, IART, ...).
+// The entries keep the order they had in the file.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use crate::raw_chunk::write_chunk;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metadata {
+    entries: Vec<([u8; 4], String)>,
+}
+
+impl Metadata {
+    // The value for any INFO id, including the ones without an accessor below
+    pub fn get(&self, id: &[u8; 4]) -> Option<&str> {
+        self.entries.iter().find(|(i, _)| i == id).map(|(_, value)| value.as_str())
+    }
+
+    // Replaces the value for `id`, or adds it to the end if there isn't one
+    pub fn set(&mut self, id: [u8; 4], value: String) {
+        match self.entries.iter_mut().find(|(i, _)| *i == id) {
+            Some(entry) => entry.1 = value,
+            None => self.entries.push((id, value)),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8; 4], &str)> {
+        self.entries.iter().map(|(id, value)| (id, value.as_str()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // INAM
+    pub fn title(&self) -> Option<&str> {
+        self.get(b"INAM")
+    }
+
+    // IART
+    pub fn artist(&self) -> Option<&str> {
+        self.get(b"IART")
+    }
+
+    // IPRD, the product the file is part of. Usually the album.
+    pub fn album(&self) -> Option<&str> {
+        self.get(b"IPRD")
+    }
+
+    // ICRD, the creation date. Supposed to be YYYY-MM-DD, but it's free text.
+    pub fn date(&self) -> Option<&str> {
+        self.get(b"ICRD")
+    }
+
+    // ICMT
+    pub fn comment(&self) -> Option<&str> {
+        self.get(b"ICMT")
+    }
+
+    // IGNR
+    pub fn genre(&self) -> Option<&str> {
+        self.get(b"IGNR")
+    }
+
+    // ICOP
+    pub fn copyright(&self) -> Option<&str> {
+        self.get(b"ICOP")
+    }
+
+    // ISFT, the software that made the file
+    pub fn software(&self) -> Option<&str> {
+        self.get(b"ISFT")
+    }
+
+    // IENG
+    pub fn engineer(&self) -> Option<&str> {
+        self.get(b"IENG")
+    }
+
+    // ITRK. Not in the original spec, but widely used.
+    pub fn track_number(&self) -> Option<&str> {
+        self.get(b"ITRK")
+    }
+
+    pub fn set_title(&mut self, title: String) {
+        self.set(*b"INAM", title);
+    }
+
+    pub fn set_artist(&mut self, artist: String) {
+        self.set(*b"IART", artist);
+    }
+
+    pub fn set_album(&mut self, album: String) {
+        self.set(*b"IPRD", album);
+    }
+
+    pub fn set_date(&mut self, date: String) {
+        self.set(*b"ICRD", date);
+    }
+
+    pub fn set_comment(&mut self, comment: String) {
+        self.set(*b"ICMT", comment);
+    }
+
+    pub fn set_genre(&mut self, genre: String) {
+        self.set(*b"IGNR", genre);
+    }
+
+    // The whole LIST chunk, header included. Each value is written null terminated, with a
+    // pad byte after it when that leaves it an odd size, which is what players expect.
+    #[cfg(feature = "std")]
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut list = b"INFO".to_vec();
+        for (id, value) in self.entries.iter() {
+            let mut text = value.as_bytes().to_vec();
+            text.push(0);
+            write_chunk(&mut list, id, &text);
+        }
+
+        let mut bytes = vec![];
+        write_chunk(&mut bytes, b"LIST", &list);
+
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::Metadata;
+
+    #[test]
+    fn test_setting_replaces_existing_value() {
+        let mut metadata = Metadata::default();
+        metadata.set(*b"INAM", "Demo".to_string());
+        metadata.set(*b"IART", "Someone".to_string());
+        metadata.set(*b"INAM", "Final".to_string());
+
+        assert_eq!(Some("Final"), metadata.title());
+        assert_eq!(Some("Someone"), metadata.artist());
+        assert_eq!(None, metadata.comment());
+        assert_eq!(2, metadata.iter().count());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_info_list_bytes() {
+        let mut metadata = Metadata::default();
+        metadata.set_title("Hum".to_string());
+        metadata.set_artist("Band".to_string());
+
+        let mut expected = b"LIST".to_vec();
+        expected.extend_from_slice(&30u32.to_le_bytes());
+        expected.extend_from_slice(b"INFO");
+        // "Hum" and the null make an even 4 bytes, "Band" and the null need a pad byte
+        expected.extend_from_slice(b"INAM\x04\x00\x00\x00Hum\0");
+        expected.extend_from_slice(b"IART\x05\x00\x00\x00Band\0\0");
+
+        assert_eq!(expected, metadata.to_bytes());
+    }
+}
@@ -0,0 +1,178 @@
+use alloc::sync::Arc;
+use core::fmt;
+
+// Knobs for how forgiving the parser is.
+// The defaults are what `WaveFileParser::parse` does: reasonably strict about the samples,
+// forgiving about the RIFF size and the pad byte after the data chunk, and no more than
+// `DEFAULT_MAX_SAMPLE_MEMORY` bytes of decoded samples.
+//
+// Build one up and hand it to `WaveFileParser::parse_with_options`:
+//
+//     let options = ParserOptions::new().tolerate_truncated_data(true).max_chunks(1000);
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParserOptions {
+    pub(crate) strict_chunk_sizes: bool,
+    pub(crate) allow_unknown_formats: bool,
+    pub(crate) max_chunks: Option<usize>,
+    pub(crate) tolerate_truncated_data: bool,
+    pub(crate) max_data_size: Option<u64>,
+    pub(crate) max_channels: Option<u16>,
+    pub(crate) max_sample_memory: Option<u64>,
+    pub(crate) on_progress: Option<ProgressCallback>,
+}
+
+// The progress callback, wrapped up so the options can still be cloned, printed and compared.
+// Two options are only equal if they share the very same callback.
+#[derive(Clone)]
+pub(crate) struct ProgressCallback(Arc<dyn Fn(u64, u64) + Send + Sync>);
+
+impl ProgressCallback {
+    pub(crate) fn call(&self, bytes_done: u64, bytes_total: u64) {
+        (self.0)(bytes_done, bytes_total)
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+impl PartialEq for ProgressCallback {
+    fn eq(&self, other: &ProgressCallback) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+// How much memory the decoded samples can take up unless the options say otherwise: 4 GiB.
+// A `Sample` is 16 bytes, so that's a bit over 268 million samples, about 50 minutes of
+// 44.1 kHz stereo. Enough for most files, and it keeps a header that lies about how many
+// samples there are from taking all of the memory there is.
+pub const DEFAULT_MAX_SAMPLE_MEMORY: u64 = 4 << 30;
+
+impl Default for ParserOptions {
+    fn default() -> ParserOptions {
+        ParserOptions {
+            strict_chunk_sizes: false,
+            allow_unknown_formats: false,
+            max_chunks: None,
+            tolerate_truncated_data: false,
+            max_data_size: None,
+            max_channels: None,
+            max_sample_memory: Some(DEFAULT_MAX_SAMPLE_MEMORY),
+            on_progress: None,
+        }
+    }
+}
+
+impl ParserOptions {
+    pub fn new() -> ParserOptions {
+        Default::default()
+    }
+
+    // Refuse files whose sizes don't add up: a RIFF size that doesn't match the length of the
+    // file, a missing pad byte after an odd sized data chunk, or a data chunk that doesn't hold
+    // a whole number of frames. All of these come back as `WaveError::InvalidChunkSize`.
+    pub fn strict_chunk_sizes(mut self, strict: bool) -> ParserOptions {
+        self.strict_chunk_sizes = strict;
+        self
+    }
+
+    // Parse files in formats nobody can decode instead of failing with `UnsupportedFormat`.
+    // The samples are skipped, so the channels come back empty. Everything else is still read.
+    pub fn allow_unknown_formats(mut self, allow: bool) -> ParserOptions {
+        self.allow_unknown_formats = allow;
+        self
+    }
+
+    // Give up with `WaveError::TooManyChunks` after this many chunks.
+    // A guard against files made up of millions of tiny chunks.
+    pub fn max_chunks(mut self, max_chunks: usize) -> ParserOptions {
+        self.max_chunks = Some(max_chunks);
+        self
+    }
+
+    // When the file ends in the middle of the data chunk, keep the frames that made it
+    // instead of failing with `UnexpectedEof`. A frame that is cut short is dropped.
+    pub fn tolerate_truncated_data(mut self, tolerate: bool) -> ParserOptions {
+        self.tolerate_truncated_data = tolerate;
+        self
+    }
+
+    // The limits below are for reading files from people you don't trust. A header costs
+    // nothing to fake, and one that claims billions of samples could otherwise eat up all
+    // the memory there is. Going over any of them is a `WaveError::LimitExceeded`.
+
+    // The most bytes a data chunk can hold
+    pub fn max_data_size(mut self, max_data_size: u64) -> ParserOptions {
+        self.max_data_size = Some(max_data_size);
+        self
+    }
+
+    // The most channels a file can have
+    pub fn max_channels(mut self, max_channels: u16) -> ParserOptions {
+        self.max_channels = Some(max_channels);
+        self
+    }
+
+    // The most memory, in bytes, the decoded samples can take up altogether. This one is on
+    // by default, at `DEFAULT_MAX_SAMPLE_MEMORY`. Pass `u64::MAX` to do without it.
+    pub fn max_sample_memory(mut self, max_sample_memory: u64) -> ParserOptions {
+        self.max_sample_memory = Some(max_sample_memory);
+        self
+    }
+
+    // Call `on_progress(bytes_done, bytes_total)` every so often while the samples are decoded,
+    // and once more when a data chunk is done. The counts are bytes of the data chunk being
+    // decoded. `bytes_total` is 0 when the data runs to the end of a file of unknown length.
+    pub fn on_progress<F: Fn(u64, u64) + Send + Sync + 'static>(mut self, on_progress: F) -> ParserOptions {
+        self.on_progress = Some(ProgressCallback(Arc::new(on_progress)));
+        self
+    }
+}
+
+// The biggest alignment `WriterOptions::align_data` takes. A megabyte is well past any sector
+// or page size, and keeps the JUNK chunk from getting anywhere near the 4GB a chunk can hold.
+pub const MAX_DATA_ALIGNMENT: u64 = 1 << 20;
+
+// Knobs for how `WaveFileWriter` lays out the chunks.
+// The defaults are what `WaveFileWriter::write` does: the bext chunk ahead of fmt, the
+// metadata between fmt and the data, and no padding.
+//
+//     let options = WriterOptions::new().metadata_after_data(true).align_data(4096);
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WriterOptions {
+    pub(crate) bext_after_fmt: bool,
+    pub(crate) metadata_after_data: bool,
+    pub(crate) data_alignment: Option<u64>,
+}
+
+impl WriterOptions {
+    pub fn new() -> WriterOptions {
+        Default::default()
+    }
+
+    // Put the bext chunk right after the fmt chunk, for tools that want fmt first, instead of
+    // ahead of it the way EBU Tech 3285 lays it out
+    pub fn bext_after_fmt(mut self, after: bool) -> WriterOptions {
+        self.bext_after_fmt = after;
+        self
+    }
+
+    // Put the LIST INFO, cue and LIST adtl chunks after the data instead of before it.
+    // Readers that stop at the data chunk won't see them, but they can be rewritten
+    // without moving the samples.
+    pub fn metadata_after_data(mut self, after: bool) -> WriterOptions {
+        self.metadata_after_data = after;
+        self
+    }
+
+    // Put a JUNK chunk ahead of the data chunk so the samples start at a multiple of
+    // `alignment` bytes from the start of the file, say the sector size for unbuffered I/O.
+    // An alignment of 0 or 1 adds no padding. Writing fails for anything over
+    // `MAX_DATA_ALIGNMENT`.
+    pub fn align_data(mut self, alignment: u64) -> WriterOptions {
+        self.data_alignment = Some(alignment);
+        self
+    }
+}
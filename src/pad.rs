@@ -0,0 +1,169 @@
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::time::Duration;
+
+use crate::error::WaveError;
+use crate::parser::silent_sample;
+use crate::wave_file::WaveFile;
+
+impl WaveFile {
+    // Adds `duration` of silence before the audio, rounded down to whole frames, say to line up
+    // stems that start at different times before mixing them. The silence is whatever means
+    // nothing in the file's format: 0x80 for 8 bit, 0 for everything else.
+    //
+    // The cue points and sampler loops are moved along to stay with their audio. Any that would
+    // end up past what a cue position can hold are dropped, along with their labels and so on.
+    // The bext time reference is moved back, so the audio stays where it was on the timeline.
+    pub fn pad_start(&mut self, duration: Duration) -> Result<(), WaveError> {
+        let frames = self.duration_to_frames(duration);
+        self.pad(frames, true)
+    }
+
+    // Adds `duration` of silence after the audio, rounded down to whole frames.
+    // See `pad_start`.
+    pub fn pad_end(&mut self, duration: Duration) -> Result<(), WaveError> {
+        let frames = self.duration_to_frames(duration);
+        self.pad(frames, false)
+    }
+
+    fn pad(&mut self, frames: u64, at_start: bool) -> Result<(), WaveError> {
+        if frames == 0 {
+            return Ok(());
+        }
+
+        let silence = silent_sample(self.wave_format, self.bits_per_sample)?;
+        let num_frames = self.num_frames();
+        for channel in self.channels.iter_mut() {
+            // Short channels are filled out first so the silence lines up
+            let length = channel.len() as u64;
+            channel.resize((length.max(num_frames) + frames) as usize, silence.clone());
+            if at_start {
+                channel.truncate(num_frames as usize + frames as usize);
+                channel.rotate_right(frames as usize);
+            }
+        }
+
+        if at_start {
+            let moved = |position: u32| u32::try_from(position as u64 + frames).ok();
+            let dropped: Vec<u32> = self.cue_points.iter().filter(|c| moved(c.sample_offset).is_none()).map(|c| c.id).collect();
+            let kept = |cue_id: u32| !dropped.contains(&cue_id);
+
+            self.cue_points.retain(|c| kept(c.id));
+            for cue_point in self.cue_points.iter_mut() {
+                cue_point.position = moved(cue_point.position).unwrap_or(u32::MAX);
+                cue_point.sample_offset = moved(cue_point.sample_offset).unwrap_or_default();
+            }
+            self.labels.retain(|l| kept(l.cue_id));
+            self.notes.retain(|n| kept(n.cue_id));
+            self.regions.retain(|r| kept(r.cue_id));
+            self.embedded_files.retain(|e| kept(e.cue_id));
+            self.playlist.retain(|p| kept(p.cue_id));
+
+            if let Some(bext) = self.broadcast_extension.as_mut() {
+                bext.time_reference = bext.time_reference.saturating_sub(frames);
+            }
+
+            if let Some(sampler) = self.sampler.as_mut() {
+                sampler.loops.retain(|l| moved(l.end).is_some());
+                for sample_loop in sampler.loops.iter_mut() {
+                    sample_loop.start = moved(sample_loop.start).unwrap_or_default();
+                    sample_loop.end = moved(sample_loop.end).unwrap_or_default();
+                }
+            }
+        }
+
+        if self.fact_sample_count.is_some() {
+            self.fact_sample_count = Some(self.num_frames().min(u32::MAX as u64) as u32);
+        }
+        // The envelope and the layout from `parse_lossless` are about the audio as it was
+        self.peak_envelope = None;
+        self.layout = None;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use std::time::Duration;
+    use crate::associated_data::Label;
+    use crate::bext::BroadcastExtension;
+    use crate::cue::CuePoint;
+    use crate::error::WaveError;
+    use crate::wave_file::{Sample, WaveFile, WaveFormatCategory};
+
+    #[test]
+    fn test_padding_the_start_moves_the_markers() {
+        let mut wave_file = WaveFile {
+            channels: vec![vec![Sample::BitDepth8(1), Sample::BitDepth8(2)]],
+            sample_rate: 4,
+            bits_per_sample: 8,
+            fact_sample_count: Some(2),
+            cue_points: vec![CuePoint {
+                id: 1,
+                position: 1,
+                chunk_id: *b"data",
+                chunk_start: 0,
+                block_start: 0,
+                sample_offset: 1,
+            }],
+            labels: vec![Label { cue_id: 1, text: "two".to_string() }],
+            ..Default::default()
+        };
+
+        wave_file.pad_start(Duration::from_millis(500)).unwrap();
+
+        assert_eq!(vec![Sample::BitDepth8(0x80), Sample::BitDepth8(0x80), Sample::BitDepth8(1), Sample::BitDepth8(2)], wave_file.channels[0]);
+        assert_eq!(Some(4), wave_file.fact_sample_count);
+        assert_eq!(3, wave_file.cue_points[0].sample_offset);
+        assert_eq!(Some("two".to_string()), wave_file.markers()[0].label);
+    }
+
+    #[test]
+    fn test_padding_the_start_moves_the_time_reference_back() {
+        let mut wave_file = WaveFile {
+            channels: vec![vec![Sample::BitDepth16(1)]],
+            sample_rate: 10,
+            bits_per_sample: 16,
+            broadcast_extension: Some(BroadcastExtension { time_reference: 25, ..Default::default() }),
+            ..Default::default()
+        };
+
+        wave_file.pad_start(Duration::from_secs(2)).unwrap();
+        assert_eq!(5, wave_file.broadcast_extension.as_ref().unwrap().time_reference);
+
+        wave_file.pad_start(Duration::from_secs(1)).unwrap();
+        assert_eq!(0, wave_file.broadcast_extension.as_ref().unwrap().time_reference);
+
+        wave_file.pad_end(Duration::from_secs(1)).unwrap();
+        assert_eq!(0, wave_file.broadcast_extension.unwrap().time_reference);
+    }
+
+    #[test]
+    fn test_padding_the_end_with_silence() {
+        let mut wave_file = WaveFile {
+            channels: vec![vec![Sample::Float32(0.5)], vec![]],
+            wave_format: WaveFormatCategory::WAVE_FORMAT_IEEE_FLOAT,
+            sample_rate: 10,
+            bits_per_sample: 32,
+            ..Default::default()
+        };
+
+        wave_file.pad_end(Duration::from_millis(200)).unwrap();
+
+        assert_eq!(vec![Sample::Float32(0.5), Sample::Float32(0.0), Sample::Float32(0.0)], wave_file.channels[0]);
+        assert_eq!(vec![Sample::Float32(0.0); 3], wave_file.channels[1]);
+    }
+
+    #[test]
+    fn test_padding_with_an_unsupported_bit_depth_returns_error() {
+        let mut wave_file = WaveFile {
+            channels: vec![vec![]],
+            sample_rate: 10,
+            bits_per_sample: 48,
+            ..Default::default()
+        };
+
+        assert_eq!(Err(WaveError::UnsupportedBitDepth(48)), wave_file.pad_end(Duration::from_secs(1)));
+    }
+}
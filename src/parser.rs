@@ -0,0 +1,2409 @@
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem;
+#[cfg(feature = "std")]
+use std::io::{Read, Seek};
+
+use crate::acid;
+use crate::adm;
+use crate::associated_data::{self, EmbeddedFile, Label, Note, Region};
+use crate::bext;
+use crate::byte_stream::{ByteStream, InMemory, Source};
+#[cfg(feature = "std")]
+use crate::byte_stream::{ForwardOnly, Seekable};
+use crate::cart;
+use crate::codec::{self, WaveCodec};
+use crate::codec::pcm::SampleEncoding;
+use crate::cue::{CuePoint, PlaylistSegment};
+use crate::custom_chunk::ChunkHandler;
+use crate::diagnostic::Diagnostic;
+use crate::error::WaveError;
+use crate::fmt_chunk::FmtChunk;
+use crate::id3::Id3Tag;
+use crate::ixml::Ixml;
+use crate::layout::FileLayout;
+use crate::levl;
+use crate::options::ParserOptions;
+use crate::raw_chunk::RawChunk;
+use crate::sampler;
+use crate::wave_file::{Sample, WaveFile, WaveFormatCategory};
+use crate::wave_file_ref::WaveFileRef;
+use crate::wave_info::WaveInfo;
+
+const BYTES_CHUNK_ID: usize = 4;
+const BYTES_CHUNK_SIZE: usize = 4;
+const BYTES_CHUNK_HEADER: u64 = (BYTES_CHUNK_ID + BYTES_CHUNK_SIZE) as u64;
+const BYTES_LIST_TYPE: usize = 4;
+// The data size we use for "the samples run to the end of the file"
+const DATA_SIZE_UNTIL_EOF: u64 = u64::MAX;
+const BYTES_CUE_POINT: u64 = 24;
+const BYTES_PLAYLIST_SEGMENT: u64 = 12;
+// How much of the data chunk is read in one go when decoding samples
+const BYTES_DECODE_BATCH: usize = 64 * 1024;
+// The most frames we reserve room for before seeing them. The data size comes from the file,
+// so a bogus one shouldn't be able to make us allocate gigabytes for samples that aren't there.
+const MAX_PREALLOCATED_FRAMES: u64 = 1 << 20;
+
+// The format tag that says "look in the SubFormat GUID for the real format tag"
+pub(crate) const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+// Everything but the first two bytes of the KSDATAFORMAT_SUBTYPE_PCM/IEEE_FLOAT/... GUIDs
+pub(crate) const KSDATAFORMAT_SUBTYPE_SUFFIX: [u8; 14] = [
+    0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71,
+];
+
+// The entry point into the library.
+// Hand it the bytes of a '.wav' file (or something to read them from) and get a WaveFile back.
+//
+// The associated functions are all most people need. Make an instance when there are
+// codecs or chunk handlers to register, and parse with its `read_from` method.
+#[derive(Default)]
+pub struct WaveFileParser {
+    codecs: BTreeMap<u16, Box<dyn WaveCodec>>,
+    chunk_handlers: BTreeMap<[u8; 4], Box<dyn ChunkHandler>>,
+    options: ParserOptions,
+}
+
+impl WaveFileParser {
+    pub fn new() -> WaveFileParser {
+        Default::default()
+    }
+
+    // Decode the data of files with `format_tag` using `codec`.
+    // This replaces any codec registered (or built in) for that format tag.
+    pub fn register_codec<C: WaveCodec + 'static>(&mut self, format_tag: u16, codec: C) {
+        self.codecs.insert(format_tag, Box::new(codec));
+    }
+
+    // Hand the contents of every `id` chunk to `handler`, and keep what it makes in
+    // `WaveFile::custom_chunks`. Only for chunks the parser doesn't know about itself,
+    // which still end up in `extra_chunks` as well.
+    pub fn register_chunk<H: ChunkHandler + 'static>(&mut self, id: [u8; 4], handler: H) {
+        self.chunk_handlers.insert(id, Box::new(handler));
+    }
+
+    // How forgiving `read_from` should be
+    pub fn set_options(&mut self, options: ParserOptions) {
+        self.options = options;
+    }
+
+    // Same as `from_reader`, but with the registered codecs, chunk handlers and options
+    #[cfg(feature = "std")]
+    pub fn read_from<R: Read>(&mut self, reader: R) -> Result<WaveFile, WaveError> {
+        self.read_source(ForwardOnly(reader))
+    }
+
+    // Same as `parse`, but with the registered codecs, chunk handlers and options
+    pub fn read_bytes(&mut self, bytes: Vec<u8>) -> Result<WaveFile, WaveError> {
+        self.read_source(InMemory::new(bytes))
+    }
+
+    fn read_source<S: Source>(&mut self, source: S) -> Result<WaveFile, WaveError> {
+        let mut parser = ChunkParser::new(source);
+        parser.codecs = mem::take(&mut self.codecs);
+        parser.chunk_handlers = mem::take(&mut self.chunk_handlers);
+        parser.options = self.options.clone();
+
+        let result = parser.read_wave_file();
+        self.codecs = parser.codecs;
+        self.chunk_handlers = parser.chunk_handlers;
+
+        result
+    }
+
+    // Parse a whole file with the default options. Like every other way of parsing, that keeps
+    // the decoded samples to `DEFAULT_MAX_SAMPLE_MEMORY`, so a file that claims to hold more
+    // than that is a `WaveError::LimitExceeded` instead of an attempt to allocate all of it.
+    pub fn parse(bytes: Vec<u8>) -> Result<WaveFile, WaveError> {
+        ChunkParser::new(InMemory::new(bytes)).read_wave_file()
+    }
+
+    // Same as `parse`, but stricter or more forgiving depending on the options
+    pub fn parse_with_options(bytes: Vec<u8>, options: ParserOptions) -> Result<WaveFile, WaveError> {
+        let mut parser = WaveFileParser::new();
+        parser.set_options(options);
+
+        parser.read_bytes(bytes)
+    }
+
+    // For files that were never finished, like the ones a recorder leaves behind when it crashes.
+    // A data size of 0, or one that runs past the end of the file, is taken to mean "the rest
+    // of the file", and as many whole frames as are there get read. The `diagnostics` on the
+    // WaveFile that comes back say what had to be fixed.
+    pub fn parse_repair(bytes: Vec<u8>) -> Result<WaveFile, WaveError> {
+        WaveFileParser::parse_repair_with_options(bytes, ParserOptions::new())
+    }
+
+    // Same as `parse_repair`, with limits and such from the options. Truncated data is always
+    // tolerated, whatever the options say.
+    pub fn parse_repair_with_options(bytes: Vec<u8>, options: ParserOptions) -> Result<WaveFile, WaveError> {
+        let file_len = bytes.len() as u64;
+
+        let mut parser = ChunkParser::new(InMemory::new(bytes));
+        parser.options = options.tolerate_truncated_data(true);
+        parser.repair_file_len = Some(file_len);
+
+        parser.read_wave_file()
+    }
+
+    // Same as `parse`, but the WaveFile remembers how the file was laid out, so that writing it
+    // back out with `WaveFileWriter` gives exactly the same bytes. See `FileLayout`.
+    pub fn parse_lossless(bytes: Vec<u8>) -> Result<WaveFile, WaveError> {
+        WaveFileParser::parse_lossless_with_options(bytes, ParserOptions::new())
+    }
+
+    // Same as `parse_lossless`, but stricter or more forgiving depending on the options
+    pub fn parse_lossless_with_options(bytes: Vec<u8>, options: ParserOptions) -> Result<WaveFile, WaveError> {
+        let mut parser = ChunkParser::new(InMemory::new(&bytes[..]));
+        parser.options = options;
+        let mut wave_file = parser.read_wave_file()?;
+        wave_file.layout = Some(FileLayout::new(&bytes, &wave_file));
+
+        Ok(wave_file)
+    }
+
+    // Read everything but the samples. The data chunks are skipped over, and only where they
+    // are and how big they are gets remembered. Much quicker than `parse` for big files.
+    pub fn parse_header(bytes: Vec<u8>) -> Result<WaveInfo, WaveError> {
+        WaveFileParser::read_header(InMemory::new(bytes))
+    }
+
+    // Same as `parse_header`, but the samples come along as slices of `bytes` rather than being
+    // decoded, so nothing the size of the data chunk gets copied
+    pub fn parse_slice(bytes: &[u8]) -> Result<WaveFileRef<'_>, WaveError> {
+        let mut parser = ChunkParser::new(InMemory::new(bytes));
+        parser.decode_samples = false;
+
+        let wave_file = parser.read_wave_file()?;
+        let data = parser
+            .data_chunks
+            .iter()
+            .map(|&(start, size)| {
+                let start = (start as usize).min(bytes.len());
+                let end = start.saturating_add(size as usize).min(bytes.len());
+
+                &bytes[start..end]
+            })
+            .collect();
+
+        Ok(WaveFileRef {
+            wave_file,
+            data,
+            big_endian: parser.byte_stream.big_endian,
+        })
+    }
+
+    // Same as `parse_header`, straight out of a file. The data chunks are skipped with a seek,
+    // so the samples are never even read.
+    #[cfg(feature = "std")]
+    pub fn header_from_reader<R: Read + Seek>(reader: R) -> Result<WaveInfo, WaveError> {
+        let source = Seekable::new(reader).map_err(|e| WaveError::Io(e.kind()))?;
+
+        WaveFileParser::read_header(source)
+    }
+
+    fn read_header<S: Source>(source: S) -> Result<WaveInfo, WaveError> {
+        let mut parser = ChunkParser::new(source);
+        parser.decode_samples = false;
+
+        let wave_file = parser.read_wave_file()?;
+
+        Ok(WaveInfo {
+            wave_file,
+            data_chunks: parser.data_chunks,
+        })
+    }
+
+    // Parse a '.wav' file straight out of anything that implements `Read`.
+    // The bytes are consumed front to back as the chunks are parsed, so the reader never
+    // has to fit into memory. Wrap unbuffered readers (files, sockets) in a BufReader.
+    #[cfg(feature = "std")]
+    pub fn from_reader<R: Read>(reader: R) -> Result<WaveFile, WaveError> {
+        ChunkParser::new(ForwardOnly(reader)).read_wave_file()
+    }
+}
+
+// The parser is just a wrapper around a ByteStream containing the
+// bytes the user passed in.
+pub(crate) struct ChunkParser<S> {
+    pub(crate) byte_stream: ByteStream<S>,
+    // When this is false the samples are left alone. We only remember where each
+    // data chunk starts (right after its size field) and how many bytes it holds.
+    pub(crate) decode_samples: bool,
+    pub(crate) data_chunks: Vec<(u64, u64)>,
+    // The fmt chunk as it was read, extension and all, for the block decoders
+    pub(crate) fmt: Option<FmtChunk>,
+    // Decoders registered by the user, by format tag
+    pub(crate) codecs: BTreeMap<u16, Box<dyn WaveCodec>>,
+    // Handlers registered by the user, by chunk id
+    pub(crate) chunk_handlers: BTreeMap<[u8; 4], Box<dyn ChunkHandler>>,
+    // The 64 bit data chunk size from the ds64 chunk of an RF64 file
+    pub(crate) ds64_data_size: Option<u64>,
+    // Whether the RIFF size was left at 0 or 0xFFFFFFFF, like streaming writers do
+    pub(crate) riff_size_unknown: bool,
+    // The chunk being read right now, for error messages
+    pub(crate) current_chunk: Option<[u8; 4]>,
+    // The real length of the file, when repairing one with sizes that can't be trusted
+    pub(crate) repair_file_len: Option<u64>,
+    pub(crate) options: ParserOptions,
+    // How many chunks we've come across, to hold up against `options.max_chunks`
+    chunks_read: usize,
+}
+
+impl<S: Source> ChunkParser<S> {
+    // The bytes can only be read once, front to back, so the chunks are handled in whatever
+    // order they show up in.
+    // (a) There is a method for each chunk defined in the '.wav' file specification
+    // (b) `read_wave_riff_form` reads each chunk id and hands off to the matching method.
+    //     Each of those methods starts by reading the chunk size.
+
+    pub(crate) fn new(source: S) -> ChunkParser<S> {
+        ChunkParser {
+            byte_stream: ByteStream::new(source),
+            decode_samples: true,
+            data_chunks: vec![],
+            fmt: None,
+            codecs: BTreeMap::new(),
+            chunk_handlers: BTreeMap::new(),
+            ds64_data_size: None,
+            riff_size_unknown: false,
+            current_chunk: None,
+            repair_file_len: None,
+            options: Default::default(),
+            chunks_read: 0,
+        }
+    }
+
+    // Every error comes back with where it happened, since "unexpected end of file" on its own
+    // doesn't help much with figuring out what's wrong with a file
+    pub(crate) fn read_wave_file(&mut self) -> Result<WaveFile, WaveError> {
+        self.read_wave_form().map_err(|error| WaveError::Context {
+            offset: self.byte_stream.offset,
+            chunk_id: self.current_chunk,
+            error: Box::new(error),
+        })
+    }
+
+    fn read_wave_form(&mut self) -> Result<WaveFile, WaveError> {
+        let mut wave_file: WaveFile = Default::default();
+
+        // RF64 is the same as RIFF, except that sizes that don't fit in 32 bits are set
+        // to 0xFFFFFFFF and the real ones are in a ds64 chunk.
+        let form: [u8; BYTES_CHUNK_ID] = self.byte_stream.read_array()?;
+        let is_rf64 = match &form[..] {
+            b"RIFF" => false,
+            // RIFX is RIFF with every number stored big-endian
+            b"RIFX" => {
+                self.byte_stream.big_endian = true;
+                false
+            }
+            // BW64 is RF64 by another name
+            b"RF64" | b"BW64" => true,
+            // Wave64 starts with a GUID that happens to begin with "riff"
+            b"riff" => {
+                self.read_wave64_file(&mut wave_file)?;
+                self.check_fact_sample_count(&mut wave_file)?;
+                return Ok(wave_file);
+            }
+            // not a .wav file
+            _ => return Err(WaveError::UnexpectedChunk {
+                expected: *b"RIFF",
+                found: form,
+            }),
+        };
+        self.current_chunk = Some(form);
+
+        // Read the size of the "RIFF" chunk
+        let riff_size = self.read_chunk_size()?;
+        let mut riff_end = BYTES_CHUNK_HEADER + riff_size as u64;
+        self.riff_size_unknown = !is_rf64 && (riff_size == 0 || riff_size == u32::MAX);
+
+        // odd, this is not the "WAVE" character code we expected
+        self.expect_id(b"WAVE")?;
+
+        // The ds64 chunk has to be the very first one
+        if is_rf64 {
+            self.expect_id(b"ds64")?;
+            self.current_chunk = Some(*b"ds64");
+            riff_end = BYTES_CHUNK_HEADER + self.read_ds64_chunk()?;
+        }
+
+        self.read_wave_riff_form(&mut wave_file, riff_end)?;
+        self.check_fact_sample_count(&mut wave_file)?;
+
+        Ok(wave_file)
+    }
+
+    // Compressed formats usually pad the last block out with samples that aren't really there.
+    // The fact chunk says how many there are supposed to be, so anything past that is dropped.
+    // Fewer samples than that means some of the data is missing.
+    fn check_fact_sample_count(&self, wave_file: &mut WaveFile) -> Result<(), WaveError> {
+        let format_tag = wave_file.format_tag;
+        let can_decode = WaveFormatCategory::from_tag(format_tag).is_some() || self.codecs.contains_key(&format_tag);
+        let count = match wave_file.fact_sample_count {
+            Some(count) if self.decode_samples && can_decode && format_tag != WaveFormatCategory::WAVE_FORMAT_PCM as u16 => count,
+            _ => return Ok(()),
+        };
+
+        let num_frames = wave_file.channels.first().map_or(0, |c| c.len());
+        if num_frames < count as usize && !self.options.tolerate_truncated_data {
+            return Err(WaveError::SampleCountMismatch(count));
+        }
+
+        for channel in wave_file.channels.iter_mut() {
+            channel.truncate(count as usize);
+        }
+
+        Ok(())
+    }
+
+    // Chunks can come in any order. Plenty of files put the LIST chunks before fmt, or the
+    // cue chunk after the data. The only thing that matters is that fmt comes before the data,
+    // since we can't make sense of the samples without it.
+    fn read_wave_riff_form(&mut self, wave_file: &mut WaveFile, riff_end: u64) -> Result<(), WaveError> {
+        let mut found_fmt = false;
+        let mut found_data = false;
+
+        loop {
+            // Some programs tack junk onto the end of the file, past the end of the RIFF chunk.
+            // Others never fill in the RIFF size. So the size is only trusted once we have the samples.
+            if (found_data || self.options.strict_chunk_sizes) && self.byte_stream.offset >= riff_end {
+                break;
+            }
+
+            let chunk_offset = self.byte_stream.offset;
+            let chunk_id = match self.byte_stream.read_or_eof(BYTES_CHUNK_ID)? {
+                Some(chunk_id) => chunk_id,
+                None => break,
+            };
+            self.current_chunk = Some(fourcc(&chunk_id));
+            self.count_chunk()?;
+
+            match &chunk_id[..] {
+                b"fmt " => {
+                    self.read_fmt_chunk(wave_file)?;
+                    found_fmt = true;
+                }
+                b"fact" => self.read_fact_chunk(wave_file)?,
+                b"cue " => self.read_cue_chunk(wave_file)?,
+                b"plst" => self.read_playlist_chunk(wave_file)?,
+                b"chna" => self.read_chna_chunk(wave_file)?,
+                b"axml" => self.read_axml_chunk(wave_file)?,
+                b"bext" => self.read_bext_chunk(wave_file)?,
+                b"iXML" => self.read_ixml_chunk(wave_file)?,
+                b"smpl" => self.read_sampler_chunk(wave_file)?,
+                b"inst" => self.read_instrument_chunk(wave_file)?,
+                b"acid" => self.read_acid_chunk(wave_file)?,
+                b"cart" => self.read_cart_chunk(wave_file)?,
+                // The id is lower case as often as not
+                b"id3 " | b"ID3 " => self.read_id3_chunk(wave_file)?,
+                b"levl" => self.read_peak_envelope_chunk(wave_file)?,
+                b"LIST" => {
+                    let list_size = self.read_chunk_size()?;
+                    if (list_size as usize) < BYTES_LIST_TYPE {
+                        return Err(WaveError::InvalidChunkSize(list_size));
+                    }
+                    let list_type = self.byte_stream.read(BYTES_LIST_TYPE)?;
+                    let contents_size = list_size - BYTES_LIST_TYPE as u32;
+
+                    match &list_type[..] {
+                        b"adtl" => self.read_associated_data_list(wave_file, contents_size)?,
+                        b"INFO" => self.read_info_list(wave_file, contents_size)?,
+                        b"wavl" => {
+                            // Wave data can be either a LIST chunk with a 'wavl' list type or
+                            // a 'data' chunk. Either way, the fmt chunk needs to come first.
+                            if !found_fmt {
+                                return Err(WaveError::MissingChunk(*b"fmt "));
+                            }
+                            self.read_wave_list(wave_file, contents_size)?;
+                            found_data = true;
+                        }
+                        // Not a list we know about :/ Hold on to it as it is
+                        _ => {
+                            let mut data = list_type;
+                            data.extend(self.byte_stream.read(contents_size as usize)?);
+                            self.byte_stream.skip(list_size as u64 % 2)?;
+
+                            wave_file.extra_chunks.push(RawChunk {
+                                id: *b"LIST",
+                                data,
+                                offset: chunk_offset,
+                            });
+                        }
+                    }
+                }
+                b"data" => {
+                    if !found_fmt {
+                        return Err(WaveError::MissingChunk(*b"fmt "));
+                    }
+                    // Some encoders split the samples over more than one data chunk.
+                    // They all get added onto the end of the same channels.
+                    self.read_wave_data_chunk(wave_file)?;
+                    found_data = true;
+                }
+                // The structure of a riff file is supposed to be backwards compatible.
+                // So the specifications says to ignore unrecognized chunk_ids.
+                // We keep them anyway, so that writing the file back out doesn't lose anything.
+                _ => {
+                    let data = self.read_chunk_contents()?;
+
+                    if let Some(handler) = self.chunk_handlers.get_mut(&fourcc(&chunk_id)) {
+                        wave_file.custom_chunks.insert(fourcc(&chunk_id), handler.parse_chunk(&data)?);
+                    }
+
+                    wave_file.extra_chunks.push(RawChunk {
+                        id: fourcc(&chunk_id),
+                        data,
+                        offset: chunk_offset,
+                    });
+                }
+            }
+        }
+
+        // Whatever goes wrong from here on is about the file as a whole
+        self.current_chunk = None;
+
+        // The file is supposed to end right where the RIFF chunk says it does
+        let file_end = self.byte_stream.offset + self.byte_stream.skip_to_end()?;
+        if file_end != riff_end && self.options.strict_chunk_sizes {
+            return Err(WaveError::InvalidChunkSize((riff_end - BYTES_CHUNK_HEADER).min(u32::MAX as u64) as u32));
+        } else if file_end != riff_end {
+            wave_file.diagnostics.push(Diagnostic::RiffSizeMismatch {
+                declared: riff_end,
+                actual: file_end,
+            });
+        }
+
+        if !found_data {
+            return Err(WaveError::MissingChunk(*b"data"));
+        }
+
+        Ok(())
+    }
+
+    // Keeps count of the chunks, so a file can't keep us busy with millions of them
+    pub(crate) fn count_chunk(&mut self) -> Result<(), WaveError> {
+        self.chunks_read += 1;
+
+        match self.options.max_chunks {
+            Some(max_chunks) if self.chunks_read > max_chunks => Err(WaveError::TooManyChunks(max_chunks)),
+            _ => Ok(()),
+        }
+    }
+
+    // The associated data list holds labels, notes and text attached to cue points.
+    // There can be any number of each, in any order.
+    fn read_associated_data_list(&mut self, wave_file: &mut WaveFile, contents_size: u32) -> Result<(), WaveError> {
+        let end_list_chunk = self.byte_stream.offset + contents_size as u64;
+
+        // A sub-chunk header needs 8 bytes. Anything less is padding at the end of the list.
+        while self.byte_stream.offset + BYTES_CHUNK_HEADER <= end_list_chunk {
+            let chunk_id: [u8; BYTES_CHUNK_ID] = self.byte_stream.read_array()?;
+
+            match &chunk_id[..] {
+                b"labl" => {
+                    let (cue_id, text) = self.read_cue_text_chunk()?;
+                    wave_file.labels.push(Label { cue_id, text });
+                }
+                b"note" => {
+                    let (cue_id, text) = self.read_cue_text_chunk()?;
+                    wave_file.notes.push(Note { cue_id, text });
+                }
+                b"ltxt" => self.read_labeled_text_chunk(wave_file)?,
+                b"file" => self.read_embedded_file_chunk(wave_file)?,
+                // Anything else does not belong in an 'adtl' list. Skip over it.
+                _ => self.skip_unimplemented_chunk()?,
+            }
+        }
+
+        if self.byte_stream.offset < end_list_chunk {
+            self.byte_stream.skip(end_list_chunk - self.byte_stream.offset)?;
+        }
+
+        Ok(())
+    }
+
+    // labl and note chunks are both a cue point id followed by null terminated text
+    fn read_cue_text_chunk(&mut self) -> Result<(u32, String), WaveError> {
+        let size = self.read_chunk_size()?;
+        if size < 4 {
+            return Err(WaveError::InvalidChunkSize(size));
+        }
+
+        // dwName
+        let cue_id = self.byte_stream.read_u32()?;
+        let text = associated_data::null_terminated_text(&self.byte_stream.read(size as usize - 4)?);
+
+        self.byte_stream.skip(size as u64 % 2)?;
+
+        Ok((cue_id, text))
+    }
+
+    fn read_labeled_text_chunk(&mut self, wave_file: &mut WaveFile) -> Result<(), WaveError> {
+        let size = self.read_chunk_size()?;
+        if size < 20 {
+            return Err(WaveError::InvalidChunkSize(size));
+        }
+
+        // dwName
+        let cue_id = self.byte_stream.read_u32()?;
+        // dwSampleLength
+        let sample_length = self.byte_stream.read_u32()?;
+        // dwPurpose
+        let purpose = self.byte_stream.read_array()?;
+        // wCountry, wLanguage, wDialect and wCodePage. Nobody fills these in.
+        self.byte_stream.skip(8)?;
+
+        let text = associated_data::null_terminated_text(&self.byte_stream.read(size as usize - 20)?);
+        self.byte_stream.skip(size as u64 % 2)?;
+
+        wave_file.regions.push(Region {
+            cue_id,
+            sample_length,
+            purpose,
+            text,
+        });
+
+        Ok(())
+    }
+
+    fn read_embedded_file_chunk(&mut self, wave_file: &mut WaveFile) -> Result<(), WaveError> {
+        let size = self.read_chunk_size()?;
+        if size < 8 {
+            return Err(WaveError::InvalidChunkSize(size));
+        }
+
+        // dwName
+        let cue_id = self.byte_stream.read_u32()?;
+        // dwMedType
+        let media_type = self.byte_stream.read_array()?;
+
+        let data = self.byte_stream.read(size as usize - 8)?;
+        self.byte_stream.skip(size as u64 % 2)?;
+
+        wave_file.embedded_files.push(EmbeddedFile {
+            cue_id,
+            media_type,
+            data,
+        });
+
+        Ok(())
+    }
+
+    // The INFO list is a run of sub-chunks, each holding one piece of null terminated text
+    fn read_info_list(&mut self, wave_file: &mut WaveFile, contents_size: u32) -> Result<(), WaveError> {
+        let end_list_chunk = self.byte_stream.offset + contents_size as u64;
+
+        while self.byte_stream.offset + (BYTES_CHUNK_ID + BYTES_CHUNK_SIZE) as u64 <= end_list_chunk {
+            let id = self.byte_stream.read_array()?;
+            let mut size = self.read_chunk_size()?;
+
+            // An entry can't run past the end of the list. Unless we're being strict, keep
+            // whatever text is inside the list and stop there.
+            let remaining = end_list_chunk - self.byte_stream.offset;
+            if size as u64 > remaining {
+                if self.options.strict_chunk_sizes {
+                    return Err(WaveError::InvalidChunkSize(size));
+                }
+                size = remaining as u32;
+            }
+
+            let text = associated_data::null_terminated_text(&self.byte_stream.read(size as usize)?);
+            if self.byte_stream.offset < end_list_chunk {
+                self.byte_stream.skip(size as u64 % 2)?;
+            }
+
+            wave_file.metadata.set(id, text);
+        }
+
+        if self.byte_stream.offset < end_list_chunk {
+            self.byte_stream.skip(end_list_chunk - self.byte_stream.offset)?;
+        }
+
+        Ok(())
+    }
+
+    fn read_wave_list(&mut self, wave_file: &mut WaveFile, contents_size: u32) -> Result<(), WaveError> {
+        let end_list_chunk = self.byte_stream.offset + contents_size as u64;
+
+        // The contents of a 'wavl` list can be a combination of data and slnt chunks
+        while self.byte_stream.offset < end_list_chunk {
+            let chunk_id: [u8; BYTES_CHUNK_ID] = self.byte_stream.read_array()?;
+
+            match &chunk_id[..] {
+                b"data" => self.read_wave_data_chunk(wave_file)?,
+                b"slnt" => self.read_wave_slnt_chunk(wave_file)?,
+                // Anything else does not belong in a 'wavl' list. Skip over it.
+                _ => self.skip_unimplemented_chunk()?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_fmt_chunk(&mut self, wave_file: &mut WaveFile) -> Result<(), WaveError> {
+        let size = self.read_chunk_size()?;
+        self.read_fmt_contents(wave_file, size)?;
+
+        self.byte_stream.skip(size as u64 % 2)
+    }
+
+    // The fmt chunk minus the id and size, which are different in a Wave64 file
+    pub(crate) fn read_fmt_contents(&mut self, wave_file: &mut WaveFile, size: u32) -> Result<(), WaveError> {
+        if size < 16 {
+            return Err(WaveError::InvalidChunkSize(size));
+        }
+
+        // wFormatTag
+        let w_format_tag = self.byte_stream.read_u16()?;
+
+        // wChannels
+        let w_channels = self.byte_stream.read_u16()?;
+
+        // dwSamplesPerSec
+        let dw_samples_per_second = self.byte_stream.read_u32()?;
+
+        // dwAverageBytesPerSec
+        let dw_average_bytes_per_second = self.byte_stream.read_u32()?;
+
+        // wBlockAlign
+        let w_block_align = self.byte_stream.read_u16()?;
+
+        // wBitsPerSample
+        let w_bits_per_sample = self.byte_stream.read_u16()?;
+        let mut bytes_consumed = 16;
+
+        match self.options.max_channels {
+            Some(max_channels) if w_channels > max_channels => return Err(WaveError::LimitExceeded { name: "channels", limit: max_channels as u64 }),
+            _ => {}
+        }
+
+        // populate the wave file structure
+        wave_file.channels = vec![vec![]; w_channels as usize];
+        wave_file.sample_rate = dw_samples_per_second;
+        wave_file.byte_rate = dw_average_bytes_per_second;
+        wave_file.block_align = w_block_align;
+        wave_file.bits_per_sample = w_bits_per_sample;
+        wave_file.valid_bits_per_sample = w_bits_per_sample;
+        wave_file.channel_mask = 0;
+
+        let mut format_tag = w_format_tag;
+        let mut extension = vec![];
+
+        // cbSize, the size of the extension. Plain PCM chunks usually stop before it.
+        let mut cb_size = 0;
+        if size >= 16 + 2 {
+            cb_size = self.byte_stream.read_u16()?;
+            bytes_consumed += 2;
+        }
+
+        // The extensible format keeps the real format tag inside a GUID in the extension.
+        // It also says how many of the bits in each sample are actually used, and which
+        // speakers the channels are meant for.
+        if w_format_tag == WAVE_FORMAT_EXTENSIBLE && size >= 16 + 2 + 22 {
+            if cb_size < 22 {
+                return Err(WaveError::InvalidChunkSize(size));
+            }
+
+            // wValidBitsPerSample
+            wave_file.valid_bits_per_sample = self.byte_stream.read_u16()?;
+
+            // dwChannelMask
+            wave_file.channel_mask = self.byte_stream.read_u32()?;
+
+            // SubFormat
+            let sub_format = self.byte_stream.read(16)?;
+            bytes_consumed += 22;
+
+            // The GUIDs for the formats we know all share the same last 14 bytes.
+            // The first two bytes are the format tag in little-endian order.
+            format_tag = (sub_format[1] as u16) << 8 | sub_format[0] as u16;
+            if sub_format[2..] != KSDATAFORMAT_SUBTYPE_SUFFIX {
+                // Not one of the GUIDs we know, so the first two bytes don't mean anything.
+                // A codec registered for WAVE_FORMAT_EXTENSIBLE gets the whole extension,
+                // SubFormat included, to work out what to do with the data.
+                format_tag = WAVE_FORMAT_EXTENSIBLE;
+                extension.extend_from_slice(&wave_file.valid_bits_per_sample.to_le_bytes());
+                extension.extend_from_slice(&wave_file.channel_mask.to_le_bytes());
+                extension.extend_from_slice(&sub_format);
+
+                let extra_size = (cb_size as u32 - 22).min(size - bytes_consumed);
+                extension.extend_from_slice(&self.byte_stream.read(extra_size as usize)?);
+                bytes_consumed += extra_size;
+            }
+        } else if w_format_tag == WAVE_FORMAT_EXTENSIBLE {
+            return Err(WaveError::InvalidChunkSize(size));
+        } else if cb_size > 0 {
+            // Hold on to the extension for the decoder. A cbSize that runs past the end of
+            // the chunk is cut short rather than trusted.
+            let extension_size = (cb_size as u32).min(size - bytes_consumed);
+            extension = self.byte_stream.read(extension_size as usize)?;
+            bytes_consumed += extension_size;
+        }
+
+        // Formats we don't know are fine as long as somebody registered a decoder for them,
+        // or the options say the samples can be skipped
+        wave_file.format_tag = format_tag;
+        wave_file.wave_format = match WaveFormatCategory::from_tag(format_tag) {
+            Some(wave_format) => wave_format,
+            None if self.codecs.contains_key(&format_tag) || self.options.allow_unknown_formats => WaveFormatCategory::WAVE_FORMAT_PCM,
+            None => return Err(WaveError::UnsupportedFormat(format_tag)),
+        };
+
+        self.fmt = Some(FmtChunk {
+            format_tag,
+            channels: w_channels,
+            sample_rate: dw_samples_per_second,
+            byte_rate: dw_average_bytes_per_second,
+            block_align: w_block_align,
+            bits_per_sample: w_bits_per_sample,
+            extension,
+        });
+
+        // Anything past what we know about has to be skipped to get to the next chunk
+        self.byte_stream.skip((size - bytes_consumed) as u64)?;
+
+        Ok(())
+    }
+
+    // Hands back the real size of the RIFF chunk
+    fn read_ds64_chunk(&mut self) -> Result<u64, WaveError> {
+        let size = self.read_chunk_size()?;
+        if size < 28 {
+            return Err(WaveError::InvalidChunkSize(size));
+        }
+
+        // riffSize
+        let riff_size = self.byte_stream.read_u64()?;
+
+        // dataSize
+        self.ds64_data_size = Some(self.byte_stream.read_u64()?);
+
+        // sampleCount, then a table of sizes for any other chunks over 4GB.
+        // Nothing but the data chunk gets that big in practice, so the table is skipped.
+        self.byte_stream.skip((size - 16) as u64 + size as u64 % 2)?;
+
+        Ok(riff_size)
+    }
+
+    fn read_fact_chunk(&mut self, wave_file: &mut WaveFile) -> Result<(), WaveError> {
+        let size = self.read_chunk_size()?;
+        self.read_fact_contents(wave_file, size)?;
+
+        self.byte_stream.skip(size as u64 % 2)
+    }
+
+    pub(crate) fn read_fact_contents(&mut self, wave_file: &mut WaveFile, size: u32) -> Result<(), WaveError> {
+        if size < 4 {
+            return Err(WaveError::InvalidChunkSize(size));
+        }
+
+        // dwSampleLength
+        wave_file.fact_sample_count = Some(self.byte_stream.read_u32()?);
+
+        self.byte_stream.skip((size - 4) as u64)
+    }
+    fn read_cue_chunk(&mut self, wave_file: &mut WaveFile) -> Result<(), WaveError> {
+        let size = self.read_chunk_size()?;
+        if size < 4 {
+            return Err(WaveError::InvalidChunkSize(size));
+        }
+
+        // dwCuePoints
+        let num_cue_points = self.byte_stream.read_u32()?;
+        if num_cue_points as u64 * BYTES_CUE_POINT > (size - 4) as u64 {
+            return Err(WaveError::InvalidChunkSize(size));
+        }
+
+        wave_file.cue_points.clear();
+        for _ in 0..num_cue_points {
+            let id = self.byte_stream.read_u32()?;
+            let position = self.byte_stream.read_u32()?;
+            let chunk_id = self.byte_stream.read_array()?;
+            let chunk_start = self.byte_stream.read_u32()?;
+            let block_start = self.byte_stream.read_u32()?;
+            let sample_offset = self.byte_stream.read_u32()?;
+
+            wave_file.cue_points.push(CuePoint {
+                id,
+                position,
+                chunk_id,
+                chunk_start,
+                block_start,
+                sample_offset,
+            });
+        }
+
+        let remaining = (size - 4) as u64 - num_cue_points as u64 * BYTES_CUE_POINT;
+        self.byte_stream.skip(remaining + size as u64 % 2)
+    }
+    fn read_playlist_chunk(&mut self, wave_file: &mut WaveFile) -> Result<(), WaveError> {
+        let size = self.read_chunk_size()?;
+        if size < 4 {
+            return Err(WaveError::InvalidChunkSize(size));
+        }
+
+        // dwSegments
+        let num_segments = self.byte_stream.read_u32()?;
+        if num_segments as u64 * BYTES_PLAYLIST_SEGMENT > (size - 4) as u64 {
+            return Err(WaveError::InvalidChunkSize(size));
+        }
+
+        wave_file.playlist.clear();
+        for _ in 0..num_segments {
+            let cue_id = self.byte_stream.read_u32()?;
+            let length_samples = self.byte_stream.read_u32()?;
+            let loop_count = self.byte_stream.read_u32()?;
+
+            wave_file.playlist.push(PlaylistSegment {
+                cue_id,
+                length_samples,
+                loop_count,
+            });
+        }
+
+        let remaining = (size - 4) as u64 - num_segments as u64 * BYTES_PLAYLIST_SEGMENT;
+        self.byte_stream.skip(remaining + size as u64 % 2)
+    }
+
+    fn read_chna_chunk(&mut self, wave_file: &mut WaveFile) -> Result<(), WaveError> {
+        let contents = self.read_chunk_contents()?;
+        wave_file.audio_ids = adm::read_chna(&contents)?;
+
+        Ok(())
+    }
+
+    fn read_axml_chunk(&mut self, wave_file: &mut WaveFile) -> Result<(), WaveError> {
+        let contents = self.read_chunk_contents()?;
+        wave_file.axml = Some(xml_text(&contents));
+
+        Ok(())
+    }
+
+    fn read_bext_chunk(&mut self, wave_file: &mut WaveFile) -> Result<(), WaveError> {
+        let contents = self.read_chunk_contents()?;
+        wave_file.broadcast_extension = Some(bext::read_bext(&contents)?);
+
+        Ok(())
+    }
+
+    fn read_ixml_chunk(&mut self, wave_file: &mut WaveFile) -> Result<(), WaveError> {
+        let contents = self.read_chunk_contents()?;
+        wave_file.ixml = Some(Ixml { xml: xml_text(&contents) });
+
+        Ok(())
+    }
+
+    fn read_sampler_chunk(&mut self, wave_file: &mut WaveFile) -> Result<(), WaveError> {
+        let contents = self.read_chunk_contents()?;
+        wave_file.sampler = Some(sampler::read_smpl(&contents)?);
+
+        Ok(())
+    }
+
+    fn read_instrument_chunk(&mut self, wave_file: &mut WaveFile) -> Result<(), WaveError> {
+        let contents = self.read_chunk_contents()?;
+        wave_file.instrument = Some(sampler::read_inst(&contents)?);
+
+        Ok(())
+    }
+
+    fn read_acid_chunk(&mut self, wave_file: &mut WaveFile) -> Result<(), WaveError> {
+        let contents = self.read_chunk_contents()?;
+        wave_file.acid = Some(acid::read_acid(&contents)?);
+
+        Ok(())
+    }
+
+    fn read_cart_chunk(&mut self, wave_file: &mut WaveFile) -> Result<(), WaveError> {
+        let contents = self.read_chunk_contents()?;
+        wave_file.cart = Some(cart::read_cart(&contents)?);
+
+        Ok(())
+    }
+
+    fn read_id3_chunk(&mut self, wave_file: &mut WaveFile) -> Result<(), WaveError> {
+        let bytes = self.read_chunk_contents()?;
+        wave_file.id3_tag = Some(Id3Tag { bytes });
+
+        Ok(())
+    }
+
+    fn read_peak_envelope_chunk(&mut self, wave_file: &mut WaveFile) -> Result<(), WaveError> {
+        let contents = self.read_chunk_contents()?;
+        wave_file.peak_envelope = Some(levl::read_levl(&contents)?);
+
+        Ok(())
+    }
+
+    fn read_wave_data_chunk(&mut self, wave_file: &mut WaveFile) -> Result<(), WaveError> {
+        let chunk_offset = self.byte_stream.offset - BYTES_CHUNK_ID as u64;
+        let (declared_size, ds64_data_size) = (self.read_chunk_size()?, self.ds64_data_size);
+        let mut size = match (declared_size, ds64_data_size) {
+            (u32::MAX, Some(ds64_data_size)) => ds64_data_size,
+            (size, _) => size as u64,
+        };
+
+        // Recorders that crash before they finish the file leave a size of 0, or whatever
+        // they had reserved. When repairing, the samples are taken to run to the end of the file.
+        if let Some(file_len) = self.repair_file_len {
+            let remaining = file_len.saturating_sub(self.byte_stream.offset);
+            if (size == 0 || size > remaining) && size != remaining {
+                wave_file.diagnostics.push(Diagnostic::DataSizeRepaired {
+                    declared: size,
+                    repaired: remaining,
+                });
+                size = remaining;
+                self.read_data_contents(wave_file, size)?;
+                return Ok(());
+            }
+        }
+
+        // Live recordings that get written out as they go can't know the sizes up front.
+        // They leave the data size at 0xFFFFFFFF, or at 0 along with the RIFF size.
+        // Either way the samples run to the end of the file.
+        let streaming = match (declared_size, ds64_data_size) {
+            (u32::MAX, None) => true,
+            (0, None) => self.riff_size_unknown,
+            _ => false,
+        };
+        if streaming {
+            return self.read_data_contents(wave_file, DATA_SIZE_UNTIL_EOF);
+        }
+
+        self.read_data_contents(wave_file, size)?;
+
+        // Odd sized chunks are followed by a pad byte.
+        // The pad byte may be missing if the data chunk is the last thing in the file.
+        if !size.is_multiple_of(2) && self.options.strict_chunk_sizes {
+            self.byte_stream.skip(1)?;
+        } else if !size.is_multiple_of(2) && self.byte_stream.read_or_eof(1)?.is_none() {
+            wave_file.diagnostics.push(Diagnostic::MissingPadByte {
+                chunk_id: *b"data",
+                offset: chunk_offset,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn read_data_contents(&mut self, wave_file: &mut WaveFile, size: u64) -> Result<(), WaveError> {
+        let start_data = self.byte_stream.offset;
+        let end_data = start_data.saturating_add(size);
+        let until_eof = size == DATA_SIZE_UNTIL_EOF;
+
+        let has_block_codec = self.codecs.contains_key(&wave_file.format_tag) || is_block_compressed(wave_file.wave_format);
+        let can_decode = has_block_codec || WaveFormatCategory::from_tag(wave_file.format_tag).is_some();
+
+        match self.options.max_data_size {
+            Some(max_data_size) if !until_eof && size > max_data_size => return Err(WaveError::LimitExceeded { name: "bytes of data", limit: max_data_size }),
+            _ => {}
+        }
+
+        if !until_eof && !has_block_codec && wave_file.block_align != 0 && !size.is_multiple_of(wave_file.block_align as u64) {
+            if self.options.strict_chunk_sizes {
+                return Err(WaveError::InvalidChunkSize(size.min(u32::MAX as u64) as u32));
+            }
+            wave_file.diagnostics.push(Diagnostic::PartialFrame {
+                data_size: size,
+                block_align: wave_file.block_align,
+            });
+        }
+
+        let result = if !can_decode {
+            // Only here when the options allow unknown formats. There's nothing we can do with the samples.
+            self.byte_stream.skip(size)
+        } else if self.decode_samples && has_block_codec {
+            self.read_compressed_blocks(wave_file, start_data, end_data)
+        } else if self.decode_samples {
+            self.read_frames(wave_file, start_data, end_data)
+        } else {
+            let result = self.byte_stream.skip(size);
+            self.data_chunks.push((start_data, self.byte_stream.offset - start_data));
+            result
+        };
+
+        match result {
+            // Whatever frames were read in full are kept. The file just ends there.
+            // When reading to the end of the file, that's just how we know we're done.
+            Err(WaveError::UnexpectedEof) if until_eof || self.options.tolerate_truncated_data => {
+                let num_frames = wave_file.channels.iter().map(|c| c.len()).min().unwrap_or(0);
+                for channel in wave_file.channels.iter_mut() {
+                    channel.truncate(num_frames);
+                }
+
+                if until_eof {
+                    return Ok(());
+                }
+                wave_file.diagnostics.push(Diagnostic::TruncatedData {
+                    declared: size,
+                    actual: self.byte_stream.offset - start_data,
+                });
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    // Reads every whole frame up to `end_data`. A frame that's cut short is skipped.
+    fn read_frames(&mut self, wave_file: &mut WaveFile, start_data: u64, end_data: u64) -> Result<(), WaveError> {
+        let encoding = SampleEncoding::new(wave_file.wave_format, wave_file.bits_per_sample)?;
+
+        if self.byte_stream.offset < end_data {
+            self.decode_frames(&mut wave_file.channels, encoding, start_data, end_data)?;
+        }
+
+        self.byte_stream.skip(end_data - self.byte_stream.offset)
+    }
+
+    // Decodes the whole frames up to `end_data` (or up to the end of the stream, whichever comes
+    // first) onto the ends of the channels. Running out of stream part way is still an
+    // `UnexpectedEof`, but every whole frame before that has been added.
+    // The samples in a frame are interleaved in channel order: for stereo that is left then right,
+    // for anything bigger it is whatever order the channel mask (or the application) says.
+    pub(crate) fn decode_frames(&mut self, channels: &mut [Vec<Sample>], encoding: SampleEncoding, start_data: u64, end_data: u64) -> Result<(), WaveError> {
+        // Without any channels a frame is empty, and we would never get through the data
+        if channels.is_empty() {
+            return Err(WaveError::UnsupportedChannelCount(0));
+        }
+
+        let frame_size = channels.len() * encoding.size();
+        let num_frames = end_data.saturating_sub(self.byte_stream.offset) / frame_size as u64;
+        for channel in channels.iter_mut() {
+            channel.reserve(num_frames.min(MAX_PREALLOCATED_FRAMES) as usize);
+        }
+
+        // The data is read a batch of whole frames at a time and decoded straight out of the batch
+        let frames_per_batch = (BYTES_DECODE_BATCH / frame_size).max(1) as u64;
+        let mut remaining = num_frames;
+        while remaining > 0 {
+            let batch_frames = remaining.min(frames_per_batch);
+            let batch = self.byte_stream.read_up_to(batch_frames as usize * frame_size)?;
+            let whole_frames = (batch.len() / frame_size) as u64;
+
+            let data_read = self.byte_stream.offset - start_data - (batch.len() % frame_size) as u64;
+            check_limits(&self.options, channels, data_read, whole_frames)?;
+
+            for frame in batch.chunks_exact(frame_size) {
+                for (channel, sample) in channels.iter_mut().zip(frame.chunks_exact(encoding.size())) {
+                    channel.push(encoding.decode(sample, self.byte_stream.big_endian));
+                }
+            }
+
+            if whole_frames < batch_frames {
+                return Err(WaveError::UnexpectedEof);
+            }
+            remaining -= batch_frames;
+
+            report_progress(&self.options, start_data, self.byte_stream.offset, end_data);
+        }
+
+        Ok(())
+    }
+
+    // Compressed formats are decoded a block at a time rather than a frame at a time
+    fn read_compressed_blocks(&mut self, wave_file: &mut WaveFile, start_data: u64, end_data: u64) -> Result<(), WaveError> {
+        if wave_file.block_align == 0 {
+            return Err(WaveError::InvalidChunkSize(0));
+        }
+        if wave_file.channels.is_empty() {
+            return Err(WaveError::UnsupportedChannelCount(0));
+        }
+
+        let fmt = match self.fmt.as_ref() {
+            Some(fmt) => fmt,
+            None => return Err(WaveError::MissingChunk(*b"fmt ")),
+        };
+
+        // A registered decoder wins over the built in one
+        let mut builtin = codec::builtin_codec(wave_file.wave_format);
+        let codec = match (self.codecs.get_mut(&fmt.format_tag), builtin.as_mut()) {
+            (Some(codec), _) | (None, Some(codec)) => codec,
+            (None, None) => return Err(WaveError::UnsupportedFormat(fmt.format_tag)),
+        };
+
+        let mut last_progress = start_data;
+        while self.byte_stream.offset < end_data {
+            let block_size = (end_data - self.byte_stream.offset).min(wave_file.block_align as u64);
+            let block = self.byte_stream.read(block_size as usize)?;
+
+            for frame in codec.decode_block(&block, fmt)? {
+                if frame.len() != wave_file.channels.len() {
+                    return Err(WaveError::ChannelLengthMismatch);
+                }
+
+                for (channel, sample) in wave_file.channels.iter_mut().zip(frame) {
+                    channel.push(sample);
+                }
+            }
+
+            check_limits(&self.options, &wave_file.channels, self.byte_stream.offset - start_data, 0)?;
+
+            // Blocks are small, so only every so often
+            if self.byte_stream.offset - last_progress >= BYTES_DECODE_BATCH as u64 || self.byte_stream.offset >= end_data {
+                report_progress(&self.options, start_data, self.byte_stream.offset, end_data);
+                last_progress = self.byte_stream.offset;
+            }
+        }
+
+        Ok(())
+    }
+
+    // A slnt chunk stands in for a stretch of silence, so it doesn't have to be stored as samples.
+    // It holds the number of silent samples (per channel).
+    fn read_wave_slnt_chunk(&mut self, wave_file: &mut WaveFile) -> Result<(), WaveError> {
+        let size = self.read_chunk_size()?;
+        if size < 4 {
+            return Err(WaveError::InvalidChunkSize(size));
+        }
+
+        // dwSamples
+        let count = self.byte_stream.read_u32()?;
+        self.byte_stream.skip((size - 4) as u64 + size as u64 % 2)?;
+
+        // The WaveReader only reads samples that are actually in the file
+        if !self.decode_samples {
+            return Ok(());
+        }
+
+        // The chunk is a few bytes however much silence it claims, so the silence isn't bounded by
+        // the size of the file the way samples are. When there's a fact chunk, it says how many
+        // samples the whole file holds, and there can't be more silence than that.
+        let num_frames = wave_file.channels.first().map_or(0, |c| c.len() as u64);
+        match wave_file.fact_sample_count {
+            Some(fact_sample_count) if num_frames + count as u64 > fact_sample_count as u64 => {
+                return Err(WaveError::LimitExceeded { name: "frames in the fact chunk", limit: fact_sample_count as u64 });
+            }
+            _ => {}
+        }
+        check_limits(&self.options, &wave_file.channels, 0, count as u64)?;
+
+        let silence = silent_sample(wave_file.wave_format, wave_file.bits_per_sample)?;
+        for channel in wave_file.channels.iter_mut() {
+            channel.resize(channel.len() + count as usize, silence.clone());
+        }
+
+        Ok(())
+    }
+
+    // Utility Methods
+    // expect_id: To check the next four bytes are `expected`
+    // read_chunk_size: To read a little-endian chunk size field
+    // read_chunk_contents: To read a whole chunk into memory
+    // skip_unimplemented_chunk: To move past a chunk we don't care about
+
+    // Attempts to match the next four bytes to `expected`
+    // The bytes are consumed either way since there is no going back in the stream.
+    fn expect_id(&mut self, expected: &[u8; 4]) -> Result<(), WaveError> {
+        let found = self.byte_stream.read_array()?;
+        if &found != expected {
+            return Err(WaveError::UnexpectedChunk { expected: *expected, found });
+        }
+
+        Ok(())
+    }
+
+    // Read the chunk size field as 32 bit unsigned integer.
+    // Little-endian, like every number in a .wav file (RIFX aside)
+    fn read_chunk_size(&mut self) -> Result<u32, WaveError> {
+        self.byte_stream.read_u32()
+    }
+
+    // Read the whole of a chunk into memory, for the chunks that are easier to pick apart that way
+    fn read_chunk_contents(&mut self) -> Result<Vec<u8>, WaveError> {
+        let size = self.read_chunk_size()?;
+        let contents = self.byte_stream.read(size as usize)?;
+        self.byte_stream.skip(size as u64 % 2)?;
+
+        Ok(contents)
+    }
+
+    // Placeholder
+    fn skip_unimplemented_chunk(&mut self) -> Result<(), WaveError> {
+        let size = self.read_chunk_size()?;
+
+        self.byte_stream.skip(size as u64 + size as u64 % 2)
+    }
+}
+
+// The first four bytes as a chunk id
+pub(crate) fn fourcc(bytes: &[u8]) -> [u8; 4] {
+    let mut id = [0; 4];
+    id.copy_from_slice(&bytes[..4]);
+
+    id
+}
+
+// The XML chunks are often padded out with nulls so they can be edited in place
+fn xml_text(contents: &[u8]) -> String {
+    String::from_utf8_lossy(contents).trim_end_matches('\0').to_string()
+}
+
+// Makes sure that reading `data_read` bytes of a data chunk, and adding `extra_frames` frames
+// to the ones we already have, stays within the limits in the options
+fn check_limits(options: &ParserOptions, channels: &[Vec<Sample>], data_read: u64, extra_frames: u64) -> Result<(), WaveError> {
+    if let Some(max_data_size) = options.max_data_size {
+        if data_read > max_data_size {
+            return Err(WaveError::LimitExceeded { name: "bytes of data", limit: max_data_size });
+        }
+    }
+
+    if let Some(max_sample_memory) = options.max_sample_memory {
+        let num_frames = channels.first().map_or(0, |c| c.len()) as u64 + extra_frames;
+        let memory = num_frames.saturating_mul(channels.len() as u64).saturating_mul(mem::size_of::<Sample>() as u64);
+        if memory > max_sample_memory {
+            return Err(WaveError::LimitExceeded { name: "bytes of sample memory", limit: max_sample_memory });
+        }
+    }
+
+    Ok(())
+}
+
+// Lets the progress callback in the options, if there is one, know how far into the data chunk we are
+fn report_progress(options: &ParserOptions, start_data: u64, offset: u64, end_data: u64) {
+    if let Some(on_progress) = options.on_progress.as_ref() {
+        // A data chunk that runs to the end of the file has no size to go by
+        let data_size = if end_data == DATA_SIZE_UNTIL_EOF { 0 } else { end_data - start_data };
+        on_progress.call(offset - start_data, data_size);
+    }
+}
+
+// The sample that means "nothing". 8 bit samples are unsigned, so that is the midpoint.
+// The other integer formats (and everything that decodes to 16 bits) are signed, so it's 0.
+pub(crate) fn silent_sample(wave_format: WaveFormatCategory, bit_depth: u16) -> Result<Sample, WaveError> {
+    match wave_format {
+        WaveFormatCategory::WAVE_FORMAT_IEEE_FLOAT if bit_depth == 64 => Ok(Sample::Float64(0.0)),
+        WaveFormatCategory::WAVE_FORMAT_IEEE_FLOAT => Ok(Sample::Float32(0.0)),
+        WaveFormatCategory::WAVE_FORMAT_PCM => match bit_depth {
+            0..=8 => Ok(Sample::BitDepth8(0x80)),
+            9..=16 => Ok(Sample::BitDepth16(0)),
+            17..=24 => Ok(Sample::BitDepth24(0)),
+            25..=32 => Ok(Sample::BitDepth32(0)),
+            _ => Err(WaveError::UnsupportedBitDepth(bit_depth)),
+        },
+        _ => Ok(Sample::BitDepth16(0)),
+    }
+}
+
+// Whether the samples are packed into blocks that have to be decoded as a whole
+pub(crate) fn is_block_compressed(wave_format: WaveFormatCategory) -> bool {
+    codec::builtin_codec(wave_format).is_some()
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use std::io::{self, Read};
+    use std::mem;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use super::{WaveFileParser, KSDATAFORMAT_SUBTYPE_SUFFIX};
+    use crate::associated_data::{Label, Note};
+    use crate::codec::WaveCodec;
+    use crate::cue::PlaylistSegment;
+    use crate::diagnostic::Diagnostic;
+    use crate::error::WaveError;
+    use crate::fmt_chunk::FmtChunk;
+    use crate::options::{ParserOptions, DEFAULT_MAX_SAMPLE_MEMORY};
+    use crate::raw_chunk::RawChunk;
+    use crate::test_utils::{fmt_bytes, riff_bytes, wave64_bytes, wave_bytes};
+    use crate::testing::WaveFixture;
+    use crate::wave_file::{Sample, WaveFormatCategory};
+
+    fn three_seconds(channels: u16, bits_per_sample: u16, sample_rate: u32) -> WaveFixture {
+        WaveFixture::new(channels, bits_per_sample, sample_rate).duration(Duration::from_secs(3))
+    }
+
+    #[test]
+    fn test_parsing_simple_wav_file() {
+        let wave_file = WaveFileParser::parse(three_seconds(1, 8, 8000).to_bytes()).unwrap();
+
+        assert_eq!(1, wave_file.channels.len());
+        assert_eq!(8, wave_file.bits_per_sample);
+        assert_eq!(8000, wave_file.sample_rate);
+        assert_eq!(24000, wave_file.channels[0].len());
+    }
+    #[test]
+    fn test_parsing_two_channel_audio() {
+        let fixture = three_seconds(2, 8, 8000);
+        let wave_file = WaveFileParser::parse(fixture.to_bytes()).unwrap();
+
+        assert_eq!(2, wave_file.channels.len());
+        assert_eq!(8, wave_file.bits_per_sample);
+        assert_eq!(8000, wave_file.sample_rate);
+        assert_eq!(Sample::BitDepth8(fixture.sample(10, 1) as u8), wave_file.channels[1][10]);
+    }
+
+    #[test]
+    fn test_parsing_16000_sample_rate() {
+        let wave_file = WaveFileParser::parse(three_seconds(1, 8, 16000).to_bytes()).unwrap();
+
+        assert_eq!(1, wave_file.channels.len());
+        assert_eq!(8, wave_file.bits_per_sample);
+        assert_eq!(16000, wave_file.sample_rate);
+        assert_eq!(48000, wave_file.channels[0].len());
+    }
+
+    #[test]
+    fn test_parsing_16bit_samples() {
+        let fixture = three_seconds(1, 16, 8000);
+        let wave_file = WaveFileParser::parse(fixture.to_bytes()).unwrap();
+
+        assert_eq!(1, wave_file.channels.len());
+        assert_eq!(16, wave_file.bits_per_sample);
+        assert_eq!(8000, wave_file.sample_rate);
+        assert_eq!(Sample::BitDepth16(fixture.sample(300, 0) as i16), wave_file.channels[0][300]);
+    }
+
+    #[test]
+    fn test_parsing_wave_file_with_metadata() {
+        let bytes = three_seconds(1, 16, 8000).info(*b"INAM", "Test").to_bytes();
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        assert_eq!(Some("Test"), wave_file.metadata.title());
+        assert_eq!(24000, wave_file.channels[0].len());
+    }
+
+    #[test]
+    fn test_parsing_24bit_samples() {
+        let data = [0x01, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f, 0x00, 0x00, 0x80];
+        let wave_file = WaveFileParser::parse(wave_bytes(1, 1, 24, &data)).unwrap();
+
+        assert_eq!(24, wave_file.bits_per_sample);
+        assert_eq!(
+            vec![
+                Sample::BitDepth24(1),
+                Sample::BitDepth24(-1),
+                Sample::BitDepth24(8_388_607),
+                Sample::BitDepth24(-8_388_608),
+            ],
+            wave_file.channels[0]
+        );
+    }
+
+    #[test]
+    fn test_parsing_32bit_samples() {
+        let data = [0xff, 0xff, 0xff, 0x7f, 0x00, 0x00, 0x00, 0x80];
+        let wave_file = WaveFileParser::parse(wave_bytes(1, 1, 32, &data)).unwrap();
+
+        assert_eq!(vec![Sample::BitDepth32(i32::MAX), Sample::BitDepth32(i32::MIN)], wave_file.channels[0]);
+    }
+
+    #[test]
+    fn test_parsing_float_samples() {
+        let mut data = vec![];
+        data.extend_from_slice(&0.5f32.to_le_bytes());
+        data.extend_from_slice(&(-1.0f32).to_le_bytes());
+        let wave_file = WaveFileParser::parse(wave_bytes(3, 1, 32, &data)).unwrap();
+
+        assert_eq!(WaveFormatCategory::WAVE_FORMAT_IEEE_FLOAT, wave_file.wave_format);
+        assert_eq!(vec![Sample::Float32(0.5), Sample::Float32(-1.0)], wave_file.channels[0]);
+
+        let data = 0.25f64.to_le_bytes();
+        let wave_file = WaveFileParser::parse(wave_bytes(3, 1, 64, &data)).unwrap();
+
+        assert_eq!(vec![Sample::Float64(0.25)], wave_file.channels[0]);
+    }
+
+    #[test]
+    fn test_parsing_extensible_format() {
+        // 24 valid bits in a 32 bit container, front left + front right
+        let mut fmt = fmt_bytes(0xfffe, 2, 32);
+        fmt.extend_from_slice(&22u16.to_le_bytes());
+        fmt.extend_from_slice(&24u16.to_le_bytes());
+        fmt.extend_from_slice(&0x0000_0003u32.to_le_bytes());
+        fmt.extend_from_slice(&[0x01, 0x00]);
+        fmt.extend_from_slice(&KSDATAFORMAT_SUBTYPE_SUFFIX);
+
+        let wave_file = WaveFileParser::parse(riff_bytes(&[(b"fmt ", &fmt), (b"data", &[0; 16])])).unwrap();
+
+        assert_eq!(WaveFormatCategory::WAVE_FORMAT_PCM, wave_file.wave_format);
+        assert_eq!(32, wave_file.bits_per_sample);
+        assert_eq!(24, wave_file.valid_bits_per_sample);
+        assert_eq!(0x0000_0003, wave_file.channel_mask);
+        assert_eq!(2, wave_file.channels.len());
+    }
+
+    #[test]
+    fn test_parsing_extensible_format_with_unknown_sub_format() {
+        // Each byte is a sample, whatever the SubFormat says
+        struct Bytes {}
+        impl WaveCodec for Bytes {
+            fn decode_block(&mut self, block: &[u8], fmt: &FmtChunk) -> Result<Vec<Vec<Sample>>, WaveError> {
+                assert_eq!(Some([0xab; 16]), fmt.sub_format());
+                assert_eq!(&[1, 2][..], &fmt.extension[22..]);
+                Ok(block.iter().map(|&b| vec![Sample::BitDepth8(b)]).collect())
+            }
+        }
+
+        let mut fmt = fmt_bytes(0xfffe, 1, 8);
+        fmt.extend_from_slice(&24u16.to_le_bytes());
+        fmt.extend_from_slice(&8u16.to_le_bytes());
+        fmt.extend_from_slice(&0x0000_0004u32.to_le_bytes());
+        fmt.extend_from_slice(&[0xab; 16]);
+        fmt.extend_from_slice(&[1, 2]);
+        let bytes = riff_bytes(&[(b"fmt ", &fmt), (b"data", &[1, 2, 3])]);
+
+        assert_eq!(Err(WaveError::UnsupportedFormat(0xfffe)), WaveFileParser::parse(bytes.clone()).map(|_| ()).map_err(|e| e.kind().clone()));
+
+        let mut parser = WaveFileParser::new();
+        parser.register_codec(0xfffe, Bytes {});
+        let wave_file = parser.read_from(&bytes[..]).unwrap();
+
+        assert_eq!(0xfffe, wave_file.format_tag);
+        assert_eq!(0x0000_0004, wave_file.channel_mask);
+        assert_eq!(vec![Sample::BitDepth8(1), Sample::BitDepth8(2), Sample::BitDepth8(3)], wave_file.channels[0]);
+    }
+
+    #[test]
+    fn test_parsing_six_channel_audio() {
+        // Two 5.1 frames, each channel holding its own index (plus 10 for the second frame)
+        let data: Vec<u8> = (0..12).map(|i| if i < 6 { i } else { i + 4 }).collect();
+        let wave_file = WaveFileParser::parse(wave_bytes(1, 6, 8, &data)).unwrap();
+
+        assert_eq!(6, wave_file.channels.len());
+        for (i, channel) in wave_file.channels.iter().enumerate() {
+            assert_eq!(vec![Sample::BitDepth8(i as u8), Sample::BitDepth8(i as u8 + 10)], *channel);
+        }
+    }
+
+    #[test]
+    fn test_parsing_zero_channels_returns_error() {
+        let result = WaveFileParser::parse(wave_bytes(1, 0, 8, &[0x80, 0x80]));
+
+        assert_eq!(Err(WaveError::UnsupportedChannelCount(0)), result.map(|_| ()).map_err(|e| e.kind().clone()));
+    }
+
+    #[test]
+    fn test_parsing_alaw_samples() {
+        let wave_file = WaveFileParser::parse(wave_bytes(6, 1, 8, &[0xd5, 0x2a])).unwrap();
+
+        assert_eq!(WaveFormatCategory::WAVE_FORMAT_ALAW, wave_file.wave_format);
+        assert_eq!(vec![Sample::BitDepth16(8), Sample::BitDepth16(-32256)], wave_file.channels[0]);
+    }
+
+    #[test]
+    fn test_parsing_mulaw_samples() {
+        let wave_file = WaveFileParser::parse(wave_bytes(7, 1, 8, &[0xff, 0x80])).unwrap();
+
+        assert_eq!(WaveFormatCategory::WAVE_FORMAT_MULAW, wave_file.wave_format);
+        assert_eq!(vec![Sample::BitDepth16(0), Sample::BitDepth16(32124)], wave_file.channels[0]);
+    }
+
+    #[test]
+    fn test_parsing_ima_adpcm_blocks_truncated_to_fact_count() {
+        // Two 8 byte mono blocks, each decoding to 9 samples. The fact chunk says there are 12.
+        let mut fmt = fmt_bytes(0x0011, 1, 4);
+        fmt[12..14].copy_from_slice(&8u16.to_le_bytes());
+        fmt.extend_from_slice(&2u16.to_le_bytes());
+        fmt.extend_from_slice(&9u16.to_le_bytes());
+        let block = [0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        let data = [block, block].concat();
+
+        let bytes = riff_bytes(&[(b"fmt ", &fmt), (b"fact", &12u32.to_le_bytes()), (b"data", &data)]);
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        assert_eq!(WaveFormatCategory::WAVE_FORMAT_IMA_ADPCM, wave_file.wave_format);
+        assert_eq!(Some(12), wave_file.fact_sample_count);
+        assert_eq!(12, wave_file.channels[0].len());
+        assert_eq!(Sample::BitDepth16(16), wave_file.channels[0][9]);
+    }
+
+    #[test]
+    fn test_parsing_ms_adpcm_with_coefficients_from_fmt() {
+        // One coefficient pair, (512, -256), which carries on in a straight line
+        let mut fmt = fmt_bytes(0x0002, 1, 4);
+        fmt[12..14].copy_from_slice(&8u16.to_le_bytes());
+        fmt.extend_from_slice(&8u16.to_le_bytes());
+        fmt.extend_from_slice(&[4, 0, 1, 0, 0x00, 0x02, 0x00, 0xff]);
+        let data = [0, 16, 0, 20, 0, 10, 0, 0x00];
+
+        let bytes = riff_bytes(&[(b"fmt ", &fmt), (b"data", &data)]);
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        let expected: Vec<Sample> = [10, 20, 30, 40].iter().map(|&s| Sample::BitDepth16(s)).collect();
+        assert_eq!(WaveFormatCategory::WAVE_FORMAT_ADPCM, wave_file.wave_format);
+        assert_eq!(expected, wave_file.channels[0]);
+    }
+
+    #[test]
+    fn test_parsing_with_registered_codec_for_unknown_format() {
+        // Each byte of this made up format is a sample, repeated on both channels
+        struct Doubler {}
+        impl WaveCodec for Doubler {
+            fn decode_block(&mut self, block: &[u8], _fmt: &FmtChunk) -> Result<Vec<Vec<Sample>>, WaveError> {
+                Ok(block.iter().map(|&b| vec![Sample::BitDepth8(b), Sample::BitDepth8(b)]).collect())
+            }
+        }
+
+        let mut fmt = fmt_bytes(0x1234, 2, 8);
+        fmt[12..14].copy_from_slice(&2u16.to_le_bytes());
+        let bytes = riff_bytes(&[(b"fmt ", &fmt), (b"data", &[1, 2, 3])]);
+
+        assert_eq!(Err(WaveError::UnsupportedFormat(0x1234)), WaveFileParser::parse(bytes.clone()).map(|_| ()).map_err(|e| e.kind().clone()));
+
+        let mut parser = WaveFileParser::new();
+        parser.register_codec(0x1234, Doubler {});
+        let wave_file = parser.read_from(&bytes[..]).unwrap();
+
+        assert_eq!(0x1234, wave_file.format_tag);
+        assert_eq!(vec![Sample::BitDepth8(1), Sample::BitDepth8(2), Sample::BitDepth8(3)], wave_file.channels[1]);
+    }
+
+    #[test]
+    fn test_parsing_rf64_uses_ds64_data_size() {
+        let mut ds64 = vec![];
+        ds64.extend_from_slice(&0u64.to_le_bytes());
+        ds64.extend_from_slice(&4u64.to_le_bytes());
+        ds64.extend_from_slice(&2u64.to_le_bytes());
+        ds64.extend_from_slice(&0u32.to_le_bytes());
+
+        let mut bytes = riff_bytes(&[(b"ds64", &ds64), (b"fmt ", &fmt_bytes(1, 1, 16)), (b"data", &[1, 0, 2, 0])]);
+        bytes[0..4].copy_from_slice(b"RF64");
+        bytes[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        let data_size_offset = bytes.len() - 8;
+        bytes[data_size_offset..data_size_offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        assert_eq!(vec![Sample::BitDepth16(1), Sample::BitDepth16(2)], wave_file.channels[0]);
+    }
+
+    #[test]
+    fn test_parsing_rf64_without_ds64_returns_error() {
+        let mut bytes = wave_bytes(1, 1, 16, &[0, 0]);
+        bytes[0..4].copy_from_slice(b"RF64");
+
+        let expected = WaveError::UnexpectedChunk { expected: *b"ds64", found: *b"fmt " };
+        assert_eq!(Err(expected), WaveFileParser::parse(bytes).map(|_| ()).map_err(|e| e.kind().clone()));
+    }
+
+    #[test]
+    fn test_parsing_bw64_adm_chunks() {
+        let mut ds64 = vec![0; 28];
+        ds64[8..16].copy_from_slice(&2u64.to_le_bytes());
+
+        let mut chna = vec![1, 0, 1, 0, 1, 0];
+        chna.extend_from_slice(b"ATU_00000001AT_00010001_01AP_00010001\0");
+        let axml = b"<ebuCoreMain/>";
+
+        let mut bytes = riff_bytes(&[(b"ds64", &ds64), (b"fmt ", &fmt_bytes(1, 1, 16)), (b"chna", &chna), (b"axml", axml), (b"data", &[0, 0])]);
+        bytes[0..4].copy_from_slice(b"BW64");
+
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        assert_eq!(1, wave_file.audio_ids.len());
+        assert_eq!(1, wave_file.audio_ids[0].track_index);
+        assert_eq!("ATU_00000001", wave_file.audio_ids[0].uid);
+        assert_eq!("AP_00010001", wave_file.audio_ids[0].pack_ref);
+        assert_eq!(Some("<ebuCoreMain/>".to_string()), wave_file.axml);
+    }
+
+    #[test]
+    fn test_parsing_rifx_big_endian_file() {
+        let mut fmt = vec![];
+        fmt.extend_from_slice(&1u16.to_be_bytes());
+        fmt.extend_from_slice(&2u16.to_be_bytes());
+        fmt.extend_from_slice(&8000u32.to_be_bytes());
+        fmt.extend_from_slice(&32000u32.to_be_bytes());
+        fmt.extend_from_slice(&4u16.to_be_bytes());
+        fmt.extend_from_slice(&16u16.to_be_bytes());
+
+        let mut bytes = vec![];
+        bytes.extend_from_slice(b"RIFX");
+        bytes.extend_from_slice(&36u32.to_be_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_be_bytes());
+        bytes.extend_from_slice(&fmt);
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&4u32.to_be_bytes());
+        bytes.extend_from_slice(&[0x01, 0x02, 0xff, 0xfe]);
+
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        assert_eq!(8000, wave_file.sample_rate);
+        assert_eq!(vec![Sample::BitDepth16(0x0102)], wave_file.channels[0]);
+        assert_eq!(vec![Sample::BitDepth16(-2)], wave_file.channels[1]);
+    }
+
+    #[test]
+    fn test_parsing_fewer_samples_than_fact_count_returns_error() {
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(7, 1, 8)), (b"fact", &3u32.to_le_bytes()), (b"data", &[0xff, 0xff])]);
+
+        assert_eq!(Err(WaveError::SampleCountMismatch(3)), WaveFileParser::parse(bytes).map(|_| ()).map_err(|e| e.kind().clone()));
+    }
+
+    #[test]
+    fn test_parsing_cue_points() {
+        let mut cue = 2u32.to_le_bytes().to_vec();
+        for (id, position) in [(1u32, 0u32), (2, 3)].iter() {
+            cue.extend_from_slice(&id.to_le_bytes());
+            cue.extend_from_slice(&position.to_le_bytes());
+            cue.extend_from_slice(b"data");
+            cue.extend_from_slice(&[0; 8]);
+            cue.extend_from_slice(&position.to_le_bytes());
+        }
+
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"cue ", &cue), (b"data", &[0x80; 4])]);
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        assert_eq!(2, wave_file.cue_points.len());
+        assert_eq!(2, wave_file.cue_points[1].id);
+        assert_eq!(3, wave_file.cue_points[1].position);
+        assert_eq!(*b"data", wave_file.cue_points[1].chunk_id);
+        assert_eq!(3, wave_file.cue_points[1].sample_offset);
+    }
+
+    #[test]
+    fn test_parsing_cue_chunk_with_too_many_points_returns_error() {
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"cue ", &5u32.to_le_bytes()), (b"data", &[0x80; 4])]);
+
+        assert_eq!(Err(WaveError::InvalidChunkSize(4)), WaveFileParser::parse(bytes).map(|_| ()).map_err(|e| e.kind().clone()));
+    }
+
+    #[test]
+    fn test_parsing_playlist_segments() {
+        let mut plst = 1u32.to_le_bytes().to_vec();
+        plst.extend_from_slice(&2u32.to_le_bytes());
+        plst.extend_from_slice(&100u32.to_le_bytes());
+        plst.extend_from_slice(&3u32.to_le_bytes());
+
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"plst", &plst), (b"data", &[0x80; 4])]);
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        let expected = PlaylistSegment {
+            cue_id: 2,
+            length_samples: 100,
+            loop_count: 3,
+        };
+        assert_eq!(vec![expected], wave_file.playlist);
+    }
+
+    #[test]
+    fn test_parsing_associated_data() {
+        let mut ltxt = vec![1, 0, 0, 0, 100, 0, 0, 0];
+        ltxt.extend_from_slice(b"rgn ");
+        ltxt.extend_from_slice(&[0; 8]);
+        ltxt.extend_from_slice(b"Intro\0");
+
+        let mut adtl = b"adtl".to_vec();
+        for (id, contents) in [(b"labl", &b"\x01\x00\x00\x00Verse\0"[..]), (b"note", b"\x01\x00\x00\x00Too loud\0"), (b"ltxt", &ltxt), (b"file", &b"\x02\x00\x00\x00MIDIMThd"[..])].iter() {
+            adtl.extend_from_slice(*id);
+            adtl.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+            adtl.extend_from_slice(contents);
+            if contents.len() % 2 == 1 {
+                adtl.push(0);
+            }
+        }
+
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"LIST", &adtl), (b"data", &[0x80; 4])]);
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        assert_eq!(vec![Label { cue_id: 1, text: "Verse".to_string() }], wave_file.labels);
+        assert_eq!(vec![Note { cue_id: 1, text: "Too loud".to_string() }], wave_file.notes);
+        assert_eq!(100, wave_file.regions[0].sample_length);
+        assert_eq!(*b"rgn ", wave_file.regions[0].purpose);
+        assert_eq!("Intro", wave_file.regions[0].text);
+        assert_eq!(2, wave_file.embedded_files[0].cue_id);
+        assert_eq!(*b"MIDI", wave_file.embedded_files[0].media_type);
+        assert_eq!(b"MThd".to_vec(), wave_file.embedded_files[0].data);
+    }
+
+    #[test]
+    fn test_parsing_associated_data_in_any_order() {
+        let mut adtl = b"adtl".to_vec();
+        for (id, contents) in [(b"note", &b"\x02\x00\x00\x00Fix\0"[..]), (b"labl", b"\x01\x00\x00\x00A\0"), (b"labl", b"\x02\x00\x00\x00B\0")].iter() {
+            adtl.extend_from_slice(*id);
+            adtl.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+            adtl.extend_from_slice(contents);
+            if contents.len() % 2 == 1 {
+                adtl.push(0);
+            }
+        }
+
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"LIST", &adtl), (b"data", &[0x80; 4])]);
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        assert_eq!(2, wave_file.labels.len());
+        assert_eq!("B", wave_file.labels[1].text);
+        assert_eq!(vec![Note { cue_id: 2, text: "Fix".to_string() }], wave_file.notes);
+        assert!(wave_file.regions.is_empty());
+        assert!(wave_file.embedded_files.is_empty());
+    }
+
+    #[test]
+    fn test_parsing_info_list() {
+        let mut info = b"INFO".to_vec();
+        for (id, text) in [(b"INAM", &b"Take 3\0"[..]), (b"IART", b"The Band\0"), (b"ICRD", b"2024-01-01\0")].iter() {
+            info.extend_from_slice(*id);
+            info.extend_from_slice(&(text.len() as u32).to_le_bytes());
+            info.extend_from_slice(text);
+            if text.len() % 2 == 1 {
+                info.push(0);
+            }
+        }
+
+        let bytes = riff_bytes(&[(b"LIST", &info), (b"fmt ", &fmt_bytes(1, 1, 8)), (b"data", &[0x80; 4])]);
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        assert_eq!(Some("Take 3"), wave_file.metadata.title());
+        assert_eq!(Some("The Band"), wave_file.metadata.artist());
+        assert_eq!(Some("2024-01-01"), wave_file.metadata.date());
+    }
+
+    #[test]
+    fn test_parsing_info_entry_longer_than_the_list() {
+        let mut info = b"INFO".to_vec();
+        info.extend_from_slice(b"INAM");
+        info.extend_from_slice(&0xffff_fff0u32.to_le_bytes());
+        info.extend_from_slice(b"Take 3\0\0");
+
+        let bytes = riff_bytes(&[(b"LIST", &info), (b"fmt ", &fmt_bytes(1, 1, 8)), (b"data", &[0x80; 4])]);
+        let wave_file = WaveFileParser::parse(bytes.clone()).unwrap();
+
+        assert_eq!(Some("Take 3"), wave_file.metadata.title());
+        assert_eq!(4, wave_file.channels[0].len());
+
+        let options = ParserOptions::new().strict_chunk_sizes(true);
+        assert_eq!(Err(WaveError::InvalidChunkSize(0xffff_fff0)), WaveFileParser::parse_with_options(bytes, options).map(|_| ()).map_err(|e| e.kind().clone()));
+    }
+
+    #[test]
+    fn test_parsing_bext_chunk() {
+        let mut bext = vec![0; 602];
+        bext[256..260].copy_from_slice(b"ACME");
+        bext[338..342].copy_from_slice(&48000u32.to_le_bytes());
+
+        let bytes = riff_bytes(&[(b"bext", &bext), (b"fmt ", &fmt_bytes(1, 1, 8)), (b"data", &[0x80; 4])]);
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        let bext = wave_file.broadcast_extension.unwrap();
+        assert_eq!("ACME", bext.originator);
+        assert_eq!(48000, bext.time_reference);
+    }
+
+    #[test]
+    fn test_parsing_ixml_chunk() {
+        let ixml = b"<BWFXML><SCENE>4</SCENE><TAKE>2</TAKE></BWFXML>\0\0\0";
+
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"iXML", ixml), (b"data", &[0x80; 4])]);
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        let ixml = wave_file.ixml.unwrap();
+        assert_eq!("<BWFXML><SCENE>4</SCENE><TAKE>2</TAKE></BWFXML>", ixml.xml);
+        assert_eq!(Some("4"), ixml.scene());
+    }
+
+    #[test]
+    fn test_parsing_smpl_and_inst_chunks() {
+        let mut smpl = vec![0; 36];
+        smpl[12..16].copy_from_slice(&69u32.to_le_bytes());
+
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"smpl", &smpl), (b"inst", &[60, 0, 0, 0, 127, 0, 127]), (b"data", &[0x80; 4])]);
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        let sampler = wave_file.sampler.unwrap();
+        assert_eq!(69, sampler.midi_unity_note);
+        assert_eq!(Some(60), wave_file.instrument.map(|i| i.unshifted_note));
+        assert!(sampler.loops.is_empty());
+    }
+
+    #[test]
+    fn test_parsing_acid_chunk() {
+        let mut acid = vec![0; 24];
+        acid[12..16].copy_from_slice(&16u32.to_le_bytes());
+        acid[20..24].copy_from_slice(&96f32.to_le_bytes());
+
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"acid", &acid), (b"data", &[0x80; 4])]);
+        let acid = WaveFileParser::parse(bytes).unwrap().acid.unwrap();
+
+        assert_eq!(96.0, acid.tempo_bpm());
+        assert_eq!(16, acid.beats());
+    }
+
+    #[test]
+    fn test_parsing_cart_chunk() {
+        let mut cart = vec![0; 2048];
+        cart[68..74].copy_from_slice(b"Artist");
+
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"cart", &cart), (b"data", &[0x80; 4])]);
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        assert_eq!("Artist", wave_file.cart.unwrap().artist);
+    }
+
+    #[test]
+    fn test_parsing_id3_chunk() {
+        let id3 = b"ID3\x03\x00\x00\x00\x00\x00\x0fTIT2\x00\x00\x00\x05\x00\x00\x00Song";
+
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"id3 ", id3), (b"data", &[0x80; 4])]);
+        let tag = WaveFileParser::parse(bytes).unwrap().id3_tag.unwrap();
+
+        assert_eq!(id3.to_vec(), tag.bytes);
+        assert_eq!(Some("Song".to_string()), tag.title());
+    }
+
+    #[test]
+    fn test_parsing_non_riff_bytes_returns_error() {
+        let result = WaveFileParser::parse(b"FORM\x04\x00\x00\x00WAVE".to_vec());
+
+        let expected = WaveError::UnexpectedChunk { expected: *b"RIFF", found: *b"FORM" };
+        assert_eq!(Err(expected), result.map(|_| ()).map_err(|e| e.kind().clone()));
+    }
+
+    #[test]
+    fn test_parsing_error_says_where_it_happened() {
+        let mut bytes = wave_bytes(1, 1, 16, &[0x00, 0x01, 0x02, 0x03]);
+        bytes.truncate(bytes.len() - 1);
+
+        let error = WaveFileParser::parse(bytes).map(|_| ()).unwrap_err();
+
+        assert_eq!(&WaveError::UnexpectedEof, error.kind());
+        assert_eq!(Some(47), error.offset());
+        assert_eq!(Some(*b"data"), error.chunk_id());
+        assert_eq!("unexpected end of file at byte 47 (in 'data' chunk)", error.to_string());
+    }
+
+    #[test]
+    fn test_parsing_unsupported_format_returns_error() {
+        let result = WaveFileParser::parse(wave_bytes(0x0055, 1, 8, &[0x80, 0x80]));
+
+        assert_eq!(Err(WaveError::UnsupportedFormat(0x0055)), result.map(|_| ()).map_err(|e| e.kind().clone()));
+    }
+
+    #[test]
+    fn test_parsing_truncated_file_returns_error() {
+        // Cut the file off in the middle of the fmt chunk
+        let mut bytes = wave_bytes(1, 1, 16, &[0x00, 0x01, 0x02, 0x03]);
+        bytes.truncate(26);
+
+        assert_eq!(Err(WaveError::UnexpectedEof), WaveFileParser::parse(bytes).map(|_| ()).map_err(|e| e.kind().clone()));
+    }
+
+    #[test]
+    fn test_parsing_truncated_data_chunk_returns_error() {
+        let mut bytes = wave_bytes(1, 1, 16, &[0x00, 0x01, 0x02, 0x03]);
+        bytes.truncate(bytes.len() - 2);
+
+        assert_eq!(Err(WaveError::UnexpectedEof), WaveFileParser::parse(bytes).map(|_| ()).map_err(|e| e.kind().clone()));
+    }
+
+    #[test]
+    fn test_parsing_truncated_data_chunk_with_tolerant_options() {
+        let mut bytes = wave_bytes(1, 1, 16, &[0x00, 0x01, 0x02, 0x03]);
+        bytes.truncate(bytes.len() - 1);
+
+        let options = ParserOptions::new().tolerate_truncated_data(true);
+        let wave_file = WaveFileParser::parse_with_options(bytes, options).unwrap();
+
+        assert_eq!(vec![vec![Sample::BitDepth16(0x0100)]], wave_file.channels);
+        let expected = vec![
+            Diagnostic::TruncatedData { declared: 4, actual: 3 },
+            Diagnostic::RiffSizeMismatch { declared: 48, actual: 47 },
+        ];
+        assert_eq!(expected, wave_file.diagnostics);
+    }
+
+    #[test]
+    fn test_parsing_large_truncated_data_chunk_keeps_whole_frames() {
+        // Big enough that the data is decoded over a few batches, cut off part way through a frame
+        let fixture = WaveFixture::new(2, 24, 8000).num_frames(20_000);
+        let mut bytes = fixture.to_bytes();
+        bytes.truncate(44 + 6 * 15_000 + 4);
+
+        let options = ParserOptions::new().tolerate_truncated_data(true);
+        let wave_file = WaveFileParser::parse_with_options(bytes, options).unwrap();
+
+        assert_eq!(15_000, wave_file.channels[0].len());
+        assert_eq!(15_000, wave_file.channels[1].len());
+        assert_eq!(Sample::BitDepth24(fixture.sample(14_999, 1) as i32), wave_file.channels[1][14_999]);
+    }
+
+    #[test]
+    fn test_progress_callback() {
+        // 150000 bytes of samples take three batches
+        let bytes = WaveFixture::new(1, 16, 8000).num_frames(75_000).to_bytes();
+        let progress = Arc::new(Mutex::new(vec![]));
+
+        let reported = progress.clone();
+        let options = ParserOptions::new().on_progress(move |done, total| reported.lock().unwrap().push((done, total)));
+        WaveFileParser::parse_with_options(bytes, options).unwrap();
+
+        assert_eq!(vec![(65_536, 150_000), (131_072, 150_000), (150_000, 150_000)], *progress.lock().unwrap());
+    }
+
+    #[test]
+    fn test_parsing_streaming_data_size() {
+        let mut bytes = wave_bytes(1, 1, 16, &[0x01, 0x00, 0x02, 0x00]);
+        bytes[40..44].copy_from_slice(&u32::MAX.to_le_bytes());
+        // Half a frame, from a recording that was cut off
+        bytes.push(0x03);
+
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        assert_eq!(vec![Sample::BitDepth16(1), Sample::BitDepth16(2)], wave_file.channels[0]);
+    }
+
+    #[test]
+    fn test_parsing_zero_data_size_with_zero_riff_size() {
+        let mut bytes = wave_bytes(1, 1, 8, &[]);
+        bytes[4..8].copy_from_slice(&[0; 4]);
+        bytes.extend_from_slice(&[0x80, 0x81, 0x82]);
+
+        let wave_file = WaveFileParser::parse(bytes.clone()).unwrap();
+        assert_eq!(3, wave_file.channels[0].len());
+
+        // With a real RIFF size, an empty data chunk is just empty
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"data", &[]), (b"junk", b"abcd")]);
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+        assert!(wave_file.channels[0].is_empty());
+        assert_eq!(1, wave_file.extra_chunks.len());
+    }
+
+    #[test]
+    fn test_repairing_file_with_zero_sizes() {
+        let mut bytes = wave_bytes(1, 1, 16, &[0x00, 0x01, 0x02, 0x03, 0x04]);
+        bytes[4..8].copy_from_slice(&[0; 4]);
+        bytes[40..44].copy_from_slice(&[0; 4]);
+        bytes.pop();
+
+        let wave_file = WaveFileParser::parse_repair(bytes).unwrap();
+
+        assert_eq!(vec![Sample::BitDepth16(0x0100), Sample::BitDepth16(0x0302)], wave_file.channels[0]);
+        let expected = vec![
+            Diagnostic::DataSizeRepaired { declared: 0, repaired: 5 },
+            Diagnostic::PartialFrame { data_size: 5, block_align: 2 },
+            Diagnostic::RiffSizeMismatch { declared: 8, actual: 49 },
+        ];
+        assert_eq!(expected, wave_file.diagnostics);
+    }
+
+    #[test]
+    fn test_repairing_file_with_oversized_data_chunk() {
+        let mut bytes = wave_bytes(1, 1, 8, &[0x80, 0x81]);
+        bytes[40..44].copy_from_slice(&0x7fff_ffffu32.to_le_bytes());
+
+        assert_eq!(Err(WaveError::UnexpectedEof), WaveFileParser::parse(bytes.clone()).map(|_| ()).map_err(|e| e.kind().clone()));
+
+        let wave_file = WaveFileParser::parse_repair(bytes).unwrap();
+
+        assert_eq!(2, wave_file.channels[0].len());
+        assert_eq!(Diagnostic::DataSizeRepaired { declared: 0x7fff_ffff, repaired: 2 }, wave_file.diagnostics[0]);
+    }
+
+    #[test]
+    fn test_parsing_with_strict_options_rejects_bytes_past_the_riff_chunk() {
+        let mut bytes = wave_bytes(1, 1, 8, &[0x80, 0x81]);
+        let options = ParserOptions::new().strict_chunk_sizes(true);
+        assert!(WaveFileParser::parse_with_options(bytes.clone(), options.clone()).is_ok());
+
+        bytes.extend_from_slice(b"junk");
+
+        assert_eq!(Err(WaveError::InvalidChunkSize(38)), WaveFileParser::parse_with_options(bytes, options).map(|_| ()).map_err(|e| e.kind().clone()));
+    }
+
+    #[test]
+    fn test_parsing_with_strict_options_rejects_missing_pad_byte() {
+        let mut bytes = wave_bytes(1, 1, 8, &[0x80, 0x81, 0x82]);
+        bytes.pop();
+
+        let wave_file = WaveFileParser::parse(bytes.clone()).unwrap();
+        assert!(wave_file.diagnostics.contains(&Diagnostic::MissingPadByte { chunk_id: *b"data", offset: 36 }));
+
+        let options = ParserOptions::new().strict_chunk_sizes(true);
+        assert_eq!(Err(WaveError::UnexpectedEof), WaveFileParser::parse_with_options(bytes, options).map(|_| ()).map_err(|e| e.kind().clone()));
+    }
+
+    #[test]
+    fn test_parsing_partial_frame_is_reported() {
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 16)), (b"data", &[0x00, 0x01, 0x02, 0x03])]);
+        assert!(WaveFileParser::parse(bytes).unwrap().validate().is_empty());
+
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 16)), (b"data", &[0x00, 0x01, 0x02])]);
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        assert_eq!(vec![Sample::BitDepth16(0x0100)], wave_file.channels[0]);
+        assert_eq!(vec![Diagnostic::PartialFrame { data_size: 3, block_align: 2 }], wave_file.validate());
+    }
+
+    #[test]
+    fn test_parsing_with_strict_options_rejects_partial_frames() {
+        let bytes = wave_bytes(1, 2, 8, &[0x80, 0x81, 0x82]);
+        let options = ParserOptions::new().strict_chunk_sizes(true);
+
+        assert_eq!(Err(WaveError::InvalidChunkSize(3)), WaveFileParser::parse_with_options(bytes, options).map(|_| ()).map_err(|e| e.kind().clone()));
+    }
+
+    #[test]
+    fn test_parsing_unknown_format_with_options() {
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(0x1234, 1, 4)), (b"fact", &4u32.to_le_bytes()), (b"data", &[0x12, 0x34]), (b"junk", b"abc")]);
+
+        let options = ParserOptions::new().allow_unknown_formats(true);
+        let wave_file = WaveFileParser::parse_with_options(bytes, options).unwrap();
+
+        assert_eq!(0x1234, wave_file.format_tag);
+        assert_eq!(vec![Vec::<Sample>::new()], wave_file.channels);
+        assert_eq!(1, wave_file.extra_chunks.len());
+    }
+
+    #[test]
+    fn test_parsing_too_many_chunks_returns_error() {
+        let bytes = riff_bytes(&[(b"junk", b"a"), (b"junk", b"b"), (b"fmt ", &fmt_bytes(1, 1, 8)), (b"data", &[0x80])]);
+
+        let options = ParserOptions::new().max_chunks(4);
+        assert!(WaveFileParser::parse_with_options(bytes.clone(), options).is_ok());
+
+        let options = ParserOptions::new().max_chunks(3);
+        assert_eq!(Err(WaveError::TooManyChunks(3)), WaveFileParser::parse_with_options(bytes, options).map(|_| ()).map_err(|e| e.kind().clone()));
+    }
+
+    #[test]
+    fn test_parsing_with_limits() {
+        let bytes = wave_bytes(1, 2, 16, &[0; 16]);
+        let parse = |options: ParserOptions| WaveFileParser::parse_with_options(bytes.clone(), options).map(|_| ()).map_err(|e| e.kind().clone());
+
+        assert_eq!(Ok(()), parse(ParserOptions::new().max_channels(2).max_data_size(16)));
+        assert_eq!(Err(WaveError::LimitExceeded { name: "channels", limit: 1 }), parse(ParserOptions::new().max_channels(1)));
+        assert_eq!(Err(WaveError::LimitExceeded { name: "bytes of data", limit: 15 }), parse(ParserOptions::new().max_data_size(15)));
+
+        let limit = 7 * mem::size_of::<Sample>() as u64;
+        assert_eq!(Err(WaveError::LimitExceeded { name: "bytes of sample memory", limit }), parse(ParserOptions::new().max_sample_memory(limit)));
+    }
+
+    #[test]
+    fn test_every_way_of_parsing_has_a_sample_memory_limit() {
+        let bytes = wave_bytes(1, 2, 16, &[0; 16]);
+        let limit = 7 * mem::size_of::<Sample>() as u64;
+        let exceeded = Err(WaveError::LimitExceeded { name: "bytes of sample memory", limit });
+
+        assert_eq!(exceeded, WaveFileParser::parse_repair_with_options(bytes.clone(), ParserOptions::new().max_sample_memory(limit)).map(|_| ()).map_err(|e| e.kind().clone()));
+        assert_eq!(exceeded, WaveFileParser::parse_lossless_with_options(bytes, ParserOptions::new().max_sample_memory(limit)).map(|_| ()).map_err(|e| e.kind().clone()));
+        assert_eq!(Some(DEFAULT_MAX_SAMPLE_MEMORY), ParserOptions::new().max_sample_memory);
+    }
+
+    #[test]
+    fn test_parsing_huge_silence_returns_error() {
+        let mut wavl = b"wavl".to_vec();
+        wavl.extend_from_slice(b"slnt\x04\x00\x00\x00\xff\xff\xff\xff");
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 2, 16)), (b"LIST", &wavl)]);
+
+        let result = WaveFileParser::parse(bytes.clone()).map(|_| ()).map_err(|e| e.kind().clone());
+        assert_eq!(Err(WaveError::LimitExceeded { name: "bytes of sample memory", limit: DEFAULT_MAX_SAMPLE_MEMORY }), result);
+        assert!(WaveFileParser::parse_repair(bytes).is_err());
+
+        // Less than the limit, but more than the fact chunk says there is
+        let mut wavl = b"wavl".to_vec();
+        wavl.extend_from_slice(b"slnt\x04\x00\x00\x00\x00\x00\x00\x01");
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 2, 16)), (b"fact", &[4, 0, 0, 0]), (b"LIST", &wavl)]);
+
+        let result = WaveFileParser::parse(bytes).map(|_| ()).map_err(|e| e.kind().clone());
+        assert_eq!(Err(WaveError::LimitExceeded { name: "frames in the fact chunk", limit: 4 }), result);
+    }
+
+    #[test]
+    fn test_parsing_huge_silence_with_memory_limit() {
+        let mut wavl = b"wavl".to_vec();
+        wavl.extend_from_slice(b"slnt\x04\x00\x00\x00\xff\xff\xff\xff");
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 2, 16)), (b"LIST", &wavl)]);
+
+        let options = ParserOptions::new().max_sample_memory(1 << 20);
+        let result = WaveFileParser::parse_with_options(bytes, options).map(|_| ()).map_err(|e| e.kind().clone());
+
+        assert_eq!(Err(WaveError::LimitExceeded { name: "bytes of sample memory", limit: 1 << 20 }), result);
+    }
+
+    #[test]
+    fn test_parsing_chunk_claiming_to_be_huge_returns_error() {
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"data", &[0x80; 2])]);
+        let mut with_huge_chunk = bytes[..12].to_vec();
+        with_huge_chunk.extend_from_slice(b"junk\xfe\xff\xff\xffabc");
+
+        let result = WaveFileParser::parse(with_huge_chunk).map(|_| ()).map_err(|e| e.kind().clone());
+
+        assert_eq!(Err(WaveError::UnexpectedEof), result);
+    }
+
+    // A levl chunk with `channels` channels of `frames` 8 bit positive peaks, and the peaks
+    // that are in `peaks`
+    fn levl_contents(channels: u32, frames: u32, peaks: &[u8]) -> Vec<u8> {
+        let mut contents = vec![0; 128];
+        contents[4..8].copy_from_slice(&1u32.to_le_bytes());
+        contents[8..12].copy_from_slice(&1u32.to_le_bytes());
+        contents[12..16].copy_from_slice(&256u32.to_le_bytes());
+        contents[16..20].copy_from_slice(&channels.to_le_bytes());
+        contents[20..24].copy_from_slice(&frames.to_le_bytes());
+        contents[28..32].copy_from_slice(&128u32.to_le_bytes());
+        contents.extend_from_slice(peaks);
+
+        contents
+    }
+
+    // An MS ADPCM fmt chunk for mono 8 byte blocks, with its own table of `coefficients`
+    fn ms_adpcm_fmt(coefficients: &[(i16, i16)]) -> Vec<u8> {
+        let mut fmt = fmt_bytes(0x0002, 1, 4);
+        fmt[12..14].copy_from_slice(&8u16.to_le_bytes());
+        fmt.extend_from_slice(&(4 + 4 * coefficients.len() as u16).to_le_bytes());
+        fmt.extend_from_slice(&4u16.to_le_bytes());
+        fmt.extend_from_slice(&(coefficients.len() as u16).to_le_bytes());
+        for &(coefficient1, coefficient2) in coefficients.iter() {
+            fmt.extend_from_slice(&coefficient1.to_le_bytes());
+            fmt.extend_from_slice(&coefficient2.to_le_bytes());
+        }
+
+        fmt
+    }
+
+    // Small files that between them go through every codec, every container and the chunks
+    // that hold counts of things, to be damaged by the test below
+    fn damaged_file_seeds() -> Vec<Vec<u8>> {
+        let mut cue = 1u32.to_le_bytes().to_vec();
+        cue.extend_from_slice(&[1, 0, 0, 0, 0, 0, 0, 0]);
+        cue.extend_from_slice(b"data");
+        cue.extend_from_slice(&[0; 12]);
+        let adtl = b"adtllabl\x07\x00\x00\x00\x01\x00\x00\x00hi\0\0note\x06\x00\x00\x00\x01\x00\x00\x00n\0";
+        let pcm = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 2, 16)), (b"cue ", &cue), (b"LIST", adtl), (b"fact", &[2, 0, 0, 0]), (b"data", &[1; 8])]);
+
+        let ms_adpcm_block = [0, 16, 0, 20, 0, 10, 0, 0x1f];
+        let ms_adpcm = riff_bytes(&[
+            (b"fmt ", &ms_adpcm_fmt(&[(256, 0), (512, -256)])),
+            (b"fact", &5u32.to_le_bytes()),
+            (b"data", &[ms_adpcm_block, ms_adpcm_block].concat()),
+        ]);
+
+        let mut ima_fmt = fmt_bytes(0x0011, 1, 4);
+        ima_fmt[12..14].copy_from_slice(&8u16.to_le_bytes());
+        ima_fmt.extend_from_slice(&2u16.to_le_bytes());
+        ima_fmt.extend_from_slice(&9u16.to_le_bytes());
+        let ima_block = [0x10, 0x00, 0x00, 0x00, 0x12, 0x34, 0x56, 0x78];
+        let ima_adpcm = riff_bytes(&[(b"fmt ", &ima_fmt), (b"fact", &12u32.to_le_bytes()), (b"data", &[ima_block, ima_block].concat())]);
+
+        let mut extensible_fmt = fmt_bytes(0xfffe, 2, 32);
+        extensible_fmt.extend_from_slice(&22u16.to_le_bytes());
+        extensible_fmt.extend_from_slice(&24u16.to_le_bytes());
+        extensible_fmt.extend_from_slice(&0x0000_0003u32.to_le_bytes());
+        extensible_fmt.extend_from_slice(&[0x01, 0x00]);
+        extensible_fmt.extend_from_slice(&KSDATAFORMAT_SUBTYPE_SUFFIX);
+        let extensible = riff_bytes(&[(b"fmt ", &extensible_fmt), (b"data", &[1; 16])]);
+
+        let mut ds64 = vec![];
+        ds64.extend_from_slice(&0u64.to_le_bytes());
+        ds64.extend_from_slice(&4u64.to_le_bytes());
+        ds64.extend_from_slice(&2u64.to_le_bytes());
+        ds64.extend_from_slice(&0u32.to_le_bytes());
+        let mut rf64 = riff_bytes(&[(b"ds64", &ds64), (b"fmt ", &fmt_bytes(1, 1, 16)), (b"data", &[1, 0, 2, 0])]);
+        rf64[0..4].copy_from_slice(b"RF64");
+        rf64[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        let data_size_offset = rf64.len() - 8;
+        rf64[data_size_offset..data_size_offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let wave64 = wave64_bytes(&[(b"fmt ", &fmt_bytes(1, 2, 16)), (b"fact", &2u32.to_le_bytes()), (b"data", &[1, 0, 2, 0, 3, 0, 4, 0])]);
+
+        let mut wavl = b"wavl".to_vec();
+        wavl.extend_from_slice(b"data\x02\x00\x00\x00\x01\x02");
+        wavl.extend_from_slice(b"slnt\x04\x00\x00\x00\x03\x00\x00\x00");
+        let with_levl_and_wavl = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"levl", &levl_contents(1, 2, &[10, 20])), (b"LIST", &wavl)]);
+
+        vec![pcm, ms_adpcm, ima_adpcm, extensible, rf64, wave64, with_levl_and_wavl]
+    }
+
+    // A file that ends anywhere at all, or has any one byte changed, has to come back as
+    // an error (or a WaveFile), never a panic
+    #[test]
+    fn test_parsing_damaged_files_never_panics() {
+        let options = ParserOptions::new().max_sample_memory(1 << 20);
+
+        for bytes in damaged_file_seeds() {
+            assert!(WaveFileParser::parse(bytes.clone()).is_ok());
+
+            for len in 0..bytes.len() {
+                let _ = WaveFileParser::parse_with_options(bytes[..len].to_vec(), options.clone());
+                let _ = WaveFileParser::parse_repair_with_options(bytes[..len].to_vec(), options.clone());
+            }
+
+            for position in 0..bytes.len() {
+                for &value in [0x00, 0x01, 0x7f, 0x80, 0xff].iter() {
+                    let mut damaged = bytes.clone();
+                    damaged[position] = value;
+                    let _ = WaveFileParser::parse_with_options(damaged.clone(), options.clone());
+                    let _ = WaveFileParser::parse_repair_with_options(damaged.clone(), options.clone());
+                    let _ = WaveFileParser::parse_lossless_with_options(damaged, options.clone());
+                }
+            }
+        }
+    }
+
+    // Files that used to take the whole process down, with the default options or the
+    // ones a careful caller would use
+    #[test]
+    fn test_parsing_files_that_used_to_crash() {
+        let careful = ParserOptions::new().max_sample_memory(1 << 20);
+
+        // A slnt chunk claiming 4 billion samples of silence
+        let mut wavl = b"wavl".to_vec();
+        wavl.extend_from_slice(b"slnt\x04\x00\x00\x00\xff\xff\xff\xff");
+        let huge_silence = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 2, 16)), (b"LIST", &wavl)]);
+        assert!(WaveFileParser::parse(huge_silence.clone()).is_err());
+        assert!(WaveFileParser::parse_repair(huge_silence).is_err());
+
+        // A levl chunk with no channels of 4 billion peak frames
+        let levl = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"levl", &levl_contents(0, u32::MAX, &[0; 20])), (b"data", &[0x80; 2])]);
+        assert!(WaveFileParser::parse(levl.clone()).is_err());
+        assert!(WaveFileParser::parse_with_options(levl, careful.clone()).is_err());
+
+        // MS ADPCM coefficients and samples that overflow the prediction, then a delta that
+        // keeps on growing
+        let overflowing_prediction = riff_bytes(&[(b"fmt ", &ms_adpcm_fmt(&[(i16::MIN, i16::MIN)])), (b"data", &[0, 16, 0, 0x00, 0x80, 0x00, 0x80, 0x00])]);
+        assert!(WaveFileParser::parse(overflowing_prediction).is_ok());
+
+        let mut growing_delta = vec![0, 0xff, 0x7f, 0, 0, 0, 0];
+        growing_delta.extend_from_slice(&[0x88; 33]);
+        let mut fmt = ms_adpcm_fmt(&[(256, 0)]);
+        fmt[12..14].copy_from_slice(&40u16.to_le_bytes());
+        let growing_delta = riff_bytes(&[(b"fmt ", &fmt), (b"data", &growing_delta)]);
+        assert!(WaveFileParser::parse_with_options(growing_delta, careful).is_ok());
+    }
+
+    #[test]
+    fn test_parsing_undersized_fmt_chunk_returns_error() {
+        let mut bytes = wave_bytes(1, 1, 16, &[0x00, 0x01]);
+        // Overwrite the fmt chunk size
+        bytes[16..20].copy_from_slice(&8u32.to_le_bytes());
+
+        assert_eq!(Err(WaveError::InvalidChunkSize(8)), WaveFileParser::parse(bytes).map(|_| ()).map_err(|e| e.kind().clone()));
+    }
+
+    #[test]
+    fn test_parsing_from_reader() {
+        // A reader that hands out a single byte at a time, like a slow network stream
+        struct Trickle(Vec<u8>, usize);
+
+        impl Read for Trickle {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if self.1 == self.0.len() || buf.is_empty() {
+                    return Ok(0);
+                }
+                buf[0] = self.0[self.1];
+                self.1 += 1;
+                Ok(1)
+            }
+        }
+
+        let bytes = wave_bytes(1, 2, 16, &[0x01, 0x00, 0xff, 0xff, 0x02, 0x00, 0xfe, 0xff]);
+        let wave_file = WaveFileParser::from_reader(Trickle(bytes, 0)).unwrap();
+
+        assert_eq!(2, wave_file.channels.len());
+        assert_eq!(2, wave_file.channels[0].len());
+        assert_eq!(2, wave_file.channels[1].len());
+    }
+
+    #[test]
+    fn test_parsing_skips_chunks_before_fmt() {
+        let bytes = wave_bytes(1, 1, 8, &[0x80, 0x81]);
+
+        // Slip an unknown chunk with an odd size (plus its pad byte) in front of the fmt chunk
+        let mut with_junk = bytes[..12].to_vec();
+        with_junk.extend_from_slice(b"junk\x03\x00\x00\x00abc\x00");
+        with_junk.extend_from_slice(&bytes[12..]);
+
+        let wave_file = WaveFileParser::parse(with_junk).unwrap();
+
+        assert_eq!(1, wave_file.channels.len());
+        assert_eq!(2, wave_file.channels[0].len());
+    }
+
+    #[test]
+    fn test_parsing_keeps_unknown_chunks() {
+        let bytes = riff_bytes(&[(b"junk", b"abc"), (b"LIST", b"abcdxyz"), (b"fmt ", &fmt_bytes(1, 1, 8)), (b"data", &[0x80; 2])]);
+
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        let expected = vec![
+            RawChunk { id: *b"junk", data: b"abc".to_vec(), offset: 12 },
+            RawChunk { id: *b"LIST", data: b"abcdxyz".to_vec(), offset: 24 },
+        ];
+        assert_eq!(expected, wave_file.extra_chunks);
+    }
+
+    #[test]
+    fn test_parsing_header_only() {
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 2, 16)), (b"data", &[0; 4000]), (b"LIST", b"INFOINAM\x04\x00\x00\x00Hum\0")]);
+
+        let info = WaveFileParser::parse_header(bytes).unwrap();
+
+        assert_eq!(vec![(44, 4000)], info.data_chunks);
+        assert_eq!(1000, info.num_frames());
+        assert_eq!(vec![Vec::<Sample>::new(); 2], info.wave_file.channels);
+        assert_eq!(Some("Hum"), info.wave_file.metadata.title());
+    }
+
+    #[test]
+    fn test_parsing_chunks_after_data() {
+        let mut cue = 1u32.to_le_bytes().to_vec();
+        cue.extend_from_slice(&1u32.to_le_bytes());
+        cue.extend_from_slice(&0u32.to_le_bytes());
+        cue.extend_from_slice(b"data");
+        cue.extend_from_slice(&[0; 8]);
+        cue.extend_from_slice(&1u32.to_le_bytes());
+
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"data", &[0x80; 3]), (b"cue ", &cue), (b"axml", b"<ebuCore/>")]);
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        assert_eq!(3, wave_file.channels[0].len());
+        assert_eq!(1, wave_file.cue_points.len());
+        assert_eq!(Some("<ebuCore/>".to_string()), wave_file.axml);
+    }
+
+    #[test]
+    fn test_parsing_multiple_data_chunks() {
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 2, 8)), (b"data", &[0x01, 0x02, 0x03, 0x04]), (b"junk", b"abc"), (b"data", &[0x05, 0x06])]);
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        let left: Vec<Sample> = [0x01, 0x03, 0x05].iter().map(|&s| Sample::BitDepth8(s)).collect();
+        let right: Vec<Sample> = [0x02, 0x04, 0x06].iter().map(|&s| Sample::BitDepth8(s)).collect();
+        assert_eq!(vec![left, right], wave_file.channels);
+    }
+
+    #[test]
+    fn test_parsing_wave_list_with_silence() {
+        let mut wavl = b"wavl".to_vec();
+        wavl.extend_from_slice(b"data\x02\x00\x00\x00\x01\x02");
+        wavl.extend_from_slice(b"slnt\x04\x00\x00\x00\x03\x00\x00\x00");
+        wavl.extend_from_slice(b"data\x01\x00\x00\x00\x03\x00");
+
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"LIST", &wavl)]);
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        let expected: Vec<Sample> = [0x01, 0x02, 0x80, 0x80, 0x80, 0x03].iter().map(|&s| Sample::BitDepth8(s)).collect();
+        assert_eq!(vec![expected], wave_file.channels);
+    }
+
+    #[test]
+    fn test_parsing_silence_in_16_bit_file() {
+        let mut wavl = b"wavl".to_vec();
+        wavl.extend_from_slice(b"slnt\x04\x00\x00\x00\x02\x00\x00\x00");
+
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 2, 16)), (b"LIST", &wavl)]);
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        assert_eq!(vec![vec![Sample::BitDepth16(0); 2]; 2], wave_file.channels);
+    }
+
+    #[test]
+    fn test_parsing_ignores_bytes_past_the_riff_chunk() {
+        let mut bytes = wave_bytes(1, 1, 8, &[0x80, 0x81]);
+        bytes.extend_from_slice(b"this is not a chunk");
+
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        assert_eq!(2, wave_file.channels[0].len());
+        assert_eq!(vec![Diagnostic::RiffSizeMismatch { declared: 46, actual: 65 }], wave_file.diagnostics);
+    }
+
+    #[test]
+    fn test_parsing_data_before_fmt_returns_error() {
+        let bytes = riff_bytes(&[(b"data", &[0x80; 2]), (b"fmt ", &fmt_bytes(1, 1, 8))]);
+
+        assert_eq!(Err(WaveError::MissingChunk(*b"fmt ")), WaveFileParser::parse(bytes).map(|_| ()).map_err(|e| e.kind().clone()));
+    }
+}
@@ -0,0 +1,107 @@
+use crate::convert::FromSample;
+use crate::wave_file::{Sample, WaveFile};
+
+// Where playback is up to in a file. Hand it to `WaveFile::fill_output_buffer` from an audio
+// callback (cpal's output stream callback, for one) and it moves along as the buffers are filled.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PlaybackCursor {
+    // The next frame to play
+    pub frame: usize,
+    // Start again from the beginning when the end of the file is reached
+    pub looping: bool,
+}
+
+impl PlaybackCursor {
+    pub fn new() -> PlaybackCursor {
+        Default::default()
+    }
+
+    pub fn looping(mut self, looping: bool) -> PlaybackCursor {
+        self.looping = looping;
+        self
+    }
+}
+
+impl WaveFile {
+    // Fill `buffer` with the frames at `cursor`, interleaved and converted to the device's sample
+    // type, and move the cursor past them. Whatever's left once the file runs out is silence,
+    // so the buffer can go straight to the device. The buffer should hold a whole number of
+    // frames for this file's channel count; anything after the last whole frame is silence too.
+    // Returns how many frames came from the file.
+    pub fn fill_output_buffer<T>(&self, buffer: &mut [T], cursor: &mut PlaybackCursor) -> usize
+    where
+        T: FromSample<Sample> + FromSample<f32>,
+    {
+        let num_channels = self.channels.len();
+        let num_frames = self.channels.iter().map(|c| c.len()).min().unwrap_or(0);
+        let mut written = 0;
+
+        if num_channels > 0 && num_frames > 0 {
+            for frame in buffer.chunks_exact_mut(num_channels) {
+                if cursor.frame >= num_frames {
+                    if !cursor.looping {
+                        break;
+                    }
+                    cursor.frame = 0;
+                }
+
+                for (out, channel) in frame.iter_mut().zip(self.channels.iter()) {
+                    *out = T::from_sample(channel[cursor.frame].clone());
+                }
+                cursor.frame += 1;
+                written += 1;
+            }
+        }
+
+        for out in buffer[written * num_channels..].iter_mut() {
+            *out = T::from_sample(0.0f32);
+        }
+
+        written
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::PlaybackCursor;
+    use crate::wave_file::{Sample, WaveFile};
+
+    fn wave_file() -> WaveFile {
+        WaveFile {
+            channels: vec![
+                vec![Sample::BitDepth16(1), Sample::BitDepth16(2), Sample::BitDepth16(3)],
+                vec![Sample::BitDepth16(-1), Sample::BitDepth16(-2), Sample::BitDepth16(-3)],
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_fill_output_buffer_interleaves_and_pads_with_silence() {
+        let wave_file = wave_file();
+        let mut cursor = PlaybackCursor::new();
+        let mut buffer = [7i16; 4];
+
+        assert_eq!(2, wave_file.fill_output_buffer(&mut buffer, &mut cursor));
+        assert_eq!([1, -1, 2, -2], buffer);
+
+        assert_eq!(1, wave_file.fill_output_buffer(&mut buffer, &mut cursor));
+        assert_eq!([3, -3, 0, 0], buffer);
+        assert_eq!(3, cursor.frame);
+
+        let mut buffer = [1.0f32; 2];
+        assert_eq!(0, wave_file.fill_output_buffer(&mut buffer, &mut cursor));
+        assert_eq!([0.0, 0.0], buffer);
+    }
+
+    #[test]
+    fn test_fill_output_buffer_loops() {
+        let wave_file = wave_file();
+        let mut cursor = PlaybackCursor::new().looping(true);
+        let mut buffer = [0i16; 8];
+
+        assert_eq!(4, wave_file.fill_output_buffer(&mut buffer, &mut cursor));
+        assert_eq!([1, -1, 2, -2, 3, -3, 1, -1], buffer);
+        assert_eq!(1, cursor.frame);
+    }
+}
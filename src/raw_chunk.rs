@@ -0,0 +1,24 @@
+use alloc::vec::Vec;
+
+// A chunk the parser doesn't understand, kept byte for byte so nothing in the file is lost
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawChunk {
+    pub id: [u8; 4],
+    // The contents, without the id, size or pad byte.
+    // For a LIST chunk this starts with the list type.
+    pub data: Vec<u8>,
+    // Where the chunk starts (its id) in the file
+    pub offset: u64,
+}
+
+// Appends a chunk header, the contents, and a pad byte if the contents are an odd size.
+// The writer, the metadata chunks and the test fixtures all lay chunks out with this.
+#[cfg(any(feature = "std", feature = "testing", test))]
+pub(crate) fn write_chunk(bytes: &mut Vec<u8>, id: &[u8; 4], contents: &[u8]) {
+    bytes.extend_from_slice(id);
+    bytes.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(contents);
+    if contents.len() % 2 == 1 {
+        bytes.push(0);
+    }
+}
@@ -0,0 +1,257 @@
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::time::Duration;
+
+use crate::byte_stream::{ForwardOnly, Seekable};
+use crate::codec::pcm::SampleEncoding;
+use crate::error::WaveError;
+use crate::options::ParserOptions;
+use crate::parser::ChunkParser;
+use crate::wave_file::Sample;
+use crate::wave_info::WaveInfo;
+
+// A lazy alternative to WaveFileParser.
+// Opening a WaveReader only parses the chunk headers and metadata. The data chunk is skipped
+// over (with a seek, so it is never even read) and samples are only decoded when asked for.
+pub struct WaveReader<R> {
+    reader: R,
+    // Everything but the samples, and where to find them
+    info: WaveInfo,
+    // Whether the samples are big-endian, which they are in RIFX files
+    big_endian: bool,
+}
+
+impl<R: Read + Seek> WaveReader<R> {
+    pub fn new(reader: R) -> Result<WaveReader<R>, WaveError> {
+        let source = Seekable::new(reader).map_err(|e| WaveError::Io(e.kind()))?;
+
+        let mut parser = ChunkParser::new(source);
+        parser.decode_samples = false;
+        // Whatever is there of a cut off data chunk can still be read
+        parser.options = ParserOptions::new().tolerate_truncated_data(true);
+
+        let wave_file = parser.read_wave_file()?;
+        let info = WaveInfo {
+            wave_file,
+            data_chunks: parser.data_chunks,
+        };
+        let big_endian = parser.byte_stream.big_endian;
+
+        Ok(WaveReader {
+            reader: parser.byte_stream.into_inner().inner,
+            info,
+            big_endian,
+        })
+    }
+
+    // Everything about the file but the samples
+    pub fn info(&self) -> &WaveInfo {
+        &self.info
+    }
+
+    pub fn num_channels(&self) -> u16 {
+        self.info.wave_file.channels.len() as u16
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.info.wave_file.sample_rate
+    }
+
+    pub fn byte_rate(&self) -> u32 {
+        self.info.wave_file.byte_rate
+    }
+
+    pub fn block_align(&self) -> u16 {
+        self.info.wave_file.block_align
+    }
+
+    pub fn bits_per_sample(&self) -> u16 {
+        self.info.wave_file.bits_per_sample
+    }
+
+    // The number of frames (one sample per channel) in the file
+    pub fn num_frames(&self) -> u64 {
+        match self.frame_size() {
+            Some(frame_size) => self.info.data_chunks.iter().map(|&(_, size)| size / frame_size).sum(),
+            None => self.info.num_frames(),
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.info.wave_file.frames_to_duration(self.num_frames())
+    }
+
+    // How many bytes a frame takes up, worked out from the channels and the sample format
+    // rather than taken from the block align in the header, the same as `WaveFileParser` does.
+    // None for formats that can't be read a frame at a time.
+    fn frame_size(&self) -> Option<u64> {
+        let wave_file = &self.info.wave_file;
+        let encoding = SampleEncoding::new(wave_file.wave_format, wave_file.bits_per_sample).ok()?;
+
+        match wave_file.channels.len() * encoding.size() {
+            0 => None,
+            frame_size => Some(frame_size as u64),
+        }
+    }
+
+    // Decode `count` frames starting at `start_frame`, one vector of samples per channel.
+    // Only those frames are read: the reader seeks straight to the first one. To start at a time
+    // instead, `info().wave_file.duration_to_frames` turns it into a frame.
+    // Asking for frames past the end gets as many as there are.
+    pub fn read_frames(&mut self, start_frame: u64, count: u64) -> Result<Vec<Vec<Sample>>, WaveError> {
+        self.read_samples(start_frame..start_frame.saturating_add(count))
+    }
+
+    // Decode the frames in `range`, one vector of samples per channel.
+    // The range is clamped to the frames that are actually in the file.
+    pub fn read_samples(&mut self, range: Range<u64>) -> Result<Vec<Vec<Sample>>, WaveError> {
+        let mut channels = vec![vec![]; self.info.wave_file.channels.len()];
+        let encoding = SampleEncoding::new(self.info.wave_file.wave_format, self.info.wave_file.bits_per_sample)?;
+        if channels.is_empty() {
+            return Err(WaveError::UnsupportedChannelCount(0));
+        }
+        // Counting, seeking and decoding all go by the same frame size
+        let frame_size = (channels.len() * encoding.size()) as u64;
+
+        // Frames are numbered across all the data chunks as if they were one
+        let mut first_frame_in_chunk = 0;
+
+        for &(offset, size) in self.info.data_chunks.iter() {
+            let frames_in_chunk = size / frame_size;
+            let start = range.start.max(first_frame_in_chunk);
+            let end = range.end.min(first_frame_in_chunk + frames_in_chunk);
+
+            if start < end {
+                let position = offset + (start - first_frame_in_chunk) * frame_size;
+                self.reader.seek(SeekFrom::Start(position)).map_err(|e| WaveError::Io(e.kind()))?;
+
+                let mut parser = ChunkParser::new(ForwardOnly(&mut self.reader));
+                parser.byte_stream.big_endian = self.big_endian;
+                parser.decode_frames(&mut channels, encoding, 0, (end - start) * frame_size)?;
+            }
+
+            first_frame_in_chunk += frames_in_chunk;
+        }
+
+        Ok(channels)
+    }
+
+    // Hand back the reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use std::io::{self, Cursor, Read, Seek, SeekFrom};
+    use std::time::Duration;
+    use super::WaveReader;
+    use crate::parser::WaveFileParser;
+    use crate::test_utils::{fmt_bytes, riff_bytes, wave_bytes};
+    use crate::testing::WaveFixture;
+    use crate::wave_file::Sample;
+
+    #[test]
+    fn test_reading_header_only() {
+        // 8000 frames of 16 bit stereo is one second of audio
+        let reader = WaveReader::new(Cursor::new(wave_bytes(1, 2, 16, &[0; 32000]))).unwrap();
+
+        assert_eq!(2, reader.num_channels());
+        assert_eq!(8000, reader.sample_rate());
+        assert_eq!(16, reader.bits_per_sample());
+        assert_eq!(8000, reader.num_frames());
+        assert_eq!(Duration::from_secs(1), reader.duration());
+    }
+
+    #[test]
+    fn test_reading_a_range_of_samples() {
+        let data = [0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07];
+        let mut reader = WaveReader::new(Cursor::new(wave_bytes(1, 2, 8, &data))).unwrap();
+
+        let channels = reader.read_samples(1..3).unwrap();
+
+        assert_eq!(vec![Sample::BitDepth8(0x02), Sample::BitDepth8(0x04)], channels[0]);
+        assert_eq!(vec![Sample::BitDepth8(0x03), Sample::BitDepth8(0x05)], channels[1]);
+    }
+
+    #[test]
+    fn test_reading_frames_only_reads_those_frames() {
+        // Counts how many bytes are read through it
+        struct Counting<R> {
+            inner: R,
+            read: usize,
+        }
+
+        impl<R: Read> Read for Counting<R> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let read = self.inner.read(buf)?;
+                self.read += read;
+                Ok(read)
+            }
+        }
+
+        impl<R: Seek> Seek for Counting<R> {
+            fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+                self.inner.seek(pos)
+            }
+        }
+
+        let fixture = WaveFixture::new(2, 16, 8000).num_frames(100_000);
+        let mut reader = WaveReader::new(Counting { inner: Cursor::new(fixture.to_bytes()), read: 0 }).unwrap();
+        let header_read = reader.reader.read;
+
+        let channels = reader.read_frames(90_000, 3).unwrap();
+
+        assert_eq!(Sample::BitDepth16(fixture.sample(90_002, 1) as i16), channels[1][2]);
+        assert_eq!(3, channels[0].len());
+        assert_eq!(12, reader.reader.read - header_read);
+    }
+
+    #[test]
+    fn test_reading_across_data_chunks() {
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"data", &[0x01, 0x02, 0x03]), (b"data", &[0x04, 0x05])]);
+        let mut reader = WaveReader::new(Cursor::new(bytes)).unwrap();
+
+        let channels = reader.read_samples(1..5).unwrap();
+
+        assert_eq!(5, reader.num_frames());
+        assert_eq!((2..=5).map(Sample::BitDepth8).collect::<Vec<Sample>>(), channels[0]);
+    }
+
+    #[test]
+    fn test_reading_truncated_file() {
+        let mut bytes = wave_bytes(1, 1, 16, &[0x01, 0x00, 0x02, 0x00, 0x03, 0x00]);
+        bytes.truncate(bytes.len() - 3);
+        let mut reader = WaveReader::new(Cursor::new(bytes)).unwrap();
+
+        assert_eq!(1, reader.num_frames());
+        assert_eq!(vec![Sample::BitDepth16(1)], reader.read_samples(0..3).unwrap()[0]);
+    }
+
+    #[test]
+    fn test_reading_with_nonstandard_block_align() {
+        let data = [0x01, 0x00, 0x02, 0x00, 0x03, 0x00, 0x04, 0x00];
+
+        for &block_align in [0u16, 1, 4].iter() {
+            let mut fmt = fmt_bytes(1, 1, 16);
+            fmt[12..14].copy_from_slice(&block_align.to_le_bytes());
+            let bytes = riff_bytes(&[(b"fmt ", &fmt), (b"data", &data)]);
+            let parsed = WaveFileParser::parse(bytes.clone()).unwrap();
+            let mut reader = WaveReader::new(Cursor::new(bytes)).unwrap();
+
+            assert_eq!(4, reader.num_frames());
+            assert_eq!(parsed.channels, reader.read_frames(0, 4).unwrap());
+            assert_eq!(vec![Sample::BitDepth16(3), Sample::BitDepth16(4)], reader.read_frames(2, 2).unwrap()[0]);
+        }
+    }
+
+    #[test]
+    fn test_reading_past_the_last_frame_is_clamped() {
+        let mut reader = WaveReader::new(Cursor::new(wave_bytes(1, 1, 16, &[0x01, 0x00, 0x02, 0x00]))).unwrap();
+
+        let channels = reader.read_samples(1..10).unwrap();
+
+        assert_eq!(vec![Sample::BitDepth16(2)], channels[0]);
+    }
+}
@@ -0,0 +1,141 @@
+// A low level look at the chunks in a RIFF file, for anything the parser doesn't model.
+// `ChunkIterator` walks the top level chunks and says where each one is, without reading
+// their contents. Put the reader back with `into_inner` and seek to `offset` to read one.
+
+use std::io::Read;
+
+use crate::byte_stream::{ByteStream, ForwardOnly};
+use crate::error::WaveError;
+use crate::parser::fourcc;
+
+// Where a chunk is and how big it is
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChunkHeader {
+    pub id: [u8; 4],
+    // The size of the contents, not counting the id, the size field or the pad byte
+    pub size: u32,
+    // Where the contents start, counting from where the reader was when the iterator was made
+    pub offset: u64,
+}
+
+pub struct ChunkIterator<R> {
+    byte_stream: ByteStream<ForwardOnly<R>>,
+    form_type: [u8; 4],
+    // Where the RIFF chunk ends. None when the size is unknown, like in RF64 files.
+    end: Option<u64>,
+    done: bool,
+}
+
+impl<R: Read> ChunkIterator<R> {
+    // Reads the RIFF header. RIFX, RF64 and BW64 files work too.
+    pub fn new(reader: R) -> Result<ChunkIterator<R>, WaveError> {
+        let mut byte_stream = ByteStream::new(ForwardOnly(reader));
+
+        let form = byte_stream.read(4)?;
+        match &form[..] {
+            b"RIFF" | b"RF64" | b"BW64" => {}
+            b"RIFX" => byte_stream.big_endian = true,
+            _ => return Err(WaveError::UnexpectedChunk {
+                expected: *b"RIFF",
+                found: fourcc(&form),
+            }),
+        }
+
+        let riff_size = byte_stream.read_u32()?;
+        let form_type = byte_stream.read_array()?;
+
+        Ok(ChunkIterator {
+            byte_stream,
+            form_type,
+            end: if riff_size == u32::MAX { None } else { Some(8 + riff_size as u64) },
+            done: false,
+        })
+    }
+
+    // What kind of RIFF file it is. "WAVE" for a '.wav' file.
+    pub fn form_type(&self) -> [u8; 4] {
+        self.form_type
+    }
+
+    pub fn into_inner(self) -> R {
+        self.byte_stream.into_inner().0
+    }
+
+    fn read_chunk_header(&mut self) -> Result<Option<ChunkHeader>, WaveError> {
+        if self.end.is_some_and(|end| self.byte_stream.offset >= end) {
+            return Ok(None);
+        }
+
+        let id_bytes = match self.byte_stream.read_or_eof(4)? {
+            Some(id_bytes) => id_bytes,
+            None => return Ok(None),
+        };
+        let mut id = [0; 4];
+        id.copy_from_slice(&id_bytes);
+
+        let size = self.byte_stream.read_u32()?;
+        let offset = self.byte_stream.offset;
+
+        // Move on to the next chunk. The last pad byte is allowed to be missing.
+        self.byte_stream.skip(size as u64)?;
+        if size % 2 == 1 {
+            self.byte_stream.read_or_eof(1)?;
+        }
+
+        Ok(Some(ChunkHeader { id, size, offset }))
+    }
+}
+
+impl<R: Read> Iterator for ChunkIterator<R> {
+    type Item = Result<ChunkHeader, WaveError>;
+
+    // Stops after the first error, since there is no telling where the next chunk is
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let result = self.read_chunk_header().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::{ChunkHeader, ChunkIterator};
+    use crate::error::WaveError;
+    use crate::test_utils::{fmt_bytes, riff_bytes};
+
+    #[test]
+    fn test_iterating_over_chunks() {
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"junk", b"abc"), (b"data", &[0x80; 2])]);
+
+        let chunks = ChunkIterator::new(&bytes[..]).unwrap();
+        assert_eq!(*b"WAVE", chunks.form_type());
+
+        let headers: Vec<ChunkHeader> = chunks.map(|c| c.unwrap()).collect();
+        assert_eq!(
+            vec![
+                ChunkHeader { id: *b"fmt ", size: 16, offset: 20 },
+                ChunkHeader { id: *b"junk", size: 3, offset: 44 },
+                ChunkHeader { id: *b"data", size: 2, offset: 56 },
+            ],
+            headers
+        );
+    }
+
+    #[test]
+    fn test_iterating_stops_after_truncated_chunk() {
+        let mut bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"data", &[0x80; 4])]);
+        bytes.truncate(bytes.len() - 2);
+
+        let results: Vec<_> = ChunkIterator::new(&bytes[..]).unwrap().collect();
+
+        assert_eq!(2, results.len());
+        assert_eq!(Err(WaveError::UnexpectedEof), results[1]);
+    }
+}
@@ -0,0 +1,167 @@
+// Chunks for sampler instruments: `smpl` says how to pitch and loop the sample, and `inst`
+// says which notes and velocities it should be played for.
+
+use alloc::vec::Vec;
+
+use crate::error::WaveError;
+
+const BYTES_SMPL_FIXED: usize = 36;
+const BYTES_SAMPLE_LOOP: usize = 24;
+const BYTES_INST: usize = 7;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SamplerChunk {
+    // The MIDI manufacturer and product the chunk was written for. 0 for any.
+    pub manufacturer: u32,
+    pub product: u32,
+    // The length of one sample, in nanoseconds
+    pub sample_period: u32,
+    // The MIDI note that plays the sample at its original pitch
+    pub midi_unity_note: u32,
+    // How far above the unity note the sample really is, as a fraction of a semitone (out of 2^32)
+    pub midi_pitch_fraction: u32,
+    pub smpte_format: u32,
+    pub smpte_offset: u32,
+    pub loops: Vec<SampleLoop>,
+    // Whatever manufacturer specific data follows the loops
+    pub sampler_data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SampleLoop {
+    pub cue_point_id: u32,
+    // 0 loops forward, 1 ping pongs and 2 loops backward
+    pub loop_type: u32,
+    // The first and last sample of the loop. Both are played.
+    pub start: u32,
+    pub end: u32,
+    // How far past `end` the loop really ends, as a fraction of a sample (out of 2^32)
+    pub fraction: u32,
+    // 0 loops forever
+    pub play_count: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Instrument {
+    // The MIDI note the sample was recorded at
+    pub unshifted_note: u8,
+    // Pitch adjustment in cents, -50 to 50
+    pub fine_tune: i8,
+    // Volume adjustment in dB
+    pub gain: i8,
+    // The range of MIDI notes and velocities the sample is meant for
+    pub low_note: u8,
+    pub high_note: u8,
+    pub low_velocity: u8,
+    pub high_velocity: u8,
+}
+
+pub(crate) fn read_inst(contents: &[u8]) -> Result<Instrument, WaveError> {
+    if contents.len() < BYTES_INST {
+        return Err(WaveError::InvalidChunkSize(contents.len() as u32));
+    }
+
+    Ok(Instrument {
+        unshifted_note: contents[0],
+        fine_tune: contents[1] as i8,
+        gain: contents[2] as i8,
+        low_note: contents[3],
+        high_note: contents[4],
+        low_velocity: contents[5],
+        high_velocity: contents[6],
+    })
+}
+
+pub(crate) fn read_smpl(contents: &[u8]) -> Result<SamplerChunk, WaveError> {
+    if contents.len() < BYTES_SMPL_FIXED {
+        return Err(WaveError::InvalidChunkSize(contents.len() as u32));
+    }
+
+    let u32_at = |i: usize| u32::from_le_bytes([contents[i], contents[i + 1], contents[i + 2], contents[i + 3]]);
+
+    let num_loops = u32_at(28) as usize;
+    let sampler_data_size = u32_at(32) as usize;
+    let loops_end = num_loops
+        .checked_mul(BYTES_SAMPLE_LOOP)
+        .map(|size| BYTES_SMPL_FIXED + size)
+        .filter(|&end| end <= contents.len())
+        .ok_or(WaveError::InvalidChunkSize(contents.len() as u32))?;
+
+    let loops = contents[BYTES_SMPL_FIXED..loops_end]
+        .chunks_exact(BYTES_SAMPLE_LOOP)
+        .map(|l| SampleLoop {
+            cue_point_id: u32::from_le_bytes([l[0], l[1], l[2], l[3]]),
+            loop_type: u32::from_le_bytes([l[4], l[5], l[6], l[7]]),
+            start: u32::from_le_bytes([l[8], l[9], l[10], l[11]]),
+            end: u32::from_le_bytes([l[12], l[13], l[14], l[15]]),
+            fraction: u32::from_le_bytes([l[16], l[17], l[18], l[19]]),
+            play_count: u32::from_le_bytes([l[20], l[21], l[22], l[23]]),
+        })
+        .collect();
+
+    // Some writers get the sampler data size wrong, so don't go past the end of the chunk
+    let sampler_data_end = (loops_end + sampler_data_size).min(contents.len());
+
+    Ok(SamplerChunk {
+        manufacturer: u32_at(0),
+        product: u32_at(4),
+        sample_period: u32_at(8),
+        midi_unity_note: u32_at(12),
+        midi_pitch_fraction: u32_at(16),
+        smpte_format: u32_at(20),
+        smpte_offset: u32_at(24),
+        loops,
+        sampler_data: contents[loops_end..sampler_data_end].to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::{read_inst, read_smpl};
+    use crate::error::WaveError;
+
+    fn smpl_bytes(unity_note: u32, loops: &[(u32, u32)]) -> Vec<u8> {
+        let mut contents = vec![0; 36];
+        contents[8..12].copy_from_slice(&22675u32.to_le_bytes());
+        contents[12..16].copy_from_slice(&unity_note.to_le_bytes());
+        contents[28..32].copy_from_slice(&(loops.len() as u32).to_le_bytes());
+        for (i, (start, end)) in loops.iter().enumerate() {
+            for value in [i as u32, 0, *start, *end, 0, 0].iter() {
+                contents.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+
+        contents
+    }
+
+    #[test]
+    fn test_reading_smpl_with_loops() {
+        let smpl = read_smpl(&smpl_bytes(60, &[(100, 200), (300, 400)])).unwrap();
+
+        assert_eq!(60, smpl.midi_unity_note);
+        assert_eq!(22675, smpl.sample_period);
+        assert_eq!(2, smpl.loops.len());
+        assert_eq!(1, smpl.loops[1].cue_point_id);
+        assert_eq!(300, smpl.loops[1].start);
+        assert_eq!(400, smpl.loops[1].end);
+    }
+
+    #[test]
+    fn test_reading_inst() {
+        let inst = read_inst(&[60, 0xfb, 0xfd, 0, 127, 1, 127]).unwrap();
+
+        assert_eq!(60, inst.unshifted_note);
+        assert_eq!(-5, inst.fine_tune);
+        assert_eq!(-3, inst.gain);
+        assert_eq!(127, inst.high_note);
+        assert_eq!(1, inst.low_velocity);
+    }
+
+    #[test]
+    fn test_reading_smpl_with_too_many_loops_returns_error() {
+        let mut contents = smpl_bytes(60, &[(100, 200)]);
+        contents[28..32].copy_from_slice(&3u32.to_le_bytes());
+
+        assert_eq!(Err(WaveError::InvalidChunkSize(60)), read_smpl(&contents));
+    }
+}
@@ -0,0 +1,63 @@
+// The inner loops for turning big runs of raw samples into numbers.
+// With the `simd` feature on an x86_64 machine they use SSE2 (which every x86_64 has), otherwise
+// they are plain loops. Either way the results are exactly the same.
+
+// Little-endian 16 bit samples, scaled to between -1.0 and 1.0 like `Sample::to_f32`.
+// `out` has to have room for one f32 per two bytes.
+pub(crate) fn i16_to_f32(bytes: &[u8], out: &mut [f32]) {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    let (bytes, out) = sse2::i16_to_f32(bytes, out);
+
+    for (sample, out) in bytes.chunks_exact(2).zip(out.iter_mut()) {
+        *out = i16::from_le_bytes([sample[0], sample[1]]) as f32 / 32_768.0;
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod sse2 {
+    use core::arch::x86_64::*;
+
+    // Does eight samples at a time, and hands back whatever is left over at the end
+    pub(super) fn i16_to_f32<'a, 'b>(bytes: &'a [u8], out: &'b mut [f32]) -> (&'a [u8], &'b mut [f32]) {
+        let count = (bytes.len() / 16).min(out.len() / 8);
+
+        // Multiplying by a power of two is exact, so this is the same as dividing by 32768
+        // SAFETY: SSE2 is always there on x86_64, and every load and store stays inside `bytes`
+        // and `out` (`count` is checked against both)
+        unsafe {
+            let scale = _mm_set1_ps(1.0 / 32_768.0);
+
+            for i in 0..count {
+                let samples = _mm_loadu_si128(bytes.as_ptr().add(i * 16) as *const __m128i);
+
+                // Put each sample in the top half of a 32 bit lane, then shift it down to sign extend it
+                let low = _mm_srai_epi32(_mm_unpacklo_epi16(samples, samples), 16);
+                let high = _mm_srai_epi32(_mm_unpackhi_epi16(samples, samples), 16);
+
+                _mm_storeu_ps(out.as_mut_ptr().add(i * 8), _mm_mul_ps(_mm_cvtepi32_ps(low), scale));
+                _mm_storeu_ps(out.as_mut_ptr().add(i * 8 + 4), _mm_mul_ps(_mm_cvtepi32_ps(high), scale));
+            }
+        }
+
+        (&bytes[count * 16..], &mut out[count * 8..])
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::i16_to_f32;
+    use crate::wave_file::Sample;
+
+    #[test]
+    fn test_i16_to_f32_matches_sample_to_f32() {
+        // Enough for a couple of rounds through the SIMD loop, and some left over
+        let samples: Vec<i16> = (0..21).map(|i| (i * 3121 - 32_768) as i16).chain(vec![i16::MIN, -1, 0, 1, i16::MAX]).collect();
+        let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes().to_vec()).collect();
+
+        let mut out = vec![9.0; samples.len()];
+        i16_to_f32(&bytes, &mut out);
+
+        let expected: Vec<f32> = samples.iter().map(|&s| Sample::BitDepth16(s).to_f32()).collect();
+        assert_eq!(expected, out);
+    }
+}
@@ -0,0 +1,400 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+use core::time::Duration;
+
+use crate::associated_data::Region;
+use crate::bext::BroadcastExtension;
+use crate::cue::{CuePoint, PlaylistSegment};
+use crate::custom_chunk::CustomChunks;
+use crate::sampler::SamplerChunk;
+use crate::wave_file::WaveFile;
+
+impl WaveFile {
+    // A new file with just the audio from `range` in it, say to cut a clip out of a recording.
+    // The ends are rounded down to whole frames. See `slice_frames`.
+    pub fn slice(&self, range: Range<Duration>) -> WaveFile {
+        self.slice_frames(self.duration_to_frames(range.start)..self.duration_to_frames(range.end))
+    }
+
+    // A new file with just the frames in `range`, clamped to the frames there are.
+    // The format and the metadata come along. The cue points (and the labels, notes and regions
+    // that go with them), sampler loops and playlist are kept when they start inside the range
+    // and moved to match, the rest are dropped. So is the bext time reference, so the slice
+    // still sits where its audio was on the timeline. What can't be moved goes: the peak envelope,
+    // the diagnostics, which are about the original file, and the custom chunks, which can't
+    // be copied.
+    pub fn slice_frames(&self, range: Range<u64>) -> WaveFile {
+        let start = range.start.min(self.num_frames());
+        let end = range.end.clamp(start, self.num_frames());
+        let length = end - start;
+
+        // Cue positions are 32 bit, so anything past that can't be in the range anyway
+        let inside = |position: u32| (start..end).contains(&(position as u64));
+        let moved = |position: u32| (position as u64 - start) as u32;
+        // How much of a stretch starting at `position` is left before the end of the range
+        let clipped = |position: u32, length: u32| length.min((end - position as u64) as u32);
+
+        let cue_points: Vec<CuePoint> = self
+            .cue_points
+            .iter()
+            .filter(|c| inside(c.sample_offset))
+            .map(|c| CuePoint {
+                position: (c.position as u64).saturating_sub(start) as u32,
+                sample_offset: moved(c.sample_offset),
+                ..c.clone()
+            })
+            .collect();
+        let kept = |cue_id: u32| self.cue_points.iter().any(|c| c.id == cue_id && inside(c.sample_offset));
+        let cue_offset = |cue_id: u32| self.cue_points.iter().find(|c| c.id == cue_id).map_or(0, |c| c.sample_offset);
+
+        WaveFile {
+            channels: self.channels.iter().map(|c| c[(start as usize).min(c.len())..(end as usize).min(c.len())].to_vec()).collect(),
+            wave_format: self.wave_format,
+            format_tag: self.format_tag,
+            sample_rate: self.sample_rate,
+            byte_rate: self.byte_rate,
+            block_align: self.block_align,
+            bits_per_sample: self.bits_per_sample,
+            valid_bits_per_sample: self.valid_bits_per_sample,
+            channel_mask: self.channel_mask,
+            fact_sample_count: self.fact_sample_count.map(|_| length as u32),
+            cue_points,
+            playlist: self
+                .playlist
+                .iter()
+                .filter(|p| kept(p.cue_id))
+                .map(|p| PlaylistSegment {
+                    length_samples: clipped(cue_offset(p.cue_id), p.length_samples),
+                    ..p.clone()
+                })
+                .collect(),
+            labels: self.labels.iter().filter(|l| kept(l.cue_id)).cloned().collect(),
+            notes: self.notes.iter().filter(|n| kept(n.cue_id)).cloned().collect(),
+            regions: self
+                .regions
+                .iter()
+                .filter(|r| kept(r.cue_id))
+                .map(|r| Region {
+                    sample_length: clipped(cue_offset(r.cue_id), r.sample_length),
+                    ..r.clone()
+                })
+                .collect(),
+            embedded_files: self.embedded_files.iter().filter(|e| kept(e.cue_id)).cloned().collect(),
+            metadata: self.metadata.clone(),
+            broadcast_extension: self.broadcast_extension.as_ref().map(|bext| BroadcastExtension {
+                time_reference: bext.time_reference.saturating_add(start),
+                ..bext.clone()
+            }),
+            ixml: self.ixml.clone(),
+            sampler: self.sampler.as_ref().map(|sampler| SamplerChunk {
+                loops: sampler
+                    .loops
+                    .iter()
+                    .filter(|l| inside(l.start) && inside(l.end))
+                    .map(|l| {
+                        let mut l = l.clone();
+                        l.start = moved(l.start);
+                        l.end = moved(l.end);
+                        l
+                    })
+                    .collect(),
+                ..sampler.clone()
+            }),
+            instrument: self.instrument.clone(),
+            acid: self.acid.clone(),
+            cart: self.cart.clone(),
+            id3_tag: self.id3_tag.clone(),
+            peak_envelope: None,
+            extra_chunks: self.extra_chunks.clone(),
+            custom_chunks: CustomChunks::new(),
+            audio_ids: self.audio_ids.clone(),
+            axml: self.axml.clone(),
+            diagnostics: Vec::new(),
+            layout: None,
+        }
+    }
+
+    // Cuts off everything before `start` and after `end`, rounded down to whole frames.
+    // See `trim_frames`.
+    pub fn trim(&mut self, start: Duration, end: Duration) {
+        self.trim_frames(self.duration_to_frames(start), self.duration_to_frames(end));
+    }
+
+    // Cuts off the frames outside `start..end` in place. The cue points, regions, loops and so on
+    // are moved and dropped the same way `slice_frames` does, so they still point at the same
+    // audio, and so is the bext time reference. Unlike `slice_frames`, the custom chunks and diagnostics stay. The peak envelope
+    // goes since it no longer matches, and so does the layout from `parse_lossless`, so the file
+    // is written out the usual way.
+    pub fn trim_frames(&mut self, start: u64, end: u64) {
+        let trimmed = self.slice_frames(start..end);
+
+        self.channels = trimmed.channels;
+        self.fact_sample_count = trimmed.fact_sample_count;
+        self.cue_points = trimmed.cue_points;
+        self.playlist = trimmed.playlist;
+        self.labels = trimmed.labels;
+        self.notes = trimmed.notes;
+        self.regions = trimmed.regions;
+        self.embedded_files = trimmed.embedded_files;
+        self.sampler = trimmed.sampler;
+        self.broadcast_extension = trimmed.broadcast_extension;
+        self.peak_envelope = None;
+        self.layout = None;
+    }
+
+    // The file cut up at its markers, say to export the takes marked in a long recording as
+    // files of their own. Each piece runs from one marker to the next, or to the end of the
+    // region when the marker has a shorter one. Anything before the first marker is a piece
+    // too, so nothing is lost. A piece gets the label of its marker as its INFO title, and
+    // otherwise keeps the title of the whole file.
+    pub fn split_at_markers(&self) -> Vec<WaveFile> {
+        let num_frames = self.num_frames();
+        let mut markers = self.markers();
+        // Markers at the same spot would only make empty pieces
+        markers.dedup_by_key(|m| m.position_samples);
+
+        let mut pieces = Vec::new();
+        let first_marker = markers.first().map_or(num_frames, |m| m.position_samples as u64);
+        if first_marker > 0 {
+            pieces.push(self.slice_frames(0..first_marker));
+        }
+
+        for (i, marker) in markers.iter().enumerate() {
+            let start = marker.position_samples as u64;
+            if start >= num_frames {
+                break;
+            }
+            let next = markers.get(i + 1).map_or(num_frames, |m| m.position_samples as u64);
+            let end = match marker.region_length {
+                Some(length) => next.min(start + length as u64),
+                None => next,
+            };
+
+            let mut piece = self.slice_frames(start..end);
+            if let Some(label) = &marker.label {
+                piece.metadata.set_title(label.clone());
+            }
+            pieces.push(piece);
+        }
+
+        pieces
+    }
+
+    // The file cut up into pieces `length` long, the last one shorter when it doesn't come out
+    // even, say to upload a long recording in parts. The pieces are made with `slice_frames`.
+    // A `length` shorter than a frame gives no pieces at all.
+    pub fn split_every(&self, length: Duration) -> Vec<WaveFile> {
+        let frames_per_piece = self.duration_to_frames(length);
+        if frames_per_piece == 0 {
+            return Vec::new();
+        }
+
+        let num_frames = self.num_frames();
+        (0..num_frames)
+            .step_by(frames_per_piece.min(usize::MAX as u64) as usize)
+            .map(|start| self.slice_frames(start..start.saturating_add(frames_per_piece)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use std::time::Duration;
+    use crate::associated_data::{Label, Region};
+    use crate::bext::BroadcastExtension;
+    use crate::cue::CuePoint;
+    use crate::sampler::{SampleLoop, SamplerChunk};
+    use crate::wave_file::{Sample, WaveFile};
+
+    fn cue_point(id: u32, sample_offset: u32) -> CuePoint {
+        CuePoint {
+            id,
+            position: sample_offset,
+            chunk_id: *b"data",
+            chunk_start: 0,
+            block_start: 0,
+            sample_offset,
+        }
+    }
+
+    fn sample_loop(start: u32, end: u32) -> SampleLoop {
+        SampleLoop {
+            cue_point_id: 0,
+            loop_type: 0,
+            start,
+            end,
+            fraction: 0,
+            play_count: 0,
+        }
+    }
+
+    #[test]
+    fn test_slicing_by_time() {
+        let wave_file = WaveFile {
+            channels: vec![(0..100).map(Sample::BitDepth8).collect(), (100..200).map(Sample::BitDepth8).collect()],
+            sample_rate: 10,
+            fact_sample_count: Some(100),
+            ..Default::default()
+        };
+
+        let slice = wave_file.slice(Duration::from_secs(2)..Duration::from_millis(3500));
+
+        assert_eq!((20..35).map(Sample::BitDepth8).collect::<Vec<Sample>>(), slice.channels[0]);
+        assert_eq!((120..135).map(Sample::BitDepth8).collect::<Vec<Sample>>(), slice.channels[1]);
+        assert_eq!(Some(15), slice.fact_sample_count);
+        assert_eq!(10, slice.sample_rate);
+    }
+
+    #[test]
+    fn test_slicing_moves_the_markers_inside() {
+        let wave_file = WaveFile {
+            channels: vec![vec![Sample::BitDepth16(0); 100]],
+            cue_points: vec![cue_point(1, 10), cue_point(2, 50), cue_point(3, 95)],
+            labels: vec![Label { cue_id: 1, text: "intro".to_string() }, Label { cue_id: 2, text: "verse".to_string() }],
+            regions: vec![Region {
+                cue_id: 2,
+                sample_length: 40,
+                purpose: *b"rgn ",
+                text: "verse".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        let slice = wave_file.slice_frames(40..80);
+
+        assert_eq!(40, slice.num_frames());
+        assert_eq!(vec![cue_point(2, 10)], slice.cue_points);
+        assert_eq!(vec!["verse"], slice.labels.iter().map(|l| l.text.as_str()).collect::<Vec<&str>>());
+        // The region ran past the end of the slice
+        assert_eq!(30, slice.regions[0].sample_length);
+    }
+
+    #[test]
+    fn test_splitting_at_markers() {
+        let wave_file = WaveFile {
+            channels: vec![(0..100).map(Sample::BitDepth8).collect()],
+            cue_points: vec![cue_point(1, 60), cue_point(2, 10), cue_point(3, 60)],
+            labels: vec![Label { cue_id: 2, text: "first take".to_string() }, Label { cue_id: 1, text: "second take".to_string() }],
+            ..Default::default()
+        };
+
+        let pieces = wave_file.split_at_markers();
+
+        assert_eq!(vec![10, 50, 40], pieces.iter().map(|p| p.num_frames()).collect::<Vec<u64>>());
+        assert_eq!(Sample::BitDepth8(60), pieces[2].channels[0][0]);
+        assert_eq!(vec![None, Some("first take"), Some("second take")], pieces.iter().map(|p| p.metadata.title()).collect::<Vec<Option<&str>>>());
+        assert_eq!(vec![cue_point(2, 0)], pieces[1].cue_points);
+    }
+
+    #[test]
+    fn test_splitting_at_markers_with_regions() {
+        let wave_file = WaveFile {
+            channels: vec![vec![Sample::BitDepth16(0); 100]],
+            cue_points: vec![cue_point(1, 0), cue_point(2, 50)],
+            regions: vec![Region {
+                cue_id: 1,
+                sample_length: 30,
+                purpose: *b"rgn ",
+                text: "".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(vec![30, 50], wave_file.split_at_markers().iter().map(|p| p.num_frames()).collect::<Vec<u64>>());
+        // No markers, no splitting
+        assert_eq!(1, WaveFile { cue_points: vec![], ..wave_file }.split_at_markers().len());
+    }
+
+    #[test]
+    fn test_splitting_every_few_seconds() {
+        let wave_file = WaveFile {
+            channels: vec![(0..25).map(Sample::BitDepth8).collect(), (25..50).map(Sample::BitDepth8).collect()],
+            sample_rate: 2,
+            cue_points: vec![cue_point(1, 12)],
+            ..Default::default()
+        };
+
+        let pieces = wave_file.split_every(Duration::from_secs(5));
+
+        assert_eq!(vec![10, 10, 5], pieces.iter().map(|p| p.num_frames()).collect::<Vec<u64>>());
+        assert_eq!((45..50).map(Sample::BitDepth8).collect::<Vec<Sample>>(), pieces[2].channels[1]);
+        assert_eq!(vec![cue_point(1, 2)], pieces[1].cue_points);
+        assert!(WaveFile { sample_rate: 2, ..Default::default() }.split_every(Duration::from_secs(1)).is_empty());
+    }
+
+    #[test]
+    fn test_splitting_every_less_than_a_frame_gives_no_pieces() {
+        let wave_file = WaveFile {
+            channels: vec![vec![Sample::BitDepth8(0); 10]],
+            sample_rate: 400,
+            ..Default::default()
+        };
+
+        assert!(wave_file.split_every(Duration::from_millis(1)).is_empty());
+    }
+
+    #[test]
+    fn test_trimming_moves_the_markers_and_loops() {
+        let mut wave_file = WaveFile {
+            channels: vec![(0..100).map(Sample::BitDepth8).collect()],
+            sample_rate: 10,
+            fact_sample_count: Some(100),
+            cue_points: vec![cue_point(1, 5), cue_point(2, 30)],
+            labels: vec![Label { cue_id: 1, text: "gone".to_string() }, Label { cue_id: 2, text: "kept".to_string() }],
+            sampler: Some(SamplerChunk {
+                manufacturer: 0,
+                product: 0,
+                sample_period: 100_000_000,
+                midi_unity_note: 60,
+                midi_pitch_fraction: 0,
+                smpte_format: 0,
+                smpte_offset: 0,
+                loops: vec![sample_loop(40, 60), sample_loop(0, 60)],
+                sampler_data: vec![],
+            }),
+            ..Default::default()
+        };
+
+        wave_file.broadcast_extension = Some(BroadcastExtension { time_reference: 1000, ..Default::default() });
+
+        wave_file.trim(Duration::from_secs(2), Duration::from_secs(8));
+
+        assert_eq!((20..80).map(Sample::BitDepth8).collect::<Vec<Sample>>(), wave_file.channels[0]);
+        assert_eq!(Some(60), wave_file.fact_sample_count);
+        assert_eq!(vec![cue_point(2, 10)], wave_file.cue_points);
+        assert_eq!(vec!["kept"], wave_file.labels.iter().map(|l| l.text.as_str()).collect::<Vec<&str>>());
+        assert_eq!(1020, wave_file.broadcast_extension.unwrap().time_reference);
+        let loops = &wave_file.sampler.unwrap().loops;
+        assert_eq!(vec![(20, 40)], loops.iter().map(|l| (l.start, l.end)).collect::<Vec<(u32, u32)>>());
+    }
+
+    #[test]
+    fn test_slicing_moves_the_time_reference() {
+        let wave_file = WaveFile {
+            channels: vec![vec![Sample::BitDepth8(0); 100]],
+            broadcast_extension: Some(BroadcastExtension { time_reference: 48_000, ..Default::default() }),
+            ..Default::default()
+        };
+
+        let slice = wave_file.slice_frames(40..80);
+        assert_eq!(48_040, slice.broadcast_extension.unwrap().time_reference);
+
+        // Past the end, the slice starts at the end
+        let slice = wave_file.slice_frames(200..300);
+        assert_eq!(48_100, slice.broadcast_extension.unwrap().time_reference);
+    }
+
+    #[test]
+    fn test_slicing_past_the_end_is_clamped() {
+        let wave_file = WaveFile {
+            channels: vec![vec![Sample::BitDepth8(1); 10]],
+            ..Default::default()
+        };
+
+        assert_eq!(2, wave_file.slice_frames(8..20).num_frames());
+        assert_eq!(0, wave_file.slice_frames(30..40).num_frames());
+        #[allow(clippy::reversed_empty_ranges)]
+        let backwards = 5..2;
+        assert_eq!(0, wave_file.slice_frames(backwards).num_frames());
+    }
+}
@@ -0,0 +1,244 @@
+use std::io::{Seek, SeekFrom, Write};
+
+use crate::error::WaveError;
+use crate::fmt_chunk::{bytes_per_sample, FmtChunk};
+use crate::wave_file::{Sample, WaveFormatCategory};
+use crate::writer::{write_all, write_sample};
+
+// Where the RIFF size field sits, relative to the start of the RIFF chunk
+const RIFF_SIZE_OFFSET: u64 = 4;
+// Where the JUNK chunk that makes room for a ds64 chunk starts
+const JUNK_OFFSET: u64 = 12;
+// riffSize, dataSize and sampleCount, then the length of a table we leave empty
+const BYTES_DS64: u32 = 28;
+
+// A writer for when the samples aren't all known up front, like a live recording.
+// The header goes out straight away with the RIFF and data sizes left as 0. Frames are
+// appended as they arrive and the sizes are patched in by `finalize`.
+// A writer that is dropped without being finalized leaves a file with sizes of 0.
+//
+// There's no telling up front how long a recording will go on for, so a JUNK chunk the size of
+// a ds64 chunk goes in right after the RIFF header. If the file ends up over 4GB, `finalize`
+// turns it into an RF64 file, with the JUNK chunk becoming the ds64 chunk that holds the real
+// sizes. Otherwise the JUNK chunk stays, and gets skipped like any other JUNK chunk.
+pub struct StreamingWaveWriter<W: Write + Seek> {
+    writer: W,
+    fmt: FmtChunk,
+    wave_format: WaveFormatCategory,
+    // Where the RIFF chunk starts in `writer`
+    start: u64,
+    // The size of the RIFF header, fmt chunk and data chunk header.
+    // The data size field is the last 4 bytes of that.
+    header_size: u64,
+    data_size: u64,
+    // The biggest RIFF size that's written as is. Past this the file becomes RF64.
+    pub(crate) max_riff_size: u64,
+    // Reused for each frame so we don't allocate every time
+    frame: Vec<u8>,
+}
+
+impl<W: Write + Seek> StreamingWaveWriter<W> {
+    pub fn new(mut writer: W, fmt: FmtChunk) -> Result<StreamingWaveWriter<W>, WaveError> {
+        if fmt.channels == 0 {
+            return Err(WaveError::UnsupportedChannelCount(fmt.channels));
+        }
+
+        let wave_format = match WaveFormatCategory::from_tag(fmt.sub_format_tag()) {
+            Some(wave_format) => wave_format,
+            None => return Err(WaveError::UnsupportedFormat(fmt.format_tag)),
+        };
+
+        // The fields are public, so the block align could be anything. The sizes are worked
+        // out from it, so it has to be what the samples really take up.
+        let block_align = fmt.channels as u32 * bytes_per_sample(fmt.bits_per_sample)?;
+        if fmt.block_align as u32 != block_align {
+            return Err(WaveError::InvalidChunkSize(fmt.block_align as u32));
+        }
+
+        let start = writer.stream_position().map_err(|e| WaveError::Io(e.kind()))?;
+
+        let mut bytes = vec![];
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(b"WAVE");
+        bytes.extend_from_slice(b"JUNK");
+        bytes.extend_from_slice(&BYTES_DS64.to_le_bytes());
+        bytes.extend_from_slice(&[0; BYTES_DS64 as usize]);
+        bytes.extend_from_slice(&fmt.to_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        write_all(&mut writer, &bytes)?;
+
+        Ok(StreamingWaveWriter {
+            writer,
+            fmt,
+            wave_format,
+            start,
+            header_size: bytes.len() as u64,
+            data_size: 0,
+            max_riff_size: u32::MAX as u64,
+            frame: vec![],
+        })
+    }
+
+    // Append one sample per channel
+    pub fn write_frame(&mut self, frame: &[Sample]) -> Result<(), WaveError> {
+        if frame.len() != self.fmt.channels as usize {
+            return Err(WaveError::ChannelLengthMismatch);
+        }
+
+        self.frame.clear();
+        for sample in frame.iter() {
+            write_sample(&mut self.frame, sample, self.wave_format, self.fmt.bits_per_sample)?;
+        }
+        write_all(&mut self.writer, &self.frame)?;
+
+        self.data_size += self.frame.len() as u64;
+
+        Ok(())
+    }
+
+    // Write the pad byte if needed, fill in the sizes and hand back the writer.
+    // A file over 4GB is turned into an RF64 file here.
+    // The writer is left positioned at the end of the file.
+    pub fn finalize(mut self) -> Result<W, WaveError> {
+        let pad = self.data_size % 2;
+        if pad == 1 {
+            write_all(&mut self.writer, &[0])?;
+        }
+
+        let riff_size = self.header_size - 8 + self.data_size + pad;
+
+        if riff_size > self.max_riff_size {
+            // The sizes that don't fit are 0xFFFFFFFF, and the real ones go in the ds64 chunk
+            let mut ds64 = vec![];
+            ds64.extend_from_slice(b"ds64");
+            ds64.extend_from_slice(&BYTES_DS64.to_le_bytes());
+            ds64.extend_from_slice(&riff_size.to_le_bytes());
+            ds64.extend_from_slice(&self.data_size.to_le_bytes());
+            ds64.extend_from_slice(&(self.data_size / self.fmt.block_align as u64).to_le_bytes());
+            ds64.extend_from_slice(&0u32.to_le_bytes());
+
+            self.patch_bytes(0, b"RF64")?;
+            self.patch(RIFF_SIZE_OFFSET, u32::MAX)?;
+            self.patch_bytes(JUNK_OFFSET, &ds64)?;
+            self.patch(self.header_size - 4, u32::MAX)?;
+        } else {
+            self.patch(RIFF_SIZE_OFFSET, riff_size as u32)?;
+            self.patch(self.header_size - 4, self.data_size as u32)?;
+        }
+
+        let end = self.start + 8 + riff_size;
+        self.writer.seek(SeekFrom::Start(end)).map_err(|e| WaveError::Io(e.kind()))?;
+        self.writer.flush().map_err(|e| WaveError::Io(e.kind()))?;
+
+        Ok(self.writer)
+    }
+
+    fn patch(&mut self, offset: u64, value: u32) -> Result<(), WaveError> {
+        self.patch_bytes(offset, &value.to_le_bytes())
+    }
+
+    fn patch_bytes(&mut self, offset: u64, bytes: &[u8]) -> Result<(), WaveError> {
+        self.writer.seek(SeekFrom::Start(self.start + offset)).map_err(|e| WaveError::Io(e.kind()))?;
+        write_all(&mut self.writer, bytes)
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use std::io::Cursor;
+    use super::StreamingWaveWriter;
+    use crate::error::WaveError;
+    use crate::fmt_chunk::FmtChunk;
+    use crate::parser::WaveFileParser;
+    use crate::wave_file::Sample;
+
+    #[test]
+    fn test_streaming_frames_and_finalizing() {
+        let fmt = FmtChunk::pcm(2, 8000, 16).unwrap();
+        let mut writer = StreamingWaveWriter::new(Cursor::new(vec![]), fmt).unwrap();
+
+        for i in 0..10 {
+            writer.write_frame(&[Sample::BitDepth16(i), Sample::BitDepth16(-i)]).unwrap();
+        }
+
+        let bytes = writer.finalize().unwrap().into_inner();
+        let wave_file = WaveFileParser::parse(bytes.clone()).unwrap();
+
+        assert_eq!(&(bytes.len() as u32 - 8).to_le_bytes(), &bytes[4..8]);
+        assert_eq!(10, wave_file.channels[0].len());
+        assert_eq!(Sample::BitDepth16(9), wave_file.channels[0][9]);
+        assert_eq!(Sample::BitDepth16(-9), wave_file.channels[1][9]);
+    }
+
+    #[test]
+    fn test_streaming_odd_sized_data_adds_pad_byte() {
+        let fmt = FmtChunk::pcm(1, 8000, 8).unwrap();
+        let mut writer = StreamingWaveWriter::new(Cursor::new(vec![]), fmt).unwrap();
+        writer.write_frame(&[Sample::BitDepth8(0x80)]).unwrap();
+
+        let bytes = writer.finalize().unwrap().into_inner();
+
+        // 12 byte RIFF header, 36 byte JUNK chunk, 24 byte fmt chunk, 8 byte data header,
+        // the sample and a pad byte
+        assert_eq!(82, bytes.len());
+        assert_eq!(&1u32.to_le_bytes(), &bytes[76..80]);
+    }
+
+    #[test]
+    fn test_streaming_past_the_riff_limit_switches_to_rf64() {
+        let fmt = FmtChunk::pcm(2, 8000, 16).unwrap();
+        let mut writer = StreamingWaveWriter::new(Cursor::new(vec![]), fmt).unwrap();
+        // Stands in for 4GB, so the test doesn't have to write that much
+        writer.max_riff_size = 100;
+
+        for i in 0..50 {
+            writer.write_frame(&[Sample::BitDepth16(i), Sample::BitDepth16(-i)]).unwrap();
+        }
+
+        let bytes = writer.finalize().unwrap().into_inner();
+
+        assert_eq!(b"RF64", &bytes[..4]);
+        assert_eq!(&u32::MAX.to_le_bytes(), &bytes[4..8]);
+        assert_eq!(b"ds64", &bytes[12..16]);
+        assert_eq!(&(bytes.len() as u64 - 8).to_le_bytes(), &bytes[20..28]);
+        assert_eq!(&200u64.to_le_bytes(), &bytes[28..36]);
+        assert_eq!(&50u64.to_le_bytes(), &bytes[36..44]);
+
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+        assert_eq!(50, wave_file.channels[0].len());
+        assert_eq!(Sample::BitDepth16(-49), wave_file.channels[1][49]);
+    }
+
+    #[test]
+    fn test_streaming_under_the_riff_limit_keeps_the_junk_chunk() {
+        let fmt = FmtChunk::pcm(1, 8000, 8).unwrap();
+        let mut writer = StreamingWaveWriter::new(Cursor::new(vec![]), fmt).unwrap();
+        writer.write_frame(&[Sample::BitDepth8(0x80)]).unwrap();
+
+        let bytes = writer.finalize().unwrap().into_inner();
+
+        assert_eq!(b"RIFF", &bytes[..4]);
+        assert_eq!(b"JUNK", &bytes[12..16]);
+        assert_eq!(&[0; 28][..], &bytes[20..48]);
+    }
+
+    #[test]
+    fn test_streaming_frame_with_wrong_channel_count_returns_error() {
+        let fmt = FmtChunk::pcm(2, 8000, 8).unwrap();
+        let mut writer = StreamingWaveWriter::new(Cursor::new(vec![]), fmt).unwrap();
+
+        assert_eq!(Err(WaveError::ChannelLengthMismatch), writer.write_frame(&[Sample::BitDepth8(0x80)]));
+    }
+
+    #[test]
+    fn test_streaming_with_wrong_block_align_returns_error() {
+        let mut fmt = FmtChunk::pcm(2, 8000, 16).unwrap();
+
+        fmt.block_align = 0;
+        assert_eq!(Err(WaveError::InvalidChunkSize(0)), StreamingWaveWriter::new(Cursor::new(vec![]), fmt.clone()).map(|_| ()));
+        fmt.block_align = 6;
+        assert_eq!(Err(WaveError::InvalidChunkSize(6)), StreamingWaveWriter::new(Cursor::new(vec![]), fmt).map(|_| ()));
+    }
+}
@@ -0,0 +1,176 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::fmt::Write;
+use core::time::Duration;
+
+use crate::wave_file::{WaveFile, WaveFormatCategory};
+
+// A short description of a file: its format, how long it is, which chunks it has and its tags.
+// Made by `WaveFile::info`. Meant for tools that index libraries of audio, which tend to want
+// this much and no more. `to_json` turns it into something they can store.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WaveSummary {
+    pub format_tag: u16,
+    // The name of the format, when it's one this library knows by name
+    pub format: Option<WaveFormatCategory>,
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+    pub num_frames: u64,
+    pub duration: Duration,
+    // The ids of the chunks that were found, known ones first and then the rest in file order.
+    // The RIFF header and the LIST chunk itself aren't in here, the lists inside it are.
+    pub chunks: Vec<[u8; 4]>,
+    // The LIST INFO tags, in file order
+    pub tags: Vec<([u8; 4], String)>,
+}
+
+impl WaveSummary {
+    // A single JSON object. Chunk ids and tag keys are strings; the duration is in seconds.
+    //
+    //     {"format_tag":1,"format":"WAVE_FORMAT_PCM","channels":2,...,"tags":{"INAM":"Title"}}
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+
+        json.push('{');
+        let _ = write!(json, "\"format_tag\":{},", self.format_tag);
+        match self.format {
+            Some(format) => {
+                json.push_str("\"format\":");
+                push_json_string(&mut json, &format!("{:?}", format));
+                json.push(',');
+            }
+            None => json.push_str("\"format\":null,"),
+        }
+        let _ = write!(
+            json,
+            "\"channels\":{},\"sample_rate\":{},\"bits_per_sample\":{},\"num_frames\":{},\"duration\":{},",
+            self.channels,
+            self.sample_rate,
+            self.bits_per_sample,
+            self.num_frames,
+            self.duration.as_secs_f64()
+        );
+
+        json.push_str("\"chunks\":[");
+        for (i, id) in self.chunks.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            push_json_string(&mut json, &String::from_utf8_lossy(id));
+        }
+        json.push_str("],");
+
+        json.push_str("\"tags\":{");
+        for (i, (id, value)) in self.tags.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            push_json_string(&mut json, &String::from_utf8_lossy(id));
+            json.push(':');
+            push_json_string(&mut json, value);
+        }
+        json.push_str("}}");
+
+        json
+    }
+}
+
+// A quoted JSON string, with everything that needs escaping escaped
+fn push_json_string(json: &mut String, text: &str) {
+    json.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+impl WaveFile {
+    // A summary of the file, for indexing. See `WaveSummary`.
+    pub fn info(&self) -> WaveSummary {
+        let mut chunks: Vec<[u8; 4]> = vec![*b"fmt "];
+        let mut found = |present: bool, id: &[u8; 4]| {
+            if present {
+                chunks.push(*id);
+            }
+        };
+
+        found(self.fact_sample_count.is_some(), b"fact");
+        found(!self.cue_points.is_empty(), b"cue ");
+        found(!self.playlist.is_empty(), b"plst");
+        found(!self.labels.is_empty() || !self.notes.is_empty() || !self.regions.is_empty() || !self.embedded_files.is_empty(), b"adtl");
+        found(!self.metadata.is_empty(), b"INFO");
+        found(self.broadcast_extension.is_some(), b"bext");
+        found(self.ixml.is_some(), b"iXML");
+        found(self.sampler.is_some(), b"smpl");
+        found(self.instrument.is_some(), b"inst");
+        found(self.acid.is_some(), b"acid");
+        found(self.cart.is_some(), b"cart");
+        found(self.id3_tag.is_some(), b"id3 ");
+        found(self.peak_envelope.is_some(), b"levl");
+        found(!self.audio_ids.is_empty(), b"chna");
+        found(self.axml.is_some(), b"axml");
+        found(true, b"data");
+        chunks.extend(self.extra_chunks.iter().map(|c| c.id));
+
+        WaveSummary {
+            format_tag: self.format_tag,
+            format: WaveFormatCategory::from_tag(self.format_tag),
+            channels: self.channels.len() as u16,
+            sample_rate: self.sample_rate,
+            bits_per_sample: self.bits_per_sample,
+            num_frames: self.num_frames(),
+            duration: self.duration(),
+            chunks,
+            tags: self.metadata.iter().map(|(id, value)| (*id, value.to_string())).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use core::time::Duration;
+    use crate::parser::WaveFileParser;
+    use crate::testing::WaveFixture;
+    use crate::wave_file::WaveFormatCategory;
+
+    #[test]
+    fn test_info_summarizes_the_file() {
+        let bytes = WaveFixture::new(2, 16, 8000)
+            .duration(Duration::from_millis(250))
+            .chunk(*b"junk", vec![0; 4])
+            .info(*b"INAM", "Title")
+            .to_bytes();
+        let info = WaveFileParser::parse(bytes).unwrap().info();
+
+        assert_eq!(Some(WaveFormatCategory::WAVE_FORMAT_PCM), info.format);
+        assert_eq!(2, info.channels);
+        assert_eq!(2000, info.num_frames);
+        assert_eq!(Duration::from_millis(250), info.duration);
+        assert_eq!(vec![*b"fmt ", *b"INFO", *b"data", *b"junk"], info.chunks);
+        assert_eq!(vec![(*b"INAM", "Title".to_string())], info.tags);
+    }
+
+    #[test]
+    fn test_to_json() {
+        let bytes = WaveFixture::new(1, 8, 8000).num_frames(4000).info(*b"ICMT", "say \"hi\"\n").to_bytes();
+        let json = WaveFileParser::parse(bytes).unwrap().info().to_json();
+
+        assert_eq!(
+            "{\"format_tag\":1,\"format\":\"WAVE_FORMAT_PCM\",\"channels\":1,\"sample_rate\":8000,\"bits_per_sample\":8,\
+             \"num_frames\":4000,\"duration\":0.5,\"chunks\":[\"fmt \",\"INFO\",\"data\"],\"tags\":{\"ICMT\":\"say \\\"hi\\\"\\n\"}}",
+            json
+        );
+    }
+}
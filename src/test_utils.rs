@@ -0,0 +1,57 @@
+// Helpers shared by the unit tests
+
+use crate::raw_chunk::write_chunk;
+use crate::testing::riff_chunk;
+use crate::wave64::{wave64_guid, WAVE64_RIFF_GUID_SUFFIX};
+
+// Builds a minimal RIFF/WAVE byte sequence with a `fmt ` chunk followed by a `data` chunk
+pub(crate) fn wave_bytes(format_tag: u16, channels: u16, bits_per_sample: u16, data: &[u8]) -> Vec<u8> {
+    riff_bytes(&[(b"fmt ", &fmt_bytes(format_tag, channels, bits_per_sample)), (b"data", data)])
+}
+
+// The 16 bytes of a plain `fmt ` chunk at 8000Hz
+pub(crate) fn fmt_bytes(format_tag: u16, channels: u16, bits_per_sample: u16) -> Vec<u8> {
+    let block_align = channels * bits_per_sample.div_ceil(8);
+
+    let mut fmt = vec![];
+    fmt.extend_from_slice(&format_tag.to_le_bytes());
+    fmt.extend_from_slice(&channels.to_le_bytes());
+    fmt.extend_from_slice(&8000u32.to_le_bytes());
+    fmt.extend_from_slice(&(8000 * block_align as u32).to_le_bytes());
+    fmt.extend_from_slice(&block_align.to_le_bytes());
+    fmt.extend_from_slice(&bits_per_sample.to_le_bytes());
+
+    fmt
+}
+
+// Wraps the given chunks (id and contents) in a RIFF/WAVE header, adding pad bytes where needed
+pub(crate) fn riff_bytes(chunks: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+    let mut body = vec![];
+    for (id, contents) in chunks.iter() {
+        write_chunk(&mut body, id, contents);
+    }
+
+    riff_chunk(&body)
+}
+
+// A Wave64 file with the given chunks, padding each one to 8 bytes
+pub(crate) fn wave64_bytes(chunks: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+    let mut body = vec![];
+    for (id, contents) in chunks.iter() {
+        body.extend_from_slice(&wave64_guid(id));
+        body.extend_from_slice(&(24 + contents.len() as u64).to_le_bytes());
+        body.extend_from_slice(contents);
+        while body.len() % 8 != 0 {
+            body.push(0);
+        }
+    }
+
+    let mut bytes = vec![];
+    bytes.extend_from_slice(b"riff");
+    bytes.extend_from_slice(&WAVE64_RIFF_GUID_SUFFIX);
+    bytes.extend_from_slice(&(40 + body.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&wave64_guid(b"wave"));
+    bytes.extend_from_slice(&body);
+
+    bytes
+}
@@ -0,0 +1,148 @@
+// Builds '.wav' files in memory, so tests don't need any files on disk.
+// The library's own tests use it, and other crates can too by turning on the `testing` feature.
+//
+//     let bytes = WaveFixture::new(2, 16, 44100).duration(Duration::from_secs(1)).info(*b"INAM", "Test").to_bytes();
+
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::raw_chunk::write_chunk;
+
+// The description of a file to build. The samples are a sawtooth, different for each channel,
+// so that tests can tell the channels (and frames) apart.
+pub struct WaveFixture {
+    channels: u16,
+    bits_per_sample: u16,
+    sample_rate: u32,
+    num_frames: u64,
+    // Chunks to put between the fmt and data chunks
+    chunks: Vec<([u8; 4], Vec<u8>)>,
+    // Text for a LIST INFO chunk
+    info: Vec<([u8; 4], String)>,
+}
+
+impl WaveFixture {
+    // Integer PCM samples. 8, 16, 24 and 32 bits per sample work.
+    pub fn new(channels: u16, bits_per_sample: u16, sample_rate: u32) -> WaveFixture {
+        WaveFixture {
+            channels,
+            bits_per_sample,
+            sample_rate,
+            num_frames: 0,
+            chunks: vec![],
+            info: vec![],
+        }
+    }
+
+    // How long the file plays for. Rounded down to a whole number of frames.
+    pub fn duration(mut self, duration: Duration) -> WaveFixture {
+        self.num_frames = (duration.as_nanos() * self.sample_rate as u128 / 1_000_000_000) as u64;
+        self
+    }
+
+    pub fn num_frames(mut self, num_frames: u64) -> WaveFixture {
+        self.num_frames = num_frames;
+        self
+    }
+
+    // Add a chunk, as it is, before the data chunk
+    pub fn chunk(mut self, id: [u8; 4], contents: Vec<u8>) -> WaveFixture {
+        self.chunks.push((id, contents));
+        self
+    }
+
+    // Add a piece of text to the LIST INFO chunk, like `INAM` for the title
+    pub fn info(mut self, id: [u8; 4], text: &str) -> WaveFixture {
+        self.info.push((id, text.to_string()));
+        self
+    }
+
+    // The sample the fixture puts at `frame` in `channel`, as it would be decoded.
+    // 8 bit samples are unsigned, the rest are signed.
+    pub fn sample(&self, frame: u64, channel: u16) -> i64 {
+        let step = (frame + channel as u64 * 7) % 256;
+
+        match self.bits_per_sample {
+            0..=8 => step as i64,
+            bits => (step as i64 - 128) << (bits - 8),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let bytes_per_sample = self.bits_per_sample.div_ceil(8);
+        let block_align = self.channels * bytes_per_sample;
+
+        let mut fmt = vec![];
+        fmt.extend_from_slice(&1u16.to_le_bytes());
+        fmt.extend_from_slice(&self.channels.to_le_bytes());
+        fmt.extend_from_slice(&self.sample_rate.to_le_bytes());
+        fmt.extend_from_slice(&(self.sample_rate * block_align as u32).to_le_bytes());
+        fmt.extend_from_slice(&block_align.to_le_bytes());
+        fmt.extend_from_slice(&self.bits_per_sample.to_le_bytes());
+
+        let mut data = Vec::with_capacity(self.num_frames as usize * block_align as usize);
+        for frame in 0..self.num_frames {
+            for channel in 0..self.channels {
+                let sample = self.sample(frame, channel).to_le_bytes();
+                data.extend_from_slice(&sample[..bytes_per_sample as usize]);
+            }
+        }
+
+        let mut body = vec![];
+        write_chunk(&mut body, b"fmt ", &fmt);
+        for (id, contents) in self.chunks.iter() {
+            write_chunk(&mut body, id, contents);
+        }
+        if !self.info.is_empty() {
+            let mut list = b"INFO".to_vec();
+            for (id, text) in self.info.iter() {
+                let mut text = text.as_bytes().to_vec();
+                text.push(0);
+                write_chunk(&mut list, id, &text);
+            }
+            write_chunk(&mut body, b"LIST", &list);
+        }
+        write_chunk(&mut body, b"data", &data);
+
+        riff_chunk(&body)
+    }
+}
+
+// The RIFF chunk around `body`, which is the chunks that go in it one after the other
+pub(crate) fn riff_chunk(body: &[u8]) -> Vec<u8> {
+    let mut contents = b"WAVE".to_vec();
+    contents.extend_from_slice(body);
+
+    let mut bytes = vec![];
+    write_chunk(&mut bytes, b"RIFF", &contents);
+
+    bytes
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use std::time::Duration;
+    use super::WaveFixture;
+
+    #[test]
+    fn test_fixture_layout() {
+        let bytes = WaveFixture::new(2, 16, 8000).duration(Duration::from_millis(10)).to_bytes();
+
+        // 80 frames of 4 bytes, after the RIFF header and the fmt chunk
+        assert_eq!(12 + 24 + 8 + 320, bytes.len());
+        assert_eq!(b"data", &bytes[36..40]);
+        assert_eq!(320u32.to_le_bytes(), bytes[40..44]);
+    }
+
+    #[test]
+    fn test_fixture_samples_differ_by_channel() {
+        let fixture = WaveFixture::new(2, 8, 8000);
+
+        assert_eq!(0, fixture.sample(0, 0));
+        assert_eq!(7, fixture.sample(0, 1));
+        assert_eq!(1, fixture.sample(1, 0));
+        assert_eq!(-128 << 8, WaveFixture::new(1, 16, 8000).sample(0, 0));
+    }
+}
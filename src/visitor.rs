@@ -0,0 +1,147 @@
+// A callback interface on top of `WaveEvents`, for pulling a few things out of a file without
+// parsing the rest of it. Implement the methods for the parts you care about and leave the others;
+// return `ControlFlow::Break` once you have everything and the parser stops right there.
+//
+//     struct FirstSecond { bext: Option<BroadcastExtension>, samples: Vec<u8>, wanted: usize }
+//
+//     impl ChunkVisitor for FirstSecond {
+//         fn on_fmt(&mut self, fmt: &FmtChunk) -> ControlFlow<()> {
+//             self.wanted = fmt.byte_rate as usize;
+//             ControlFlow::Continue(())
+//         }
+//         fn on_data_block(&mut self, samples: &[u8]) -> ControlFlow<()> { ... }
+//         fn on_bext(&mut self, bext: &BroadcastExtension) -> ControlFlow<()> { ... }
+//     }
+
+use core::ops::ControlFlow;
+
+use crate::bext::{self, BroadcastExtension};
+use crate::error::WaveError;
+use crate::events::{WaveEvent, WaveEvents};
+use crate::fmt_chunk::FmtChunk;
+use crate::parser::WaveFileParser;
+
+pub trait ChunkVisitor {
+    fn on_fmt(&mut self, _fmt: &FmtChunk) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    // Some of the samples, as they are in the file. A data chunk comes in one or more blocks
+    // of whole frames, in order.
+    fn on_data_block(&mut self, _samples: &[u8]) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_bext(&mut self, _bext: &BroadcastExtension) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    // Every chunk that doesn't have a method of its own, with its contents as they are in the file
+    fn on_unknown_chunk(&mut self, _id: [u8; 4], _contents: &[u8]) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+}
+
+impl WaveFileParser {
+    // Walk the chunks in `bytes`, calling the matching method of `visitor` for each one.
+    // Only the fmt chunk and the chunks with a typed method are picked apart.
+    pub fn visit<V: ChunkVisitor>(bytes: &[u8], visitor: &mut V) -> Result<(), WaveError> {
+        let mut chunk_id = [0; 4];
+
+        for event in WaveEvents::new(bytes)? {
+            let flow = match event? {
+                WaveEvent::ChunkStart(id, _) => {
+                    chunk_id = id;
+                    ControlFlow::Continue(())
+                }
+                WaveEvent::FmtParsed(fmt) => visitor.on_fmt(&fmt),
+                WaveEvent::Samples(samples) => visitor.on_data_block(samples),
+                WaveEvent::ChunkData(contents) if &chunk_id == b"bext" => visitor.on_bext(&bext::read_bext(contents)?),
+                WaveEvent::ChunkData(contents) => visitor.on_unknown_chunk(chunk_id, contents),
+                WaveEvent::ChunkEnd => ControlFlow::Continue(()),
+            };
+
+            if flow.is_break() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use core::ops::ControlFlow;
+    use super::ChunkVisitor;
+    use crate::bext::BroadcastExtension;
+    use crate::fmt_chunk::FmtChunk;
+    use crate::parser::WaveFileParser;
+    use crate::test_utils::{fmt_bytes, riff_bytes};
+
+    // Picks out the bext description and the first `wanted` bytes of samples
+    #[derive(Default)]
+    struct Collector {
+        description: Option<String>,
+        channels: u16,
+        samples: Vec<u8>,
+        wanted: usize,
+        chunks: Vec<[u8; 4]>,
+    }
+
+    impl ChunkVisitor for Collector {
+        fn on_fmt(&mut self, fmt: &FmtChunk) -> ControlFlow<()> {
+            self.channels = fmt.channels;
+            ControlFlow::Continue(())
+        }
+
+        fn on_data_block(&mut self, samples: &[u8]) -> ControlFlow<()> {
+            let count = samples.len().min(self.wanted - self.samples.len());
+            self.samples.extend_from_slice(&samples[..count]);
+
+            if self.samples.len() == self.wanted {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        }
+
+        fn on_bext(&mut self, bext: &BroadcastExtension) -> ControlFlow<()> {
+            self.description = Some(bext.description.clone());
+            ControlFlow::Continue(())
+        }
+
+        fn on_unknown_chunk(&mut self, id: [u8; 4], _contents: &[u8]) -> ControlFlow<()> {
+            self.chunks.push(id);
+            ControlFlow::Continue(())
+        }
+    }
+
+    #[test]
+    fn test_visiting_chunks() {
+        let mut bext = vec![0; 602];
+        bext[..5].copy_from_slice(b"Scene");
+        let data: Vec<u8> = (0..200).collect();
+        let bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 2, 16)), (b"bext", &bext), (b"junk", b""), (b"data", &data), (b"afte", b"r")]);
+
+        let mut collector = Collector { wanted: 10, ..Default::default() };
+        WaveFileParser::visit(&bytes, &mut collector).unwrap();
+
+        assert_eq!(2, collector.channels);
+        assert_eq!(Some("Scene".to_string()), collector.description);
+        assert_eq!((0..10).collect::<Vec<u8>>(), collector.samples);
+        // Stopped in the data chunk, so the chunk after it was never visited
+        assert_eq!(vec![*b"junk"], collector.chunks);
+    }
+
+    #[test]
+    fn test_visiting_truncated_file_returns_error() {
+        let mut bytes = riff_bytes(&[(b"fmt ", &fmt_bytes(1, 1, 8)), (b"data", &[0x80; 4])]);
+        bytes.truncate(bytes.len() - 1);
+
+        let mut collector = Collector { wanted: 100, ..Default::default() };
+
+        assert!(WaveFileParser::visit(&bytes, &mut collector).is_err());
+        assert_eq!(vec![0x80; 3], collector.samples);
+    }
+}
@@ -0,0 +1,124 @@
+// Sony Wave64, which is RIFF with the size limits taken off.
+// Chunk ids are 16 byte GUIDs and chunk sizes are 64 bits. Each size counts the 24 byte
+// chunk header as well as the contents, and every chunk is padded out to a multiple of 8 bytes.
+// Apart from that the chunks hold the same things as their RIFF counterparts.
+
+use alloc::vec::Vec;
+
+use crate::byte_stream::Source;
+use crate::error::WaveError;
+use crate::parser::{fourcc, ChunkParser};
+use crate::wave_file::WaveFile;
+
+// The chunk GUIDs for the chunks we care about all start with the RIFF fourcc and end with this
+const WAVE64_GUID_SUFFIX: [u8; 12] = [0xf3, 0xac, 0xd3, 0x11, 0x8c, 0xd1, 0x00, 0xc0, 0x4f, 0x8e, 0xdb, 0x8a];
+// Except for the riff GUID itself
+pub(crate) const WAVE64_RIFF_GUID_SUFFIX: [u8; 12] = [0x2e, 0x91, 0xcf, 0x11, 0xa5, 0xd6, 0x28, 0xdb, 0x04, 0xc1, 0x00, 0x00];
+
+const BYTES_GUID: usize = 16;
+const BYTES_CHUNK_SIZE: usize = 8;
+const BYTES_CHUNK_HEADER: u64 = (BYTES_GUID + BYTES_CHUNK_SIZE) as u64;
+
+impl<S: Source> ChunkParser<S> {
+    // Picks up right after the "riff" that starts the riff GUID
+    pub(crate) fn read_wave64_file(&mut self, wave_file: &mut WaveFile) -> Result<(), WaveError> {
+        if self.byte_stream.read(12)? != WAVE64_RIFF_GUID_SUFFIX {
+            return Err(WaveError::UnexpectedChunk {
+                expected: *b"RIFF",
+                found: *b"riff",
+            });
+        }
+        self.current_chunk = Some(*b"riff");
+        // The size of the whole file
+        self.read_wave64_size()?;
+
+        let form_type = self.byte_stream.read(BYTES_GUID)?;
+        if form_type != wave64_guid(b"wave") {
+            return Err(WaveError::UnexpectedChunk {
+                expected: *b"wave",
+                found: fourcc(&form_type),
+            });
+        }
+
+        let mut found_fmt = false;
+
+        loop {
+            let guid = match self.byte_stream.read_or_eof(BYTES_GUID)? {
+                Some(guid) => guid,
+                None => return Err(WaveError::MissingChunk(*b"data")),
+            };
+            self.current_chunk = Some(fourcc(&guid));
+            self.count_chunk()?;
+            let size = match self.read_wave64_size()?.checked_sub(BYTES_CHUNK_HEADER) {
+                Some(size) => size,
+                None => return Err(WaveError::InvalidChunkSize(0)),
+            };
+            let padding = (8 - size % 8) % 8;
+
+            if guid == wave64_guid(b"fmt ") {
+                self.read_fmt_contents(wave_file, small_size(size)?)?;
+                found_fmt = true;
+            } else if guid == wave64_guid(b"fact") {
+                self.read_fact_contents(wave_file, small_size(size)?)?;
+            } else if guid == wave64_guid(b"data") {
+                if !found_fmt {
+                    return Err(WaveError::MissingChunk(*b"fmt "));
+                }
+                self.read_data_contents(wave_file, size)?;
+
+                // The padding may be missing if the data chunk is the last thing in the file
+                self.byte_stream.read_or_eof(padding as usize)?;
+                return Ok(());
+            } else {
+                // Not a chunk we know about :/ Skip over it
+                self.byte_stream.skip(size)?;
+            }
+
+            self.byte_stream.skip(padding)?;
+        }
+    }
+
+    fn read_wave64_size(&mut self) -> Result<u64, WaveError> {
+        self.byte_stream.read_u64()
+    }
+}
+
+pub(crate) fn wave64_guid(fourcc: &[u8; 4]) -> Vec<u8> {
+    [&fourcc[..], &WAVE64_GUID_SUFFIX[..]].concat()
+}
+
+// The chunks other than data have no business being over 4GB
+fn small_size(size: u64) -> Result<u32, WaveError> {
+    if size > u32::MAX as u64 {
+        return Err(WaveError::InvalidChunkSize(u32::MAX));
+    }
+
+    Ok(size as u32)
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use crate::error::WaveError;
+    use crate::parser::WaveFileParser;
+    use crate::test_utils::{fmt_bytes, wave64_bytes};
+    use crate::wave_file::Sample;
+
+    #[test]
+    fn test_parsing_wave64() {
+        // The junk chunk isn't a multiple of 8 bytes, so it gets padded
+        let bytes = wave64_bytes(&[(b"junk", &[0; 3]), (b"fmt ", &fmt_bytes(1, 2, 16)), (b"data", &[1, 0, 2, 0, 3, 0, 4, 0])]);
+
+        let wave_file = WaveFileParser::parse(bytes).unwrap();
+
+        assert_eq!(8000, wave_file.sample_rate);
+        assert_eq!(vec![Sample::BitDepth16(1), Sample::BitDepth16(3)], wave_file.channels[0]);
+        assert_eq!(vec![Sample::BitDepth16(2), Sample::BitDepth16(4)], wave_file.channels[1]);
+    }
+
+    #[test]
+    fn test_parsing_wave64_data_before_fmt_returns_error() {
+        let bytes = wave64_bytes(&[(b"data", &[0; 4]), (b"fmt ", &fmt_bytes(1, 2, 16))]);
+
+        assert_eq!(Err(WaveError::MissingChunk(*b"fmt ")), WaveFileParser::parse(bytes).map(|_| ()).map_err(|e| e.kind().clone()));
+    }
+}
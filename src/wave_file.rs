@@ -0,0 +1,419 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::acid::AcidChunk;
+use crate::adm::AudioId;
+use crate::associated_data::{EmbeddedFile, Label, Note, Region};
+use crate::bext::BroadcastExtension;
+use crate::cart::CartChunk;
+use crate::channel_layout::ChannelLayout;
+use crate::convert::FromSample;
+use crate::cue::{CuePoint, Marker, PlaylistSegment};
+use crate::custom_chunk::CustomChunks;
+use crate::diagnostic::Diagnostic;
+use crate::frame::{Frames, Windows};
+use crate::fmt_chunk;
+use crate::id3::Id3Tag;
+use crate::ixml::Ixml;
+use crate::layout::FileLayout;
+use crate::levl::PeakEnvelope;
+use crate::metadata::Metadata;
+use crate::raw_chunk::RawChunk;
+use crate::sampler::{Instrument, SamplerChunk};
+
+// This library only supports integer samples up to 32 bits
+// 24 bit samples are sign extended into an i32, so they range from -8388608 to 8388607.
+// Floating point samples are nominally between -1.0 and 1.0.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Sample {
+    BitDepth8(u8),
+    BitDepth16(i16),
+    BitDepth24(i32),
+    BitDepth32(i32),
+    Float32(f32),
+    Float64(f64),
+}
+
+impl Sample {
+    // The sample scaled to between -1.0 and 1.0, whatever its bit depth.
+    // Integers are divided by 2^(bits - 1), so the most negative value maps to exactly -1.0 and
+    // the most positive to just under 1.0. For 32 bit samples "just under" is too close to 1.0
+    // for an f32 to hold, so the most positive one rounds to exactly 1.0.
+    // 8 bit samples are unsigned, centered on 128.
+    // Floats are passed through as they are, even when they are out of range.
+    pub fn to_f32(&self) -> f32 {
+        match *self {
+            Sample::BitDepth8(s) => (s as f32 - 128.0) / 128.0,
+            Sample::BitDepth16(s) => s as f32 / 32_768.0,
+            Sample::BitDepth24(s) => s as f32 / 8_388_608.0,
+            Sample::BitDepth32(s) => (s as f64 / 2_147_483_648.0) as f32,
+            Sample::Float32(s) => s,
+            Sample::Float64(s) => s as f32,
+        }
+    }
+}
+
+// The encodings this library knows how to decode.
+// The values are the wFormatTag that identifies them in the `fmt ` chunk.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WaveFormatCategory {
+    WAVE_FORMAT_PCM = 0x0001,
+    // Microsoft ADPCM. Blocks of 4 bit codes that decode to 16 bit samples.
+    WAVE_FORMAT_ADPCM = 0x0002,
+    WAVE_FORMAT_IEEE_FLOAT = 0x0003,
+    // G.711 A-law. Each 8 bit sample decodes to a 16 bit one.
+    WAVE_FORMAT_ALAW = 0x0006,
+    // G.711 µ-law. Each 8 bit sample decodes to a 16 bit one.
+    WAVE_FORMAT_MULAW = 0x0007,
+    // IMA ADPCM. Blocks of 4 bit codes that decode to 16 bit samples.
+    WAVE_FORMAT_IMA_ADPCM = 0x0011,
+}
+
+impl WaveFormatCategory {
+    pub fn from_tag(format_tag: u16) -> Option<WaveFormatCategory> {
+        match format_tag {
+            0x0001 => Some(WaveFormatCategory::WAVE_FORMAT_PCM),
+            0x0002 => Some(WaveFormatCategory::WAVE_FORMAT_ADPCM),
+            0x0003 => Some(WaveFormatCategory::WAVE_FORMAT_IEEE_FLOAT),
+            0x0006 => Some(WaveFormatCategory::WAVE_FORMAT_ALAW),
+            0x0007 => Some(WaveFormatCategory::WAVE_FORMAT_MULAW),
+            0x0011 => Some(WaveFormatCategory::WAVE_FORMAT_IMA_ADPCM),
+            _ => None,
+        }
+    }
+}
+
+// The structure of the wave file that will be returned by the call to
+// WaveFileParser::parse()
+pub struct WaveFile {
+    pub channels: Vec<Vec<Sample>>,
+    pub wave_format: WaveFormatCategory,
+    // The format tag as it appears in the file (or in the SubFormat, for extensible files).
+    // Formats decoded by a registered WaveCodec have no WaveFormatCategory, so this is
+    // the way to tell them apart. `wave_format` is left as PCM for those.
+    pub format_tag: u16,
+    pub sample_rate: u32,
+    pub byte_rate: u32,
+    pub block_align: u16,
+    pub bits_per_sample: u16,
+    // How many of the bits in each sample carry information. Only the extensible format
+    // can make this smaller than `bits_per_sample`.
+    pub valid_bits_per_sample: u16,
+    // Which speaker each channel is meant for. 0 when the file doesn't say.
+    pub channel_mask: u32,
+    // The number of samples per channel, according to the fact chunk.
+    // Files in compressed formats are supposed to have one.
+    pub fact_sample_count: Option<u32>,
+    // The markers from the cue chunk
+    pub cue_points: Vec<CuePoint>,
+    // The order to play the audio in, from the plst chunk
+    pub playlist: Vec<PlaylistSegment>,
+    // Names and comments attached to cue points, from the associated data list
+    pub labels: Vec<Label>,
+    pub notes: Vec<Note>,
+    // Text attached to stretches of audio, from the associated data list
+    pub regions: Vec<Region>,
+    // Files attached to cue points, from the associated data list
+    pub embedded_files: Vec<EmbeddedFile>,
+    // Title, artist and friends from the LIST INFO chunk
+    pub metadata: Metadata,
+    // The Broadcast Wave bext chunk
+    pub broadcast_extension: Option<BroadcastExtension>,
+    // The iXML production metadata
+    pub ixml: Option<Ixml>,
+    // Pitch and loop points for samplers, from the smpl chunk
+    pub sampler: Option<SamplerChunk>,
+    // Key and velocity ranges for samplers, from the inst chunk
+    pub instrument: Option<Instrument>,
+    // Tempo and beats for loops, from the acid chunk
+    pub acid: Option<AcidChunk>,
+    // Radio automation data, from the cart chunk
+    pub cart: Option<CartChunk>,
+    // An ID3v2 tag from an id3 chunk
+    pub id3_tag: Option<Id3Tag>,
+    // A precomputed waveform overview, from the levl chunk
+    pub peak_envelope: Option<PeakEnvelope>,
+    // Every chunk the parser doesn't know about, in the order they appear in the file
+    pub extra_chunks: Vec<RawChunk>,
+    // What the chunk handlers registered with `WaveFileParser::register_chunk` made
+    pub custom_chunks: CustomChunks,
+    // The track to ADM object mapping from the chna chunk of a BW64 file
+    pub audio_ids: Vec<AudioId>,
+    // The ADM XML from the axml chunk
+    pub axml: Option<String>,
+    // Problems the parser ran into but got past. `validate` has the full list.
+    pub diagnostics: Vec<Diagnostic>,
+    // How the file was laid out, when it was parsed with `WaveFileParser::parse_lossless`.
+    // The writer uses it to write the file back out exactly as it was.
+    pub layout: Option<FileLayout>,
+}
+
+impl WaveFile {
+    // The number of frames (one sample per channel)
+    pub fn num_frames(&self) -> u64 {
+        self.channels.first().map_or(0, |c| c.len()) as u64
+    }
+
+    // How long the samples take to play
+    pub fn duration(&self) -> Duration {
+        self.frames_to_duration(self.num_frames())
+    }
+
+    // How long `frames` frames take to play at this file's sample rate
+    pub fn frames_to_duration(&self, frames: u64) -> Duration {
+        let sample_rate = self.sample_rate as u64;
+        if sample_rate == 0 {
+            return Duration::from_secs(0);
+        }
+
+        Duration::from_secs(frames / sample_rate) + Duration::from_nanos((frames % sample_rate) * 1_000_000_000 / sample_rate)
+    }
+
+    // The frame that plays `duration` into the file, rounded down
+    pub fn duration_to_frames(&self, duration: Duration) -> u64 {
+        (duration.as_nanos() * self.sample_rate as u128 / 1_000_000_000) as u64
+    }
+
+    // The samples a frame at a time: one sample from each channel, in channel order.
+    // Handy for processing interleaved audio without indexing into every channel by hand.
+    pub fn frames(&self) -> Frames<'_> {
+        Frames::new(&self.channels)
+    }
+
+    // The samples in blocks of `window_size` frames, each starting `hop_size` frames after the
+    // one before, for analysis that works a window at a time (RMS envelopes, spectra and the like).
+    // A `hop_size` smaller than `window_size` gives overlapping windows.
+    // Panics if either of them is 0.
+    pub fn windows(&self, window_size: usize, hop_size: usize) -> Windows<'_> {
+        Windows::new(&self.channels, window_size, hop_size)
+    }
+
+    // Every channel converted with `Sample::to_f32`
+    pub fn channels_f32(&self) -> Vec<Vec<f32>> {
+        self.channels.iter().map(|c| c.iter().map(Sample::to_f32).collect()).collect()
+    }
+
+    // Every channel converted to `T`, using the rules in `FromSample`.
+    // `samples_as::<f32>()` is the same as `channels_f32()`.
+    pub fn samples_as<T: FromSample<Sample>>(&self) -> Vec<Vec<T>> {
+        self.channels.iter().map(|c| c.iter().cloned().map(T::from_sample).collect()).collect()
+    }
+
+    // Which speaker each of `channels` is meant for, going by the channel mask
+    pub fn channel_layout(&self) -> ChannelLayout {
+        ChannelLayout::from_mask(self.channel_mask, self.channels.len())
+    }
+
+    // The cue points joined up with their labels, notes and regions, in the order they
+    // appear in the audio. When a cue point has more than one of something, the first one wins.
+    pub fn markers(&self) -> Vec<Marker> {
+        let mut markers: Vec<Marker> = self
+            .cue_points
+            .iter()
+            .map(|cue_point| Marker {
+                cue_id: cue_point.id,
+                position_samples: cue_point.sample_offset,
+                label: self.labels.iter().find(|l| l.cue_id == cue_point.id).map(|l| l.text.clone()),
+                note: self.notes.iter().find(|n| n.cue_id == cue_point.id).map(|n| n.text.clone()),
+                region_length: self.regions.iter().find(|r| r.cue_id == cue_point.id).map(|r| r.sample_length),
+            })
+            .collect();
+
+        markers.sort_by_key(|m| m.position_samples);
+
+        markers
+    }
+
+    // Everything that's off about the file without being bad enough to stop it from being read.
+    // That's whatever the parser ran into, plus any fmt fields that don't agree with each other.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.diagnostics.clone();
+
+        // The compressed formats (and anything decoded by a registered codec) work out
+        // the block align and byte rate their own way
+        let is_per_sample = match WaveFormatCategory::from_tag(self.format_tag) {
+            Some(WaveFormatCategory::WAVE_FORMAT_ADPCM) | Some(WaveFormatCategory::WAVE_FORMAT_IMA_ADPCM) | None => false,
+            Some(_) => true,
+        };
+        if !is_per_sample {
+            return diagnostics;
+        }
+
+        if let Ok(bytes_per_sample) = fmt_chunk::bytes_per_sample(self.bits_per_sample) {
+            let expected = self.channels.len() as u32 * bytes_per_sample;
+            if expected != self.block_align as u32 {
+                diagnostics.push(Diagnostic::BlockAlignMismatch {
+                    declared: self.block_align,
+                    expected: expected.min(u16::MAX as u32) as u16,
+                });
+            }
+        }
+
+        let expected = self.sample_rate.saturating_mul(self.block_align as u32);
+        if expected != self.byte_rate {
+            diagnostics.push(Diagnostic::ByteRateMismatch {
+                declared: self.byte_rate,
+                expected,
+            });
+        }
+
+        diagnostics
+    }
+}
+
+// Parsed files get handed to other threads, so nothing in a WaveFile can be allowed to stop that
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<WaveFile>();
+};
+
+impl Default for WaveFile {
+    fn default() -> Self {
+        WaveFile {
+            channels: vec![],
+            wave_format: WaveFormatCategory::WAVE_FORMAT_PCM,
+            format_tag: WaveFormatCategory::WAVE_FORMAT_PCM as u16,
+            sample_rate: 0,
+            byte_rate: 0,
+            block_align: 0,
+            bits_per_sample: 0,
+            valid_bits_per_sample: 0,
+            channel_mask: 0,
+            fact_sample_count: None,
+            cue_points: vec![],
+            playlist: vec![],
+            labels: vec![],
+            notes: vec![],
+            regions: vec![],
+            embedded_files: vec![],
+            metadata: Default::default(),
+            broadcast_extension: None,
+            ixml: None,
+            sampler: None,
+            instrument: None,
+            acid: None,
+            cart: None,
+            id3_tag: None,
+            peak_envelope: None,
+            extra_chunks: vec![],
+            custom_chunks: CustomChunks::new(),
+            audio_ids: vec![],
+            axml: None,
+            diagnostics: vec![],
+            layout: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use core::time::Duration;
+    use super::{Sample, WaveFile};
+    use crate::associated_data::{Label, Note, Region};
+    use crate::cue::CuePoint;
+    use crate::diagnostic::Diagnostic;
+
+    fn cue_point(id: u32, sample_offset: u32) -> CuePoint {
+        CuePoint {
+            id,
+            position: sample_offset,
+            chunk_id: *b"data",
+            chunk_start: 0,
+            block_start: 0,
+            sample_offset,
+        }
+    }
+
+    #[test]
+    fn test_markers_join_cue_points_with_associated_data() {
+        let wave_file = WaveFile {
+            cue_points: vec![cue_point(1, 500), cue_point(2, 100)],
+            labels: vec![Label { cue_id: 1, text: "Chorus".to_string() }, Label { cue_id: 2, text: "Verse".to_string() }],
+            notes: vec![Note { cue_id: 2, text: "Too loud".to_string() }],
+            regions: vec![Region { cue_id: 1, sample_length: 250, purpose: *b"rgn ", text: String::new() }],
+            ..Default::default()
+        };
+
+        let markers = wave_file.markers();
+
+        assert_eq!(2, markers[0].cue_id);
+        assert_eq!(100, markers[0].position_samples);
+        assert_eq!(Some("Verse".to_string()), markers[0].label);
+        assert_eq!(Some("Too loud".to_string()), markers[0].note);
+        assert_eq!(None, markers[0].region_length);
+        assert_eq!(Some("Chorus".to_string()), markers[1].label);
+        assert_eq!(None, markers[1].note);
+        assert_eq!(Some(250), markers[1].region_length);
+    }
+
+    #[test]
+    fn test_validate_checks_block_align_and_byte_rate() {
+        let mut wave_file = WaveFile {
+            channels: vec![vec![], vec![]],
+            sample_rate: 44100,
+            byte_rate: 176400,
+            block_align: 4,
+            bits_per_sample: 16,
+            ..Default::default()
+        };
+        assert!(wave_file.validate().is_empty());
+
+        wave_file.block_align = 2;
+        wave_file.byte_rate = 44100;
+
+        let expected = vec![
+            Diagnostic::BlockAlignMismatch { declared: 2, expected: 4 },
+            Diagnostic::ByteRateMismatch { declared: 44100, expected: 88200 },
+        ];
+        assert_eq!(expected, wave_file.validate());
+    }
+
+    #[test]
+    fn test_duration_and_frame_conversions() {
+        let wave_file = WaveFile {
+            channels: vec![vec![Sample::BitDepth8(0x80); 12000]; 2],
+            sample_rate: 8000,
+            ..Default::default()
+        };
+
+        assert_eq!(12000, wave_file.num_frames());
+        assert_eq!(Duration::from_millis(1500), wave_file.duration());
+        assert_eq!(Duration::from_micros(125), wave_file.frames_to_duration(1));
+        assert_eq!(4000, wave_file.duration_to_frames(Duration::from_millis(500)));
+        assert_eq!(Duration::from_secs(0), WaveFile::default().duration());
+    }
+
+    #[test]
+    fn test_samples_to_f32() {
+        assert_eq!(-1.0, Sample::BitDepth8(0).to_f32());
+        assert_eq!(0.0, Sample::BitDepth8(128).to_f32());
+        assert_eq!(-1.0, Sample::BitDepth16(i16::MIN).to_f32());
+        assert_eq!(0.5, Sample::BitDepth16(16_384).to_f32());
+        assert_eq!(-1.0, Sample::BitDepth24(-8_388_608).to_f32());
+        assert_eq!(1.0, Sample::BitDepth32(i32::MAX).to_f32());
+        assert_eq!(0.25, Sample::Float64(0.25).to_f32());
+    }
+
+    #[test]
+    fn test_channels_f32() {
+        let wave_file = WaveFile {
+            channels: vec![vec![Sample::BitDepth16(0), Sample::BitDepth16(-16_384)], vec![Sample::BitDepth16(8_192), Sample::BitDepth16(32_767)]],
+            ..Default::default()
+        };
+
+        assert_eq!(vec![vec![0.0, -0.5], vec![0.25, 32_767.0 / 32_768.0]], wave_file.channels_f32());
+        assert_eq!(wave_file.channels_f32(), wave_file.samples_as::<f32>());
+    }
+
+    #[test]
+    fn test_samples_as() {
+        let wave_file = WaveFile {
+            channels: vec![vec![Sample::BitDepth8(0), Sample::BitDepth8(0x80), Sample::BitDepth8(0xff)]],
+            ..Default::default()
+        };
+
+        assert_eq!(vec![vec![i16::MIN, 0, 0x7f00]], wave_file.samples_as::<i16>());
+    }
+}
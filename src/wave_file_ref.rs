@@ -0,0 +1,340 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::codec::pcm::SampleEncoding;
+use crate::convert::FromSample;
+use crate::error::WaveError;
+use crate::simd;
+use crate::wave_file::WaveFile;
+
+// How many frames are converted at a time on their way into the channels
+const FRAMES_PER_BATCH: usize = 4096;
+// The fewest frames worth handing to a thread of their own. Starting a thread costs more
+// than converting a handful of frames.
+const MIN_FRAMES_PER_SEGMENT: usize = 16 * 1024;
+
+// What `WaveFileParser::parse_slice` hands back: a file that borrows its samples from the bytes
+// it was parsed out of instead of copying them. For big files that's the difference between
+// holding the file in memory once and holding it twice (or more, once decoded).
+pub struct WaveFileRef<'a> {
+    // Everything but the samples. The channels are there, but they are empty.
+    pub wave_file: WaveFile,
+    // The raw bytes of each data chunk, as they are in the file
+    pub data: Vec<&'a [u8]>,
+    // Whether the samples are big-endian, which they are in RIFX files
+    pub big_endian: bool,
+}
+
+impl<'a> WaveFileRef<'a> {
+    // How many bytes of samples there are, over all the data chunks
+    pub fn data_size(&self) -> usize {
+        self.data.iter().map(|d| d.len()).sum()
+    }
+
+    // The raw bytes of each frame, `block_align` bytes at a time, over all the data chunks.
+    // A frame cut short at the end of a data chunk is left out.
+    pub fn raw_frames(&self) -> impl Iterator<Item = &'a [u8]> + '_ {
+        let block_align = (self.wave_file.block_align as usize).max(1);
+
+        self.data.iter().flat_map(move |d| d.chunks_exact(block_align))
+    }
+
+    // The number of frames (one sample per channel) in the file
+    pub fn num_frames(&self) -> u64 {
+        match self.wave_file.block_align {
+            0 => 0,
+            block_align => self.data.iter().map(|d| (d.len() / block_align as usize) as u64).sum(),
+        }
+    }
+
+    // Every channel decoded and scaled to between -1.0 and 1.0, the same as `WaveFile::channels_f32`
+    // would give after a full parse. Goes straight from the bytes to the channels without making
+    // a `Sample` out of each one for 16 bit PCM, which is also what the `simd` feature speeds up.
+    pub fn channels_f32(&self) -> Result<Vec<Vec<f32>>, WaveError> {
+        self.convert_f32(1)
+    }
+
+    // Every channel decoded and converted to i16, the same as `WaveFile::samples_as::<i16>`
+    // would give after a full parse
+    pub fn channels_i16(&self) -> Result<Vec<Vec<i16>>, WaveError> {
+        self.convert_i16(1)
+    }
+
+    // Same as `channels_f32`, but the data is split up over as many as `threads` threads which
+    // convert at the same time. Only worth it for big files on machines with the cores for it.
+    // There are never more threads than the machine can run at once, and each one gets at least
+    // `MIN_FRAMES_PER_SEGMENT` frames, so small files are converted on the calling thread.
+    #[cfg(feature = "parallel")]
+    pub fn channels_f32_parallel(&self, threads: usize) -> Result<Vec<Vec<f32>>, WaveError> {
+        self.convert_f32(thread_count(threads))
+    }
+
+    // Same as `channels_i16`, with the data split over threads like `channels_f32_parallel`
+    #[cfg(feature = "parallel")]
+    pub fn channels_i16_parallel(&self, threads: usize) -> Result<Vec<Vec<i16>>, WaveError> {
+        self.convert_i16(thread_count(threads))
+    }
+
+    fn convert_f32(&self, threads: usize) -> Result<Vec<Vec<f32>>, WaveError> {
+        let encoding = SampleEncoding::new(self.wave_file.wave_format, self.wave_file.bits_per_sample)?;
+        let big_endian = self.big_endian;
+
+        match encoding {
+            SampleEncoding::Signed16 if !big_endian => self.convert_channels(encoding, threads, simd::i16_to_f32),
+            SampleEncoding::Float32 if !big_endian => self.convert_channels(encoding, threads, |bytes, out| {
+                for (sample, out) in bytes.chunks_exact(4).zip(out.iter_mut()) {
+                    *out = f32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]);
+                }
+            }),
+            _ => self.convert_channels(encoding, threads, |bytes, out: &mut [f32]| {
+                for (sample, out) in bytes.chunks_exact(encoding.size()).zip(out.iter_mut()) {
+                    *out = encoding.decode(sample, big_endian).to_f32();
+                }
+            }),
+        }
+    }
+
+    fn convert_i16(&self, threads: usize) -> Result<Vec<Vec<i16>>, WaveError> {
+        let encoding = SampleEncoding::new(self.wave_file.wave_format, self.wave_file.bits_per_sample)?;
+        let big_endian = self.big_endian;
+
+        match encoding {
+            SampleEncoding::Signed16 if !big_endian => self.convert_channels(encoding, threads, |bytes, out| {
+                for (sample, out) in bytes.chunks_exact(2).zip(out.iter_mut()) {
+                    *out = i16::from_le_bytes([sample[0], sample[1]]);
+                }
+            }),
+            _ => self.convert_channels(encoding, threads, |bytes, out: &mut [i16]| {
+                for (sample, out) in bytes.chunks_exact(encoding.size()).zip(out.iter_mut()) {
+                    *out = i16::from_sample(encoding.decode(sample, big_endian));
+                }
+            }),
+        }
+    }
+
+    // Converts the data chunks with `convert`, which turns a run of interleaved samples into the
+    // same number of values, and splits those up into the channels. With more than one thread,
+    // each data chunk is cut into up to `threads` segments of whole frames. A frame cut short at
+    // the end of a data chunk is left out.
+    fn convert_channels<T, F>(&self, encoding: SampleEncoding, threads: usize, convert: F) -> Result<Vec<Vec<T>>, WaveError>
+    where
+        T: Copy + Default + Send,
+        F: Fn(&[u8], &mut [T]) + Sync,
+    {
+        let num_channels = self.wave_file.channels.len();
+        if num_channels == 0 {
+            return Err(WaveError::UnsupportedChannelCount(0));
+        }
+
+        // The bytes are all here already, so the frame count is a real one and safe to allocate for
+        let frame_size = num_channels * encoding.size();
+        let num_frames = self.data.iter().map(|d| d.len() / frame_size).sum();
+        let mut channels = vec![Vec::with_capacity(num_frames); num_channels];
+
+        let segments = split_into_segments(&self.data, frame_size, threads);
+        convert_segments(&mut channels, &segments, encoding.size(), threads, &convert);
+
+        Ok(channels)
+    }
+}
+
+// `threads` as many as the machine can actually run at once, and at least 1
+#[cfg(feature = "parallel")]
+fn thread_count(threads: usize) -> usize {
+    let available = std::thread::available_parallelism().map_or(1, |n| n.get());
+
+    threads.clamp(1, available)
+}
+
+// The whole frames of each data chunk, cut into up to `threads` segments of at least
+// `MIN_FRAMES_PER_SEGMENT` frames. With one thread each data chunk is one segment.
+fn split_into_segments<'a>(data: &[&'a [u8]], frame_size: usize, threads: usize) -> Vec<&'a [u8]> {
+    let mut segments = Vec::new();
+    for data in data.iter() {
+        let whole_frames = &data[..data.len() - data.len() % frame_size];
+        if whole_frames.is_empty() {
+            continue;
+        }
+        let frames_per_segment = (whole_frames.len() / frame_size).div_ceil(threads).max(MIN_FRAMES_PER_SEGMENT);
+
+        segments.extend(whole_frames.chunks(frames_per_segment * frame_size));
+    }
+
+    segments
+}
+
+// Converts each segment in turn onto the ends of the channels
+#[cfg(not(feature = "parallel"))]
+fn convert_segments<T, F>(channels: &mut [Vec<T>], segments: &[&[u8]], sample_size: usize, _threads: usize, convert: &F)
+where
+    T: Copy + Default,
+    F: Fn(&[u8], &mut [T]),
+{
+    for segment in segments {
+        convert_segment(channels, segment, sample_size, convert);
+    }
+}
+
+// Splits the segments into `threads` runs, converts each run on its own thread, then puts the
+// pieces together in order. A run whose thread can't be started is converted right here instead.
+#[cfg(feature = "parallel")]
+fn convert_segments<T, F>(channels: &mut [Vec<T>], segments: &[&[u8]], sample_size: usize, threads: usize, convert: &F)
+where
+    T: Copy + Default + Send,
+    F: Fn(&[u8], &mut [T]) + Sync,
+{
+    // Not worth starting a thread for
+    if threads <= 1 || segments.len() <= 1 {
+        for segment in segments {
+            convert_segment(channels, segment, sample_size, convert);
+        }
+        return;
+    }
+
+    let num_channels = channels.len();
+    let frame_size = num_channels * sample_size;
+    let convert_run = |run: &[&[u8]]| {
+        let frames = run.iter().map(|segment| segment.len() / frame_size).sum();
+        let mut piece = vec![Vec::with_capacity(frames); num_channels];
+        for segment in run {
+            convert_segment(&mut piece, segment, sample_size, convert);
+        }
+        piece
+    };
+
+    let runs: Vec<&[&[u8]]> = segments.chunks(segments.len().div_ceil(threads)).collect();
+    let pieces: Vec<Vec<Vec<T>>> = std::thread::scope(|scope| {
+        let convert_run = &convert_run;
+        let handles: Vec<_> = runs.iter().map(|&run| std::thread::Builder::new().spawn_scoped(scope, move || convert_run(run))).collect();
+
+        handles
+            .into_iter()
+            .zip(runs.iter())
+            .map(|(handle, &run)| match handle {
+                // A panic in `convert` is a bug, so it's passed on rather than turned into an error
+                Ok(handle) => handle.join().unwrap(),
+                Err(_) => convert_run(run),
+            })
+            .collect()
+    });
+
+    for piece in pieces {
+        for (channel, samples) in channels.iter_mut().zip(piece) {
+            channel.extend(samples);
+        }
+    }
+}
+
+// Converts a batch at a time, to keep the buffer of interleaved values small
+fn convert_segment<T, F>(channels: &mut [Vec<T>], segment: &[u8], sample_size: usize, convert: &F)
+where
+    T: Copy + Default,
+    F: Fn(&[u8], &mut [T]),
+{
+    let num_channels = channels.len();
+    let mut interleaved = vec![T::default(); FRAMES_PER_BATCH * num_channels];
+
+    for batch in segment.chunks(FRAMES_PER_BATCH * num_channels * sample_size) {
+        let interleaved = &mut interleaved[..batch.len() / sample_size];
+        convert(batch, interleaved);
+
+        for frame in interleaved.chunks_exact(num_channels) {
+            for (channel, &sample) in channels.iter_mut().zip(frame) {
+                channel.push(sample);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::{split_into_segments, MIN_FRAMES_PER_SEGMENT};
+    use crate::error::WaveError;
+    use crate::parser::WaveFileParser;
+    use crate::test_utils::wave_bytes;
+    use crate::testing::WaveFixture;
+
+    #[test]
+    fn test_parse_slice_borrows_the_data() {
+        let fixture = WaveFixture::new(2, 16, 8000).num_frames(3);
+        let bytes = fixture.to_bytes();
+        let wave_file = WaveFileParser::parse_slice(&bytes).unwrap();
+
+        assert_eq!(12, wave_file.data_size());
+        assert_eq!(3, wave_file.num_frames());
+        assert_eq!(&bytes[44..56], wave_file.data[0]);
+        assert!(wave_file.wave_file.channels.iter().all(|c| c.is_empty()));
+
+        let frames: Vec<&[u8]> = wave_file.raw_frames().collect();
+        assert_eq!(3, frames.len());
+        assert_eq!(fixture.sample(2, 1) as i16, i16::from_le_bytes([frames[2][2], frames[2][3]]));
+    }
+
+    #[test]
+    fn test_converting_channels_matches_a_full_parse() {
+        // Long enough to take a few batches, and for each fast path
+        for &bits_per_sample in &[16, 24, 32, 8] {
+            let bytes = WaveFixture::new(3, bits_per_sample, 8000).num_frames(10_000).to_bytes();
+            let wave_file = WaveFileParser::parse_slice(&bytes).unwrap();
+            let parsed = WaveFileParser::parse(bytes.clone()).unwrap();
+
+            assert_eq!(parsed.channels_f32(), wave_file.channels_f32().unwrap());
+            assert_eq!(parsed.samples_as::<i16>(), wave_file.channels_i16().unwrap());
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_converting_channels_in_parallel() {
+        let bytes = WaveFixture::new(2, 16, 8000).num_frames(MIN_FRAMES_PER_SEGMENT as u64 * 3 + 1).to_bytes();
+        let wave_file = WaveFileParser::parse_slice(&bytes).unwrap();
+
+        for threads in (0..6).chain(Some(1_000_000)) {
+            assert_eq!(wave_file.channels_f32().unwrap(), wave_file.channels_f32_parallel(threads).unwrap());
+            assert_eq!(wave_file.channels_i16().unwrap(), wave_file.channels_i16_parallel(threads).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_splitting_into_segments() {
+        let data = vec![0u8; 4 * (MIN_FRAMES_PER_SEGMENT * 3 + 1) + 2];
+        let data = [&data[..], &data[..4]];
+
+        // One thread: the whole frames of each data chunk, as they are
+        let segments = split_into_segments(&data, 4, 1);
+        assert_eq!(vec![4 * (MIN_FRAMES_PER_SEGMENT * 3 + 1), 4], segments.iter().map(|s| s.len()).collect::<Vec<usize>>());
+
+        // However many threads are asked for, the segments don't get smaller than the minimum
+        let segments = split_into_segments(&data, 4, 1_000_000);
+        assert_eq!(5, segments.len());
+        assert!(segments[..3].iter().all(|s| s.len() == 4 * MIN_FRAMES_PER_SEGMENT));
+    }
+
+    #[test]
+    fn test_converting_float_channels() {
+        let samples: Vec<u8> = [0.5f32, -0.25, 1.0, 0.0].iter().flat_map(|s| s.to_le_bytes().to_vec()).collect();
+        let bytes = wave_bytes(3, 2, 32, &samples);
+        let wave_file = WaveFileParser::parse_slice(&bytes).unwrap();
+
+        assert_eq!(vec![vec![0.5, 1.0], vec![-0.25, 0.0]], wave_file.channels_f32().unwrap());
+        assert_eq!(vec![vec![16_384, 32_767], vec![-8_192, 0]], wave_file.channels_i16().unwrap());
+    }
+
+    #[test]
+    fn test_converting_channels_of_compressed_file_returns_error() {
+        let mut bytes = WaveFixture::new(1, 16, 8000).num_frames(4).to_bytes();
+        // IMA ADPCM
+        bytes[20] = 0x11;
+
+        let wave_file = WaveFileParser::parse_slice(&bytes).unwrap();
+
+        assert_eq!(Err(WaveError::UnsupportedFormat(0x11)), wave_file.channels_f32().map_err(|e| e.kind().clone()));
+    }
+
+    #[test]
+    fn test_parse_slice_of_truncated_file_returns_error() {
+        let bytes = WaveFixture::new(1, 16, 8000).num_frames(10).to_bytes();
+
+        assert!(WaveFileParser::parse_slice(&bytes[..30]).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::wave_file::WaveFile;
+
+// What `WaveFileParser::parse_header` hands back: everything about a file except the samples.
+// Good for listing a big library of files, where decoding every one of them would take forever.
+pub struct WaveInfo {
+    // Everything but the samples. The channels are there, but they are empty.
+    pub wave_file: WaveFile,
+    // Where each data chunk's samples start in the file, and how many bytes they take up
+    pub data_chunks: Vec<(u64, u64)>,
+}
+
+impl WaveInfo {
+    // How many bytes of samples there are, over all the data chunks
+    pub fn data_size(&self) -> u64 {
+        self.data_chunks.iter().map(|&(_, size)| size).sum()
+    }
+
+    // The number of frames (one sample per channel) in the file
+    pub fn num_frames(&self) -> u64 {
+        self.data_chunks.iter().map(|&(_, size)| self.frames_in(size)).sum()
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.wave_file.frames_to_duration(self.num_frames())
+    }
+
+    pub(crate) fn frames_in(&self, chunk_size: u64) -> u64 {
+        match self.wave_file.block_align {
+            0 => 0,
+            block_align => chunk_size / block_align as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use core::time::Duration;
+    use super::WaveInfo;
+    use crate::wave_file::WaveFile;
+
+    #[test]
+    fn test_frames_and_duration_over_all_data_chunks() {
+        let info = WaveInfo {
+            wave_file: WaveFile {
+                sample_rate: 8000,
+                block_align: 4,
+                ..Default::default()
+            },
+            data_chunks: vec![(44, 16000), (16052, 18000)],
+        };
+
+        assert_eq!(34000, info.data_size());
+        assert_eq!(8500, info.num_frames());
+        assert_eq!(Duration::from_millis(1062) + Duration::from_micros(500), info.duration());
+    }
+}
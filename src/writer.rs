@@ -0,0 +1,463 @@
+use core::convert::TryFrom;
+use std::io::Write;
+
+use crate::associated_data;
+use crate::bext;
+use crate::cue;
+use crate::error::WaveError;
+use crate::fmt_chunk::{bytes_per_sample, FmtChunk};
+use crate::options::{WriterOptions, MAX_DATA_ALIGNMENT};
+use crate::wave_file::{Sample, WaveFile, WaveFormatCategory};
+
+// The other direction: turn a WaveFile back into the bytes of a '.wav' file.
+// Only the fields that describe the samples are used, along with the broadcast extension (a bext
+// chunk), the metadata (a LIST INFO chunk) and the cue points and what's attached to them
+// (cue and LIST adtl chunks). `WriterOptions` says where those go.
+// The block align and byte rate are worked out from the channels and bit depth so they are
+// always consistent. Files with more than two channels, more than 16 bits per sample, a channel
+// mask or fewer valid bits than bits per sample get the extensible format.
+pub struct WaveFileWriter {}
+
+impl WaveFileWriter {
+    pub fn to_bytes(wave_file: &WaveFile) -> Result<Vec<u8>, WaveError> {
+        WaveFileWriter::to_bytes_with_options(wave_file, &WriterOptions::new())
+    }
+
+    pub fn to_bytes_with_options(wave_file: &WaveFile, options: &WriterOptions) -> Result<Vec<u8>, WaveError> {
+        let mut bytes = vec![];
+        WaveFileWriter::write_with_options(wave_file, &mut bytes, options)?;
+
+        Ok(bytes)
+    }
+
+    pub fn write<W: Write>(wave_file: &WaveFile, writer: W) -> Result<(), WaveError> {
+        WaveFileWriter::write_with_options(wave_file, writer, &WriterOptions::new())
+    }
+
+    // A WaveFile from `WaveFileParser::parse_lossless` is written the way it was laid out,
+    // and the options don't apply
+    pub fn write_with_options<W: Write>(wave_file: &WaveFile, mut writer: W, options: &WriterOptions) -> Result<(), WaveError> {
+        if let Some(layout) = wave_file.layout.as_ref() {
+            layout.write(wave_file, &mut writer)?;
+            return writer.flush().map_err(|e| WaveError::Io(e.kind()));
+        }
+
+        let num_channels = wave_file.channels.len();
+        let num_frames = wave_file.channels.first().map_or(0, |c| c.len());
+
+        if num_channels > u16::MAX as usize {
+            return Err(WaveError::UnsupportedChannelCount(num_channels as u16));
+        }
+
+        // Every channel needs a sample for every frame
+        if wave_file.channels.iter().any(|c| c.len() != num_frames) {
+            return Err(WaveError::ChannelLengthMismatch);
+        }
+
+        let mut fmt = FmtChunk::new(wave_file.wave_format, num_channels as u16, wave_file.sample_rate, wave_file.bits_per_sample)?;
+
+        // A valid bit count of 0 or more than there are bits means it wasn't set
+        let bits_per_sample = wave_file.bits_per_sample;
+        let valid_bits_per_sample = match wave_file.valid_bits_per_sample {
+            0 => bits_per_sample,
+            valid_bits => valid_bits.min(bits_per_sample),
+        };
+        if num_channels > 2 || bits_per_sample > 16 || wave_file.channel_mask != 0 || valid_bits_per_sample != bits_per_sample {
+            fmt = fmt.extensible(valid_bits_per_sample, wave_file.channel_mask);
+        }
+        let fmt_bytes = fmt.to_bytes();
+
+        let bext_bytes = match wave_file.broadcast_extension.as_ref() {
+            Some(bext) => bext::bext_chunk_bytes(bext)?,
+            None => vec![],
+        };
+
+        let mut metadata_bytes = vec![];
+        if !wave_file.metadata.is_empty() {
+            metadata_bytes.extend_from_slice(&wave_file.metadata.to_bytes());
+        }
+        if !wave_file.cue_points.is_empty() {
+            metadata_bytes.extend_from_slice(&cue::cue_chunk_bytes(&wave_file.cue_points));
+        }
+        let has_adtl = !(wave_file.labels.is_empty() && wave_file.notes.is_empty() && wave_file.regions.is_empty() && wave_file.embedded_files.is_empty());
+        if has_adtl {
+            metadata_bytes.extend_from_slice(&associated_data::adtl_list_bytes(wave_file));
+        }
+
+        // Everything up to the data chunk header, then everything after the data chunk
+        let mut bytes = vec![];
+        let mut trailer = vec![];
+
+        // RIFF header. The size is filled in once we know it.
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&[0; 4]);
+        bytes.extend_from_slice(b"WAVE");
+
+        // bext and fmt chunks
+        if options.bext_after_fmt {
+            bytes.extend_from_slice(&fmt_bytes);
+            bytes.extend_from_slice(&bext_bytes);
+        } else {
+            bytes.extend_from_slice(&bext_bytes);
+            bytes.extend_from_slice(&fmt_bytes);
+        }
+
+        // LIST INFO, cue and LIST adtl chunks
+        if options.metadata_after_data {
+            trailer.extend_from_slice(&metadata_bytes);
+        } else {
+            bytes.extend_from_slice(&metadata_bytes);
+        }
+
+        // JUNK chunk, to line the samples up
+        if let Some(alignment) = options.data_alignment {
+            if alignment > MAX_DATA_ALIGNMENT {
+                return Err(WaveError::LimitExceeded { name: "bytes of data alignment", limit: MAX_DATA_ALIGNMENT });
+            }
+
+            let padding = alignment_padding(bytes.len() as u64 + 8, alignment);
+            if padding > 0 {
+                let junk_size = u32::try_from(padding - 8).map_err(|_| WaveError::InvalidChunkSize(u32::MAX))?;
+                bytes.extend_from_slice(b"JUNK");
+                bytes.extend_from_slice(&junk_size.to_le_bytes());
+                bytes.resize(bytes.len() + padding as usize - 8, 0);
+            }
+        }
+
+        let data_size = num_frames as u64 * fmt.block_align as u64;
+        let pad = data_size % 2;
+        let riff_size = bytes.len() as u64 - 8 + (8 + data_size + pad) + trailer.len() as u64;
+        if riff_size > u32::MAX as u64 {
+            return Err(WaveError::InvalidChunkSize(u32::MAX));
+        }
+        bytes[4..8].copy_from_slice(&(riff_size as u32).to_le_bytes());
+
+        // data chunk header
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&(data_size as u32).to_le_bytes());
+        write_all(&mut writer, &bytes)?;
+
+        // The samples are interleaved, one frame at a time
+        let mut frame = vec![];
+        for i in 0..num_frames {
+            frame.clear();
+            for channel in wave_file.channels.iter() {
+                write_sample(&mut frame, &channel[i], wave_file.wave_format, wave_file.bits_per_sample)?;
+            }
+            write_all(&mut writer, &frame)?;
+        }
+
+        // Odd sized chunks are followed by a pad byte
+        if pad == 1 {
+            write_all(&mut writer, &[0])?;
+        }
+
+        write_all(&mut writer, &trailer)?;
+
+        writer.flush().map_err(|e| WaveError::Io(e.kind()))
+    }
+}
+
+// How many bytes of JUNK chunk to put at `offset` so what comes after starts at a multiple of
+// `alignment`. 0 when it already does. A JUNK chunk needs room for its header and has to be an
+// even size, so sometimes it takes a few multiples to get there.
+fn alignment_padding(offset: u64, alignment: u64) -> u64 {
+    if alignment <= 1 {
+        return 0;
+    }
+
+    let mut padding = (alignment - offset % alignment) % alignment;
+    while padding != 0 && (padding < 8 || padding % 2 == 1) {
+        padding += alignment;
+    }
+
+    padding
+}
+
+// Appends the little-endian bytes of `sample`.
+// The sample has to be of the kind the parser would produce for `wave_format` and `bits_per_sample`.
+pub(crate) fn write_sample(bytes: &mut Vec<u8>, sample: &Sample, wave_format: WaveFormatCategory, bits_per_sample: u16) -> Result<(), WaveError> {
+    let is_float = wave_format == WaveFormatCategory::WAVE_FORMAT_IEEE_FLOAT;
+
+    match (sample, bytes_per_sample(bits_per_sample)?, is_float) {
+        (Sample::BitDepth8(s), 1, false) => bytes.push(*s),
+        (Sample::BitDepth16(s), 2, false) => bytes.extend_from_slice(&s.to_le_bytes()),
+        // The low three bytes hold the sample, the top byte is just the sign extension
+        (Sample::BitDepth24(s), 3, false) => bytes.extend_from_slice(&s.to_le_bytes()[..3]),
+        (Sample::BitDepth32(s), 4, false) => bytes.extend_from_slice(&s.to_le_bytes()),
+        (Sample::Float32(s), 4, true) => bytes.extend_from_slice(&s.to_le_bytes()),
+        (Sample::Float64(s), 8, true) => bytes.extend_from_slice(&s.to_le_bytes()),
+        _ => return Err(WaveError::SampleFormatMismatch(bits_per_sample)),
+    }
+
+    Ok(())
+}
+
+pub(crate) fn write_all<W: Write>(writer: &mut W, bytes: &[u8]) -> Result<(), WaveError> {
+    writer.write_all(bytes).map_err(|e| WaveError::Io(e.kind()))
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::{alignment_padding, WaveFileWriter};
+    use crate::associated_data::{Label, Note, Region};
+    use crate::bext::BroadcastExtension;
+    use crate::options::{WriterOptions, MAX_DATA_ALIGNMENT};
+    use crate::cue::CuePoint;
+    use crate::error::WaveError;
+    use crate::parser::WaveFileParser;
+    use crate::wave_file::{Sample, WaveFile, WaveFormatCategory};
+
+    #[test]
+    fn test_writing_and_parsing_round_trip() {
+        let wave_file = WaveFile {
+            channels: vec![
+                vec![Sample::BitDepth16(1), Sample::BitDepth16(-2)],
+                vec![Sample::BitDepth16(3), Sample::BitDepth16(-4)],
+            ],
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            ..Default::default()
+        };
+
+        let parsed = WaveFileParser::parse(WaveFileWriter::to_bytes(&wave_file).unwrap()).unwrap();
+
+        assert_eq!(wave_file.channels, parsed.channels);
+        assert_eq!(44100, parsed.sample_rate);
+        assert_eq!(4, parsed.block_align);
+        assert_eq!(44100 * 4, parsed.byte_rate);
+        assert_eq!(16, parsed.bits_per_sample);
+    }
+
+    #[test]
+    fn test_writing_24bit_samples_round_trip() {
+        let wave_file = WaveFile {
+            channels: vec![vec![Sample::BitDepth24(-8_388_608), Sample::BitDepth24(8_388_607), Sample::BitDepth24(-2)]],
+            sample_rate: 48000,
+            bits_per_sample: 24,
+            ..Default::default()
+        };
+
+        let parsed = WaveFileParser::parse(WaveFileWriter::to_bytes(&wave_file).unwrap()).unwrap();
+
+        assert_eq!(3, parsed.block_align);
+        assert_eq!(wave_file.channels, parsed.channels);
+    }
+
+    #[test]
+    fn test_writing_multichannel_uses_the_extensible_format() {
+        let wave_file = WaveFile {
+            channels: (0..6).map(|c| vec![Sample::BitDepth24(c * 1000 - 2000); 4]).collect(),
+            sample_rate: 48000,
+            bits_per_sample: 24,
+            valid_bits_per_sample: 20,
+            channel_mask: 0x3f,
+            ..Default::default()
+        };
+
+        let bytes = WaveFileWriter::to_bytes(&wave_file).unwrap();
+        let parsed = WaveFileParser::parse(bytes.clone()).unwrap();
+
+        assert_eq!(&0xfffeu16.to_le_bytes(), &bytes[20..22]);
+        assert_eq!(WaveFormatCategory::WAVE_FORMAT_PCM, parsed.wave_format);
+        assert_eq!(1, parsed.format_tag);
+        assert_eq!(20, parsed.valid_bits_per_sample);
+        assert_eq!(0x3f, parsed.channel_mask);
+        assert_eq!(wave_file.channels, parsed.channels);
+    }
+
+    #[test]
+    fn test_writing_stereo_16bit_stays_plain_pcm() {
+        let wave_file = WaveFile {
+            channels: vec![vec![Sample::BitDepth16(1)], vec![Sample::BitDepth16(2)]],
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            valid_bits_per_sample: 16,
+            ..Default::default()
+        };
+
+        let bytes = WaveFileWriter::to_bytes(&wave_file).unwrap();
+
+        assert_eq!(&1u16.to_le_bytes(), &bytes[20..22]);
+        assert_eq!(&16u32.to_le_bytes(), &bytes[16..20]);
+    }
+
+    #[test]
+    fn test_writing_float_samples_round_trip() {
+        let wave_file = WaveFile {
+            channels: vec![vec![Sample::Float32(0.5), Sample::Float32(-0.25)]],
+            wave_format: WaveFormatCategory::WAVE_FORMAT_IEEE_FLOAT,
+            sample_rate: 48000,
+            bits_per_sample: 32,
+            ..Default::default()
+        };
+
+        let parsed = WaveFileParser::parse(WaveFileWriter::to_bytes(&wave_file).unwrap()).unwrap();
+
+        assert_eq!(WaveFormatCategory::WAVE_FORMAT_IEEE_FLOAT, parsed.wave_format);
+        assert_eq!(wave_file.channels, parsed.channels);
+    }
+
+    #[test]
+    fn test_writing_odd_sized_data_adds_pad_byte() {
+        let wave_file = WaveFile {
+            channels: vec![vec![Sample::BitDepth8(0x80); 3]],
+            sample_rate: 8000,
+            bits_per_sample: 8,
+            ..Default::default()
+        };
+
+        let bytes = WaveFileWriter::to_bytes(&wave_file).unwrap();
+
+        // 12 byte RIFF header, 24 byte fmt chunk, 8 byte data header, 3 samples and a pad byte
+        assert_eq!(48, bytes.len());
+        assert_eq!(&40u32.to_le_bytes(), &bytes[4..8]);
+        assert_eq!(&3u32.to_le_bytes(), &bytes[40..44]);
+        assert_eq!(0, bytes[47]);
+    }
+
+    #[test]
+    fn test_writing_metadata_round_trip() {
+        let mut wave_file = WaveFile {
+            channels: vec![vec![Sample::BitDepth16(7); 5]],
+            sample_rate: 22050,
+            bits_per_sample: 16,
+            ..Default::default()
+        };
+        wave_file.metadata.set_title("Take 3".to_string());
+        wave_file.metadata.set_artist("The Band".to_string());
+        wave_file.metadata.set_date("2024-01-01".to_string());
+        wave_file.metadata.set_comment("Odd".to_string());
+
+        let bytes = WaveFileWriter::to_bytes(&wave_file).unwrap();
+        let parsed = WaveFileParser::parse(bytes.clone()).unwrap();
+
+        assert_eq!(wave_file.metadata, parsed.metadata);
+        assert_eq!(wave_file.channels, parsed.channels);
+        assert_eq!(&(bytes.len() as u32 - 8).to_le_bytes(), &bytes[4..8]);
+        assert!(parsed.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_writing_markers_round_trip() {
+        let wave_file = WaveFile {
+            channels: vec![vec![Sample::BitDepth8(0x80); 100]],
+            sample_rate: 8000,
+            bits_per_sample: 8,
+            cue_points: vec![
+                CuePoint { id: 1, position: 10, chunk_id: *b"data", chunk_start: 0, block_start: 0, sample_offset: 10 },
+                CuePoint { id: 2, position: 60, chunk_id: *b"data", chunk_start: 0, block_start: 0, sample_offset: 60 },
+            ],
+            labels: vec![Label { cue_id: 1, text: "Intro".to_string() }, Label { cue_id: 2, text: "Outro".to_string() }],
+            notes: vec![Note { cue_id: 2, text: "Too loud".to_string() }],
+            regions: vec![Region { cue_id: 1, sample_length: 40, purpose: *b"rgn ", text: "Verse".to_string() }],
+            ..Default::default()
+        };
+
+        let parsed = WaveFileParser::parse(WaveFileWriter::to_bytes(&wave_file).unwrap()).unwrap();
+
+        assert_eq!(wave_file.cue_points, parsed.cue_points);
+        assert_eq!(wave_file.labels, parsed.labels);
+        assert_eq!(wave_file.notes, parsed.notes);
+        assert_eq!(wave_file.regions, parsed.regions);
+        assert_eq!(Some("Outro".to_string()), parsed.markers()[1].label);
+        assert_eq!(wave_file.channels, parsed.channels);
+    }
+
+    #[test]
+    fn test_writing_bext_round_trip() {
+        let wave_file = WaveFile {
+            channels: vec![vec![Sample::BitDepth24(5); 10], vec![Sample::BitDepth24(-5); 10]],
+            sample_rate: 48000,
+            bits_per_sample: 24,
+            broadcast_extension: Some(BroadcastExtension {
+                description: "Scene 4, take 2".to_string(),
+                originator: "Field recorder".to_string(),
+                origination_date: "2024-05-06".to_string(),
+                origination_time: "10:00:00".to_string(),
+                time_reference: 48000 * 3600 * 10,
+                coding_history: "A=PCM,F=48000,W=24,M=stereo\r\n".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let bytes = WaveFileWriter::to_bytes(&wave_file).unwrap();
+        let parsed = WaveFileParser::parse(bytes.clone()).unwrap();
+
+        assert_eq!(b"bext", &bytes[12..16]);
+        assert_eq!(wave_file.broadcast_extension, parsed.broadcast_extension);
+        assert_eq!(wave_file.channels, parsed.channels);
+    }
+
+    #[test]
+    fn test_writing_with_chunk_layout_options() {
+        let mut wave_file = WaveFile {
+            channels: vec![vec![Sample::BitDepth16(3); 7], vec![Sample::BitDepth16(-3); 7]],
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            broadcast_extension: Some(BroadcastExtension::default()),
+            ..Default::default()
+        };
+        wave_file.metadata.set_title("Aligned".to_string());
+
+        let options = WriterOptions::new().bext_after_fmt(true).metadata_after_data(true).align_data(512);
+        let bytes = WaveFileWriter::to_bytes_with_options(&wave_file, &options).unwrap();
+        let parsed = WaveFileParser::parse(bytes.clone()).unwrap();
+
+        assert_eq!(b"fmt ", &bytes[12..16]);
+        assert_eq!(b"bext", &bytes[36..40]);
+        assert_eq!(b"JUNK", &bytes[646..650]);
+        // The header runs past 512, so the samples start at 1024
+        assert_eq!(b"data", &bytes[1016..1020]);
+        assert_eq!(b"LIST", &bytes[1024 + 28..1024 + 32]);
+        assert_eq!(&(bytes.len() as u32 - 8).to_le_bytes(), &bytes[4..8]);
+        assert_eq!(wave_file.channels, parsed.channels);
+        assert_eq!(Some("Aligned"), parsed.metadata.title());
+    }
+
+    #[test]
+    fn test_alignment_padding() {
+        assert_eq!(0, alignment_padding(4096, 4096));
+        assert_eq!(4096 - 44, alignment_padding(44, 4096));
+        // 4 bytes is too small for a JUNK chunk
+        assert_eq!(20, alignment_padding(12, 16));
+        // An odd alignment needs an even JUNK chunk
+        assert_eq!(8, alignment_padding(42, 5));
+        assert_eq!(0, alignment_padding(43, 1));
+    }
+
+    #[test]
+    fn test_writing_with_huge_alignment_returns_error() {
+        let wave_file = WaveFile {
+            channels: vec![vec![Sample::BitDepth16(3); 7]],
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            ..Default::default()
+        };
+
+        let options = WriterOptions::new().align_data(MAX_DATA_ALIGNMENT);
+        let bytes = WaveFileWriter::to_bytes_with_options(&wave_file, &options).unwrap();
+        assert_eq!(b"data", &bytes[MAX_DATA_ALIGNMENT as usize - 8..MAX_DATA_ALIGNMENT as usize - 4]);
+
+        for alignment in [MAX_DATA_ALIGNMENT + 1, 1 << 40, u64::MAX].iter() {
+            let options = WriterOptions::new().align_data(*alignment);
+            assert_eq!(
+                Err(WaveError::LimitExceeded { name: "bytes of data alignment", limit: MAX_DATA_ALIGNMENT }),
+                WaveFileWriter::to_bytes_with_options(&wave_file, &options)
+            );
+        }
+    }
+
+    #[test]
+    fn test_writing_mismatched_channels_returns_error() {
+        let wave_file = WaveFile {
+            channels: vec![vec![Sample::BitDepth8(0x80); 3], vec![Sample::BitDepth8(0x80); 2]],
+            sample_rate: 8000,
+            bits_per_sample: 8,
+            ..Default::default()
+        };
+
+        assert_eq!(Err(WaveError::ChannelLengthMismatch), WaveFileWriter::to_bytes(&wave_file));
+    }
+}